@@ -0,0 +1,205 @@
+//! Actix-web extractor module
+//!
+//! This module provides actix-web extractors that read the request convention described in the
+//! `request` module, validate it against a `FilterRequestConfig` and hand the handler a
+//! `ValidatedFilters`.
+//!
+//! The config is looked up with `HttpRequest::app_data`, so it can be registered per app, scope
+//! or resource with `.app_data(config)`. `FilterRequestError` implements `ResponseError`, so a
+//! rejected request becomes a JSON response of the form
+//! `{"error": {"code": "unknown_column", "message": "...", "field": "sort"}}` with status 400,
+//! or 500 if no config was registered. Handlers return errors from building or running the
+//! query as `FilterRequestError::internal()`, a 500 without the details of the error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use actix_web::{web, App};
+//! use pg_filters::actix_extract::FilterQuery;
+//! use pg_filters::request::{FilterRequestConfig, FilterRequestError};
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! async fn list_people(FilterQuery(filters): FilterQuery) -> Result<String, FilterRequestError> {
+//!     filters
+//!         .to_pg_filters(0)
+//!         .and_then(|filters| filters.sql())
+//!         .map_err(|_| FilterRequestError::internal())
+//! }
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let app = App::new()
+//!     .app_data(FilterRequestConfig::new(columns))
+//!     .route("/people", web::get().to(list_people));
+//! ```
+
+use crate::request::{
+    ErrorBody, FilterErrorCode, FilterQueryParams, FilterRequest, FilterRequestConfig,
+    FilterRequestError, ValidatedFilters,
+};
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+
+/// Extracts validated filters from the query string
+#[derive(Clone)]
+pub struct FilterQuery(pub ValidatedFilters);
+
+/// Extracts validated filters from a JSON request body
+#[derive(Clone)]
+pub struct FilterJson(pub ValidatedFilters);
+
+impl FromRequest for FilterQuery {
+    type Error = FilterRequestError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(config(request).and_then(|config| {
+            let web::Query(params) = web::Query::<FilterQueryParams>::from_query(
+                request.query_string(),
+            )
+            .map_err(|e| FilterRequestError::new(FilterErrorCode::InvalidQuery, e.to_string()))?;
+            config.validate(&params.into_request()?).map(FilterQuery)
+        }))
+    }
+}
+
+impl FromRequest for FilterJson {
+    type Error = FilterRequestError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(request: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = config(request).cloned();
+        let json = web::Json::<FilterRequest>::from_request(request, payload);
+
+        Box::pin(async move {
+            let config = config?;
+            let web::Json(request) = json.await.map_err(|e| {
+                FilterRequestError::new(FilterErrorCode::InvalidBody, e.to_string())
+            })?;
+            config.validate(&request).map(FilterJson)
+        })
+    }
+}
+
+impl ResponseError for FilterRequestError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(ResponseError::status_code(self)).json(ErrorBody { error: self })
+    }
+}
+
+fn config(request: &HttpRequest) -> Result<&FilterRequestConfig, FilterRequestError> {
+    request.app_data::<FilterRequestConfig>().ok_or_else(|| {
+        FilterRequestError::new(
+            FilterErrorCode::MissingConfig,
+            "No FilterRequestConfig registered with app_data",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnDef;
+    use actix_web::body::to_bytes;
+    use actix_web::test::TestRequest;
+    use std::collections::HashMap;
+
+    fn setup_config() -> FilterRequestConfig {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        FilterRequestConfig::new(columns)
+    }
+
+    async fn extract_query(uri: &str) -> Result<FilterQuery, FilterRequestError> {
+        let (request, mut payload) = TestRequest::get()
+            .uri(uri)
+            .app_data(setup_config())
+            .to_http_parts();
+        FilterQuery::from_request(&request, &mut payload).await
+    }
+
+    #[tokio::test]
+    async fn test_filter_query() -> eyre::Result<()> {
+        let FilterQuery(filters) = extract_query(
+            "/people?page=2&per_page=5&sort=-age&filters=%5B%7B%22n%22%3A%22age%22%2C%22f%22%3A%22%3E%22%2C%22v%22%3A%2218%22%7D%5D",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            filters.to_pg_filters(0)?.sql()?,
+            " WHERE age > 18 ORDER BY age DESC LIMIT 5 OFFSET 5"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_query_rejections() {
+        let error = extract_query("/people?per_page=ten").await.err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidQuery);
+
+        let error = extract_query("/people?per_page=1000").await.err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidPerPage);
+
+        let (request, mut payload) = TestRequest::get().uri("/people").to_http_parts();
+        let error = FilterQuery::from_request(&request, &mut payload)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::MissingConfig);
+    }
+
+    #[tokio::test]
+    async fn test_filter_json() -> eyre::Result<()> {
+        let (request, mut payload) = TestRequest::post()
+            .uri("/people/search")
+            .app_data(setup_config())
+            .set_json(serde_json::json!({
+                "sort": "name",
+                "filters": [{"n": "name", "f": "=", "v": "John"}],
+            }))
+            .to_http_parts();
+
+        let FilterJson(filters) = FilterJson::from_request(&request, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(
+            filters.to_pg_filters(0)?.sql()?,
+            " WHERE LOWER(name) = LOWER('John') ORDER BY name ASC LIMIT 20 OFFSET 0"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let error =
+            FilterRequestError::new(FilterErrorCode::UnknownColumn, "Unknown column").field("sort");
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            r#"{"error":{"code":"unknown_column","message":"Unknown column","field":"sort"}}"#
+        );
+
+        let response = FilterRequestError::internal().error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            r#"{"error":{"code":"internal","message":"Internal server error"}}"#
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Axum extractor module
+//!
+//! This module provides axum extractors that read the request convention described in the
+//! `request` module, validate it against a `FilterRequestConfig` and hand the handler a
+//! `ValidatedFilters`.
+//!
+//! The config is looked up in the request extensions, so each route can register its own
+//! column definitions and limits with `Extension`. A rejected request becomes a JSON response
+//! of the form `{"error": {"code": "unknown_column", "message": "...", "field": "sort"}}` with
+//! status 400, or 500 if no config was registered.
+//!
+//! # Example
+//!
+//! ```rust
+//! use axum::routing::get;
+//! use axum::{Extension, Router};
+//! use pg_filters::axum_extract::FilterQuery;
+//! use pg_filters::request::FilterRequestConfig;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! async fn list_people(FilterQuery(filters): FilterQuery) -> String {
+//!     filters.to_pg_filters(0).and_then(|f| f.sql()).unwrap_or_default()
+//! }
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let app: Router = Router::new()
+//!     .route("/people", get(list_people))
+//!     .layer(Extension(FilterRequestConfig::new(columns)));
+//! ```
+
+use crate::request::{
+    ErrorBody, FilterErrorCode, FilterQueryParams, FilterRequest, FilterRequestConfig,
+    FilterRequestError, ValidatedFilters,
+};
+use axum::extract::{FromRequest, FromRequestParts, Query, Request};
+use axum::http::request::Parts;
+use axum::http::{Extensions, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+/// Extracts validated filters from the query string
+#[derive(Clone)]
+pub struct FilterQuery(pub ValidatedFilters);
+
+/// Extracts validated filters from a JSON request body
+#[derive(Clone)]
+pub struct FilterJson(pub ValidatedFilters);
+
+impl<S: Send + Sync> FromRequestParts<S> for FilterQuery {
+    type Rejection = FilterRequestError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let config = config(&parts.extensions)?;
+        let Query(params) = Query::<FilterQueryParams>::try_from_uri(&parts.uri)
+            .map_err(|e| FilterRequestError::new(FilterErrorCode::InvalidQuery, e.body_text()))?;
+
+        config.validate(&params.into_request()?).map(FilterQuery)
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for FilterJson {
+    type Rejection = FilterRequestError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let config = config(request.extensions())?.clone();
+        let Json(request) = Json::<FilterRequest>::from_request(request, state)
+            .await
+            .map_err(|e| FilterRequestError::new(FilterErrorCode::InvalidBody, e.body_text()))?;
+
+        config.validate(&request).map(FilterJson)
+    }
+}
+
+impl IntoResponse for FilterRequestError {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(ErrorBody { error: &self })).into_response()
+    }
+}
+
+fn config(extensions: &Extensions) -> Result<&FilterRequestConfig, FilterRequestError> {
+    extensions.get::<FilterRequestConfig>().ok_or_else(|| {
+        FilterRequestError::new(
+            FilterErrorCode::MissingConfig,
+            "No FilterRequestConfig extension registered for this route",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnDef;
+    use axum::body::{to_bytes, Body};
+    use std::collections::HashMap;
+
+    fn setup_config() -> FilterRequestConfig {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        FilterRequestConfig::new(columns)
+    }
+
+    async fn extract_query(uri: &str) -> Result<FilterQuery, FilterRequestError> {
+        let request = Request::builder()
+            .uri(uri)
+            .extension(setup_config())
+            .body(Body::empty())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+        FilterQuery::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_filter_query() -> eyre::Result<()> {
+        let FilterQuery(filters) = extract_query(
+            "/people?page=2&per_page=5&sort=-age&filters=%5B%7B%22n%22%3A%22age%22%2C%22f%22%3A%22%3E%22%2C%22v%22%3A%2218%22%7D%5D",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            filters.to_pg_filters(0)?.sql()?,
+            " WHERE age > 18 ORDER BY age DESC LIMIT 5 OFFSET 5"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_query_rejections() {
+        let error = extract_query("/people?page=two").await.err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidQuery);
+
+        let error = extract_query("/people?sort=password").await.err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::UnknownColumn);
+
+        let request = Request::builder().uri("/people").body(()).unwrap();
+        let (mut parts, _) = request.into_parts();
+        let error = FilterQuery::from_request_parts(&mut parts, &())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::MissingConfig);
+    }
+
+    #[tokio::test]
+    async fn test_filter_json() -> eyre::Result<()> {
+        let request = Request::builder()
+            .uri("/people/search")
+            .header("content-type", "application/json")
+            .extension(setup_config())
+            .body(Body::from(
+                r#"{"sort": "name", "filters": [{"n": "name", "f": "=", "v": "John"}]}"#,
+            ))
+            .unwrap();
+
+        let FilterJson(filters) = FilterJson::from_request(request, &()).await.unwrap();
+        assert_eq!(
+            filters.to_pg_filters(0)?.sql()?,
+            " WHERE LOWER(name) = LOWER('John') ORDER BY name ASC LIMIT 20 OFFSET 0"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let response = FilterRequestError::new(FilterErrorCode::UnknownColumn, "Unknown column")
+            .field("sort")
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            r#"{"error":{"code":"unknown_column","message":"Unknown column","field":"sort"}}"#
+        );
+    }
+}
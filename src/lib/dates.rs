@@ -0,0 +1,249 @@
+//! ISO-8601 validation of date and time filter values
+//!
+//! Values are checked before they reach SQL so that a malformed timestamp is reported as a filter
+//! error rather than as a database error. Dates are `YYYY-MM-DD`, times are `HH:MM[:SS[.fff]]`
+//! with an optional `Z` or `±HH[:MM]` offset, and timestamps are a date, optionally followed by
+//! `T` or a space and a time.
+
+use crate::ColumnDef;
+
+/// Whether `value` is a `YYYY-MM-DD` date with a valid month and day
+pub(crate) fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+
+    match (
+        number(&value[..4]),
+        number(&value[5..7]),
+        number(&value[8..]),
+    ) {
+        (Some(year), Some(month), Some(day)) => {
+            (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `value` is a time of day, with an optional UTC offset
+pub(crate) fn is_time(value: &str) -> bool {
+    match value.find(['Z', 'z', '+', '-']) {
+        Some(index) => is_local_time(&value[..index]) && is_offset(&value[index..]),
+        None => is_local_time(value),
+    }
+}
+
+/// Whether `value` is a date, or a date and a time separated by `T` or a space
+pub(crate) fn is_timestamp(value: &str) -> bool {
+    match (value.get(..10), value.get(10..11), value.get(11..)) {
+        (Some(date), None, _) => is_date(date),
+        (Some(date), Some("T" | "t" | " "), Some(time)) => is_date(date) && is_time(time),
+        _ => false,
+    }
+}
+
+/// Whether the time of a timestamp ends with a UTC offset
+pub(crate) fn has_offset(value: &str) -> bool {
+    value
+        .get(11..)
+        .is_some_and(|time| time.contains(['Z', 'z', '+', '-']))
+}
+
+/// Whether `value` looks like an IANA time zone name such as `UTC` or `America/Los_Angeles`
+///
+/// Only the syntax is checked, which keeps other text out of the SQL. Whether the zone exists is
+/// only known when the query runs: PostgreSQL then fails it with `time zone "..." not
+/// recognized`, which callers see as a database error rather than as a filter error.
+pub(crate) fn is_time_zone(value: &str) -> bool {
+    value.len() <= 64
+        && value.split('/').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic())
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        })
+}
+
+/// Whether `value` is valid for `column_def`; values of non-temporal columns are not checked
+pub(crate) fn is_valid_for(column_def: &ColumnDef, value: &str) -> bool {
+    match column_def {
+        ColumnDef::Date(_) => is_date(value),
+        ColumnDef::Time(_) | ColumnDef::TimeTz(_) => is_time(value),
+        ColumnDef::Timestamp(_) | ColumnDef::TimestampTz(_) => is_timestamp(value),
+        _ => true,
+    }
+}
+
+fn is_local_time(value: &str) -> bool {
+    let (clock, fraction) = match value.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (value, None),
+    };
+
+    let parts = clock.split(':').map(two_digits).collect::<Vec<_>>();
+    let valid_clock = match parts.as_slice() {
+        [Some(hour), Some(minute)] => fraction.is_none() && *hour < 24 && *minute < 60,
+        // 60 allows for leap seconds
+        [Some(hour), Some(minute), Some(second)] => *hour < 24 && *minute < 60 && *second <= 60,
+        _ => false,
+    };
+    valid_clock && fraction.is_none_or(|digits| number(digits).is_some())
+}
+
+fn is_offset(value: &str) -> bool {
+    if value == "Z" || value == "z" {
+        return true;
+    }
+
+    let hours_and_minutes = match value.strip_prefix(['+', '-']) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let (hours, minutes) = match hours_and_minutes.len() {
+        2 => (two_digits(hours_and_minutes), Some(0)),
+        4 => (
+            two_digits(&hours_and_minutes[..2]),
+            two_digits(&hours_and_minutes[2..]),
+        ),
+        5 if &hours_and_minutes[2..3] == ":" => (
+            two_digits(&hours_and_minutes[..2]),
+            two_digits(&hours_and_minutes[3..]),
+        ),
+        _ => return false,
+    };
+    matches!((hours, minutes), (Some(hours), Some(minutes)) if hours < 24 && minutes < 60)
+}
+
+fn two_digits(value: &str) -> Option<u32> {
+    if value.len() == 2 {
+        number(value)
+    } else {
+        None
+    }
+}
+
+/// An unsigned number written with ASCII digits only
+fn number(value: &str) -> Option<u32> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        for valid in ["2024-01-31", "2024-02-29", "2000-02-29", "1999-12-31"] {
+            assert!(is_date(valid), "{}", valid);
+        }
+        for invalid in [
+            "2024-1-31",
+            "2024-13-01",
+            "2024-04-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-00-10",
+            "2024/01/31",
+            "2024-01-31 ",
+            "today",
+            "",
+        ] {
+            assert!(!is_date(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_times() {
+        for valid in [
+            "14:30",
+            "14:30:00",
+            "23:59:60",
+            "14:30:00.123456",
+            "14:30:00Z",
+            "14:30+02",
+            "14:30:00-0530",
+            "14:30:00.5+05:30",
+        ] {
+            assert!(is_time(valid), "{}", valid);
+        }
+        for invalid in [
+            "24:00",
+            "14:60",
+            "1:30",
+            "14:30.5",
+            "14:30:00.",
+            "14:30:00+5",
+            "14:30:00+25:00",
+            "noon",
+        ] {
+            assert!(!is_time(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        for valid in [
+            "2024-01-31",
+            "2024-01-31 14:30:00",
+            "2024-01-31T14:30:00Z",
+            "2024-01-31T14:30:00.123+01:00",
+        ] {
+            assert!(is_timestamp(valid), "{}", valid);
+        }
+        for invalid in [
+            "2024-01-31T",
+            "2024-01-31X14:30:00",
+            "2024-02-30 14:30:00",
+            "2024-01-31 25:00:00",
+            "now() - interval '1 day'",
+            "2024-01-31'; DROP TABLE users; --",
+        ] {
+            assert!(!is_timestamp(invalid), "{}", invalid);
+        }
+
+        assert!(has_offset("2024-01-31T14:30:00-05:00"));
+        assert!(has_offset("2024-01-31 14:30:00Z"));
+        assert!(!has_offset("2024-01-31 14:30:00"));
+        assert!(!has_offset("2024-01-31"));
+    }
+
+    #[test]
+    fn test_time_zones() {
+        for valid in [
+            "UTC",
+            "Australia/Sydney",
+            "America/Argentina/Buenos_Aires",
+            "Etc/GMT+10",
+            "America/Port-au-Prince",
+        ] {
+            assert!(is_time_zone(valid), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "/UTC",
+            "Europe/",
+            "+02:00",
+            "UTC'; DROP TABLE users; --",
+            "Europe/Paris ",
+        ] {
+            assert!(!is_time_zone(invalid), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Diesel filter module
+//!
+//! This module converts a `FilterExpression` tree into a boxed Diesel expression that can be
+//! passed to `.filter()`, and a `Sorting` into expressions that can be passed to
+//! `.then_order_by()`, so the same filters can be used with Diesel's query builder.
+//!
+//! Each condition becomes a SQL fragment whose values are bound with `push_bind_param`;
+//! groups are combined with Diesel's own `.and()` and `.or()`. The expressions are not tied to
+//! a particular table, so they can be boxed for any `QS`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use diesel::pg::Pg;
+//! use diesel::prelude::*;
+//! use pg_filters::diesel_filter::{filter_expression, order_by};
+//! use pg_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use pg_filters::sorting::{SortedColumn, Sorting};
+//! use std::collections::HashMap;
+//!
+//! diesel::table! {
+//!     person (id) {
+//!         id -> Integer,
+//!         name -> Text,
+//!         age -> Integer,
+//!     }
+//! }
+//!
+//! let expression = FilterExpression::Condition(FilterCondition::integer(
+//!     "age",
+//!     FilterOperator::GreaterThan,
+//!     Some(18),
+//! ));
+//!
+//! let mut query = person::table.into_boxed::<Pg>();
+//! if let Some(filter) = filter_expression(&expression, false, &HashMap::new()).unwrap() {
+//!     query = query.filter(filter);
+//! }
+//! for order in order_by(&Sorting::new(vec![SortedColumn::new("name", "asc")])) {
+//!     query = query.then_order_by(order);
+//! }
+//!
+//! let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+//! assert!(sql.ends_with("WHERE age > $1 ORDER BY name ASC -- binds: [18]"));
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterExpression, LogicalOperator, SqlParam, SqlPart};
+use crate::sorting::{SimilaritySort, SortOrder, Sorting};
+use crate::ColumnDef;
+use diesel::expression::{is_aggregate, AppearsOnTable, Expression, SelectableExpression};
+use diesel::expression::{BoxableExpression, ValidGrouping};
+use diesel::pg::Pg;
+use diesel::prelude::BoolExpressionMethods;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::sql_types::{Array, BigInt, Bool, Double, Float, Integer, SmallInt, Text, Untyped};
+use diesel::QueryResult;
+use eyre::Result;
+use std::collections::HashMap;
+
+/// A boxed boolean expression usable in `.filter()` on a query over `QS`
+pub type BoxedFilter<QS> = Box<dyn BoxableExpression<QS, Pg, SqlType = Bool>>;
+
+/// A single filter condition rendered as SQL with bound values
+#[derive(Debug, Clone)]
+pub struct FilterSql {
+    parts: Vec<SqlPart>,
+}
+
+impl Expression for FilterSql {
+    type SqlType = Bool;
+}
+
+impl<QS> AppearsOnTable<QS> for FilterSql {}
+
+impl<QS> SelectableExpression<QS> for FilterSql {}
+
+impl<GB> ValidGrouping<GB> for FilterSql {
+    type IsAggregate = is_aggregate::Never;
+}
+
+impl QueryId for FilterSql {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl QueryFragment<Pg> for FilterSql {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        for part in &self.parts {
+            match part {
+                SqlPart::Sql(sql) => out.push_sql(sql),
+                SqlPart::Param(SqlParam::Text(value)) => out.push_bind_param::<Text, _>(value)?,
+                SqlPart::Param(SqlParam::SmallInt(value)) => {
+                    out.push_bind_param::<SmallInt, _>(value)?
+                }
+                SqlPart::Param(SqlParam::Integer(value)) => {
+                    out.push_bind_param::<Integer, _>(value)?
+                }
+                SqlPart::Param(SqlParam::BigInt(value)) => {
+                    out.push_bind_param::<BigInt, _>(value)?
+                }
+                SqlPart::Param(SqlParam::Real(value)) => out.push_bind_param::<Float, _>(value)?,
+                SqlPart::Param(SqlParam::DoublePrecision(value)) => {
+                    out.push_bind_param::<Double, _>(value)?
+                }
+                SqlPart::Param(SqlParam::Boolean(value)) => {
+                    out.push_bind_param::<Bool, _>(value)?
+                }
+                SqlPart::Param(SqlParam::TextArray(values)) => {
+                    out.push_bind_param::<Array<Text>, _>(values)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single sort column, e.g. `name ASC`, or a similarity sort with its query bound
+#[derive(Debug, Clone)]
+pub struct OrderSql {
+    column: String,
+    similarity: Option<SimilaritySort>,
+    order: SortOrder,
+}
+
+impl Expression for OrderSql {
+    type SqlType = Untyped;
+}
+
+impl<QS> AppearsOnTable<QS> for OrderSql {}
+
+impl<QS> SelectableExpression<QS> for OrderSql {}
+
+impl<GB> ValidGrouping<GB> for OrderSql {
+    type IsAggregate = is_aggregate::Never;
+}
+
+impl QueryId for OrderSql {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl QueryFragment<Pg> for OrderSql {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        match &self.similarity {
+            Some(similarity) if similarity.word => {
+                out.push_sql("word_similarity(");
+                out.push_bind_param::<Text, _>(&similarity.query)?;
+                out.push_sql(", ");
+                out.push_sql(&self.column);
+                out.push_sql(")");
+            }
+            Some(similarity) => {
+                out.push_sql("similarity(");
+                out.push_sql(&self.column);
+                out.push_sql(", ");
+                out.push_bind_param::<Text, _>(&similarity.query)?;
+                out.push_sql(")");
+            }
+            None => out.push_sql(&self.column),
+        }
+        match self.order {
+            SortOrder::Asc => out.push_sql(" ASC"),
+            SortOrder::Desc => out.push_sql(" DESC"),
+        }
+        Ok(())
+    }
+}
+
+/// Converts `expression` into a boxed Diesel expression
+///
+/// Returns `None` when the expression is an empty group or its negation. IN list values are cast
+/// to the type of their column in `column_defs`.
+pub fn filter_expression<QS: 'static>(
+    expression: &FilterExpression,
+    case_insensitive: bool,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<Option<BoxedFilter<QS>>> {
+    match expression {
+        FilterExpression::Condition(_) | FilterExpression::Not { .. } => {
+            let parts = expression.to_sql_parts(case_insensitive, column_defs)?;
+            if parts.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(Box::new(FilterSql { parts })))
+        }
+        FilterExpression::Group {
+            operator,
+            expressions,
+        } => {
+            let mut combined: Option<BoxedFilter<QS>> = None;
+            for expression in expressions {
+                if let Some(filter) = filter_expression(expression, case_insensitive, column_defs)?
+                {
+                    combined = Some(match (combined, operator) {
+                        (None, _) => filter,
+                        (Some(left), LogicalOperator::And) => Box::new(left.and(filter)),
+                        (Some(left), LogicalOperator::Or) => Box::new(left.or(filter)),
+                    });
+                }
+            }
+            Ok(combined)
+        }
+    }
+}
+
+/// Converts the filters of `filter_builder` into a boxed Diesel expression
+///
+/// Returns `None` when the builder has no conditions.
+pub fn filter_builder<QS: 'static>(
+    filter_builder: &FilterBuilder,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<Option<BoxedFilter<QS>>> {
+    match filter_builder.expression()? {
+        Some(expression) => {
+            filter_expression(&expression, filter_builder.case_insensitive, column_defs)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Converts `sorting` into one order expression per column, in the same order as `sorting.sql`
+///
+/// A similarity sort comes first, with its query as a bound value.
+pub fn order_by(sorting: &Sorting) -> Vec<OrderSql> {
+    let similarity = sorting.similarity().into_iter().map(|similarity| OrderSql {
+        column: similarity.column.clone(),
+        similarity: Some(similarity.clone()),
+        order: SortOrder::Desc,
+    });
+    let columns = sorting.columns.iter().map(|column| OrderSql {
+        column: column.column.clone(),
+        similarity: None,
+        order: column.order.clone(),
+    });
+    similarity.chain(columns).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::{FilterCondition, FilterOperator};
+    use crate::sorting::SortedColumn;
+    use diesel::prelude::*;
+
+    diesel::table! {
+        person (id) {
+            id -> Uuid,
+            name -> Text,
+            age -> Integer,
+        }
+    }
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("id", ColumnDef::Uuid("id"));
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns
+    }
+
+    #[test]
+    fn test_filter_expression_groups() -> Result<()> {
+        let expression = FilterExpression::and(vec![
+            FilterExpression::Condition(FilterCondition::in_values(
+                "id",
+                FilterOperator::In,
+                vec!["123e4567-e89b-12d3-a456-426614174000"],
+            )),
+            FilterExpression::or(vec![
+                FilterExpression::Condition(FilterCondition::text(
+                    "name",
+                    FilterOperator::Like,
+                    Some("%John%"),
+                )),
+                FilterExpression::Condition(FilterCondition::integer(
+                    "age",
+                    FilterOperator::GreaterThan,
+                    Some(18),
+                )),
+            ]),
+        ]);
+
+        let filter =
+            filter_expression::<person::table>(&expression, true, &setup_test_columns())?.unwrap();
+        let query = person::table.into_boxed::<Pg>().filter(filter);
+        assert_eq!(
+            diesel::debug_query::<Pg, _>(&query).to_string(),
+            "SELECT \"person\".\"id\", \"person\".\"name\", \"person\".\"age\" FROM \"person\" \
+             WHERE (id IN ($1::uuid) AND (LOWER(name) LIKE LOWER($2) OR age > $3)) \
+             -- binds: [\"123e4567-e89b-12d3-a456-426614174000\", \"%John%\", 18]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_builder_empty() -> Result<()> {
+        let columns = setup_test_columns();
+        assert!(filter_builder::<person::table>(&FilterBuilder::new(), &columns)?.is_none());
+
+        let empty_group = FilterBuilder::new().group(LogicalOperator::Or, vec![]);
+        assert!(filter_builder::<person::table>(&empty_group, &columns)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by() {
+        let sorting = Sorting::new(vec![
+            SortedColumn::new("name", "asc"),
+            SortedColumn::new("age", "desc"),
+        ]);
+
+        let mut query = person::table.select(person::id).into_boxed::<Pg>();
+        for order in order_by(&sorting) {
+            query = query.then_order_by(order);
+        }
+        assert_eq!(
+            diesel::debug_query::<Pg, _>(&query).to_string(),
+            "SELECT \"person\".\"id\" FROM \"person\" ORDER BY age DESC, name ASC -- binds: []"
+        );
+
+        let sorting = sorting.with_similarity(SimilaritySort::word("name", "jon"));
+        let mut query = person::table.select(person::id).into_boxed::<Pg>();
+        for order in order_by(&sorting) {
+            query = query.then_order_by(order);
+        }
+        assert_eq!(
+            diesel::debug_query::<Pg, _>(&query).to_string(),
+            "SELECT \"person\".\"id\" FROM \"person\" ORDER BY word_similarity($1, name) DESC, age DESC, name ASC -- binds: [\"jon\"]"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Executor module
+//!
+//! This module runs a filtered, sorted and paginated query with tokio-postgres and returns the
+//! rows together with fully populated `Pagination` metadata.
+//!
+//! The count and the page query run in a single read-only `REPEATABLE READ` transaction, so the
+//! total and the page are taken from the same snapshot. Filter values are sent as bound
+//! parameters of their own type rather than inline literals; text values are cast to the type
+//! of their column in the SQL, e.g. `$1::uuid`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use pg_filters::executor::fetch_page;
+//! use pg_filters::{PaginationOptions, PgFilters};
+//! use std::collections::HashMap;
+//!
+//! # async fn run(client: &mut tokio_postgres::Client) -> eyre::Result<()> {
+//! let filters = PgFilters::new(
+//!     Some(PaginationOptions::new(2, 10, 50, 0)),
+//!     vec![],
+//!     None,
+//!     HashMap::new(),
+//! )?;
+//!
+//! let page = fetch_page(client, "SELECT * FROM person", &[], &filters, 50).await?;
+//! println!("page {} of {}", page.pagination.current_page, page.pagination.total_pages);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::filtering::{QueryParams, SqlParam};
+use crate::pagination::{Page, Paginate};
+use crate::PgFilters;
+use eyre::Result;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{Client, IsolationLevel, Row};
+
+/// Runs `base_query` with the filters, sorting and pagination from `filters` applied
+///
+/// `base_query` must not contain a WHERE, ORDER BY or LIMIT clause of its own, e.g.
+/// `SELECT * FROM person`. It may bind its own parameters as `$1` to `$n`, which are passed in
+/// `params`; filter values are numbered after them.
+///
+/// The `total_records` of the pagination options used to build `filters` is ignored and
+/// replaced with the filtered count, so the returned `Pagination` is fully populated and the
+/// current page is clamped to the last page. Pages have at most `per_page_limit` rows; without
+/// pagination in `filters`, the first `per_page_limit` rows are returned.
+pub async fn fetch_page(
+    client: &mut Client,
+    base_query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    filters: &PgFilters,
+    per_page_limit: i64,
+) -> Result<Page<Row>> {
+    let mut query_params =
+        QueryParams::starting_after(params.len()).with_column_defs(&filters.column_defs);
+    let where_sql = match &filters.filters {
+        Some(filters) => filters.build_with_params(&mut query_params)?,
+        None => String::new(),
+    };
+    // The count only binds the filter values, the page also the similarity sort query
+    let count_params = params.len() + query_params.values.len();
+    let order_sql = match &filters.sorting {
+        Some(sorting) => sorting.sql_with_params(&mut query_params),
+        None => String::new(),
+    };
+
+    let filter_params = query_params
+        .values
+        .iter()
+        .map(bound_param)
+        .collect::<Vec<_>>();
+    let mut all_params = params.to_vec();
+    all_params.extend(filter_params.iter().map(|p| p.as_ref()));
+
+    let transaction = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await?;
+
+    // The parameters of `base_query` keep their inferred types, filter values are declared as
+    // the type they are sent as
+    let mut types = if params.is_empty() {
+        Vec::new()
+    } else {
+        transaction.prepare(base_query).await?.params().to_vec()
+    };
+    types.extend(query_params.values.iter().map(param_type));
+
+    let count_sql = format!(
+        "SELECT COUNT(*) FROM ({}{}) AS filtered",
+        base_query, where_sql
+    );
+    let count = transaction
+        .prepare_typed(&count_sql, &types[..count_params])
+        .await?;
+    let total_records: i64 = transaction
+        .query_one(&count, &all_params[..count_params])
+        .await?
+        .get(0);
+
+    let mut sql = format!("{}{}{}", base_query, where_sql, order_sql);
+
+    let (current_page, per_page) = match &filters.pagination {
+        Some(requested) => (
+            requested.pagination.current_page,
+            requested.pagination.per_page,
+        ),
+        None => (1, per_page_limit),
+    };
+    let paginate = Paginate::new(current_page, per_page, per_page_limit, total_records);
+    sql.push(' ');
+    sql.push_str(&paginate.sql);
+
+    let page = transaction.prepare_typed(&sql, &types).await?;
+    let rows = transaction.query(&page, &all_params).await?;
+    transaction.commit().await?;
+
+    Ok(Page {
+        rows,
+        pagination: paginate.pagination,
+    })
+}
+
+/// The value of a parameter, as the Rust type of `param_type`
+fn bound_param(param: &SqlParam) -> Box<dyn ToSql + Sync> {
+    match param {
+        SqlParam::Text(value) => Box::new(value.clone()),
+        SqlParam::SmallInt(value) => Box::new(*value),
+        SqlParam::Integer(value) => Box::new(*value),
+        SqlParam::BigInt(value) => Box::new(*value),
+        SqlParam::Real(value) => Box::new(*value),
+        SqlParam::DoublePrecision(value) => Box::new(*value),
+        SqlParam::Boolean(value) => Box::new(*value),
+        SqlParam::TextArray(values) => Box::new(values.clone()),
+    }
+}
+
+/// The type a parameter is declared as when the query is prepared
+fn param_type(param: &SqlParam) -> Type {
+    match param {
+        SqlParam::Text(_) => Type::TEXT,
+        SqlParam::SmallInt(_) => Type::INT2,
+        SqlParam::Integer(_) => Type::INT4,
+        SqlParam::BigInt(_) => Type::INT8,
+        SqlParam::Real(_) => Type::FLOAT4,
+        SqlParam::DoublePrecision(_) => Type::FLOAT8,
+        SqlParam::Boolean(_) => Type::BOOL,
+        SqlParam::TextArray(_) => Type::TEXT_ARRAY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_params_match_declared_types() {
+        let params = [
+            SqlParam::Text("O'Brien".to_string()),
+            SqlParam::SmallInt(1),
+            SqlParam::Integer(18),
+            SqlParam::BigInt(7),
+            SqlParam::Real(1.5),
+            SqlParam::DoublePrecision(0.4),
+            SqlParam::Boolean(true),
+            SqlParam::TextArray(vec!["EPC".to_string(), "a,b".to_string()]),
+        ];
+        for param in &params {
+            let mut out = bytes::BytesMut::new();
+            let result = bound_param(param).to_sql_checked(&param_type(param), &mut out);
+            assert!(result.is_ok(), "{:?}", param);
+        }
+
+        // Text values are not accepted as other types
+        let mut out = bytes::BytesMut::new();
+        assert!(bound_param(&params[0])
+            .to_sql_checked(&Type::UUID, &mut out)
+            .is_err());
+    }
+}
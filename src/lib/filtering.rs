@@ -1,6 +1,10 @@
-use crate::ColumnDef;
+use crate::relation::{find_relation, RelatedRows, Relation};
+use crate::relative_date::{Clock, DateUnit, RelativeDate};
+use crate::{dates, ColumnDef};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
@@ -34,20 +38,31 @@ impl fmt::Display for LogicalOperator {
 pub enum FilterOperator {
     Equal,
     NotEqual,
+    IsDistinctFrom,
+    IsNotDistinctFrom,
     GreaterThan,
     GreaterThanOrEqual,
     LessThan,
     LessThanOrEqual,
     Like,
     NotLike,
+    ILike,
     In,
     NotIn,
+    Between,
+    NotBetween,
+    BetweenSymmetric,
+    NotBetweenSymmetric,
     IsNull,
     IsNotNull,
     StartsWith,
     EndsWith,
     Contains,
     Overlaps,
+    /// Trigram similarity, `%`, from the `pg_trgm` extension
+    Similar,
+    /// Trigram word similarity, `<%`, from the `pg_trgm` extension
+    WordSimilar,
     DateEqual,
     DateRange,
     RelativeDate,
@@ -58,26 +73,45 @@ impl FilterOperator {
         match self {
             FilterOperator::Equal => "=",
             FilterOperator::NotEqual => "!=",
+            FilterOperator::IsDistinctFrom => "IS DISTINCT FROM",
+            FilterOperator::IsNotDistinctFrom => "IS NOT DISTINCT FROM",
             FilterOperator::GreaterThan => ">",
             FilterOperator::GreaterThanOrEqual => ">=",
             FilterOperator::LessThan => "<",
             FilterOperator::LessThanOrEqual => "<=",
             FilterOperator::Like => "LIKE",
             FilterOperator::NotLike => "NOT LIKE",
+            FilterOperator::ILike => "ILIKE",
             FilterOperator::In => "IN",
             FilterOperator::NotIn => "NOT IN",
+            FilterOperator::Between => "BETWEEN",
+            FilterOperator::NotBetween => "NOT BETWEEN",
+            FilterOperator::BetweenSymmetric => "BETWEEN SYMMETRIC",
+            FilterOperator::NotBetweenSymmetric => "NOT BETWEEN SYMMETRIC",
             FilterOperator::IsNull => "IS NULL",
             FilterOperator::IsNotNull => "IS NOT NULL",
             FilterOperator::StartsWith => "LIKE",
             FilterOperator::EndsWith => "LIKE",
             FilterOperator::Contains => "@>",
             FilterOperator::Overlaps => "&&",
+            FilterOperator::Similar => "%",
+            FilterOperator::WordSimilar => "<%",
             FilterOperator::DateEqual => "=",
             FilterOperator::DateRange => "BETWEEN",
             FilterOperator::RelativeDate => ">",
         }
     }
 
+    pub fn is_between(&self) -> bool {
+        matches!(
+            self,
+            FilterOperator::Between
+                | FilterOperator::NotBetween
+                | FilterOperator::BetweenSymmetric
+                | FilterOperator::NotBetweenSymmetric
+        )
+    }
+
     pub fn format_value(&self, value: &str) -> String {
         match self {
             FilterOperator::StartsWith => format!("{}%", value),
@@ -109,20 +143,87 @@ pub enum FilterExpression {
         operator: LogicalOperator,
         expressions: Vec<FilterExpression>,
     },
+    /// Matches the rows `expression` does not match
+    ///
+    /// `NOT` follows SQL semantics, so rows where `expression` is NULL match neither it nor its
+    /// negation. When `null_safe` is set, it renders as `IS NOT TRUE` and those rows match.
+    Not {
+        expression: Box<FilterExpression>,
+        null_safe: bool,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JsonFilter {
-    pub n: String,         // name/column
-    pub f: String,         // filter operator
-    pub v: String,         // value
+    pub n: String, // name/column
+    pub f: String, // filter operator
+    #[serde(default)]
+    pub v: Value, // value: a string, number, boolean, null, array or {start, end} object
     pub c: Option<String>, // optional connector (AND/OR)
 }
 
+impl JsonFilter {
+    /// Creates a filter from a column, an operator and a value
+    ///
+    /// `value` may be a `&str` or `String`, as `v` was before 0.2, or any other JSON value.
+    pub fn new(
+        column: &str,
+        operator: &str,
+        value: impl Into<Value>,
+        connector: Option<&str>,
+    ) -> JsonFilter {
+        JsonFilter {
+            n: column.to_string(),
+            f: operator.to_string(),
+            v: value.into(),
+            c: connector.map(str::to_string),
+        }
+    }
+
+    /// The value in its string form, if it is a string, number or boolean
+    pub fn text(&self) -> Option<String> {
+        scalar_text(&self.v)
+    }
+}
+
+/// The string form of a string, number or boolean
+pub(crate) fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
 impl FilterExpression {
     pub fn to_sql(&self, case_insensitive: bool) -> Result<String> {
+        self.render(case_insensitive, &mut SqlValues::inline())
+    }
+
+    /// Renders the expression with numbered placeholders, binding its values to `params`
+    pub fn to_sql_with_params(
+        &self,
+        case_insensitive: bool,
+        params: &mut QueryParams,
+    ) -> Result<String> {
+        self.render(case_insensitive, &mut SqlValues::bound(params))
+    }
+
+    /// Renders the expression as SQL pieces and the values bound between them, casting IN list
+    /// values to the type of their column in `column_defs`
+    #[cfg(any(feature = "diesel", feature = "sea-query"))]
+    pub(crate) fn to_sql_parts(
+        &self,
+        case_insensitive: bool,
+        column_defs: &HashMap<&'static str, ColumnDef>,
+    ) -> Result<Vec<SqlPart>> {
+        render_parts(column_defs, |values| self.render(case_insensitive, values))
+    }
+
+    pub(crate) fn render(&self, case_insensitive: bool, values: &mut SqlValues) -> Result<String> {
         match self {
-            FilterExpression::Condition(condition) => condition.to_sql(case_insensitive),
+            FilterExpression::Condition(condition) => condition.render(case_insensitive, values),
             FilterExpression::Group {
                 operator,
                 expressions,
@@ -133,7 +234,7 @@ impl FilterExpression {
 
                 let conditions: Result<Vec<String>> = expressions
                     .iter()
-                    .map(|expr| expr.to_sql(case_insensitive))
+                    .map(|expr| expr.render(case_insensitive, values))
                     .collect();
 
                 let conditions = conditions?;
@@ -142,6 +243,26 @@ impl FilterExpression {
                     conditions.join(&format!(" {} ", operator.as_sql()))
                 ))
             }
+            FilterExpression::Not {
+                expression,
+                null_safe,
+            } => {
+                let sql = expression.render(case_insensitive, values)?;
+                if sql.is_empty() {
+                    return Ok(sql);
+                }
+
+                // Groups render with their own parentheses
+                let sql = match **expression {
+                    FilterExpression::Group { .. } => sql,
+                    _ => format!("({})", sql),
+                };
+                if *null_safe {
+                    Ok(format!("{} IS NOT TRUE", sql))
+                } else {
+                    Ok(format!("NOT {}", sql))
+                }
+            }
         }
     }
 
@@ -158,6 +279,131 @@ impl FilterExpression {
             expressions,
         }
     }
+
+    /// Groups `operands` with `operator`, or returns the single operand as is
+    pub(crate) fn combine(operator: LogicalOperator, mut operands: Vec<FilterExpression>) -> Self {
+        if operands.len() == 1 {
+            operands.remove(0)
+        } else {
+            FilterExpression::Group {
+                operator,
+                expressions: operands,
+            }
+        }
+    }
+
+    /// Rewrites every `!=` condition to `IS DISTINCT FROM` and every `NOT IN` condition to
+    /// `(... NOT IN (...) OR ... IS NULL)`, so they also match rows where the column is NULL
+    pub fn null_safe_inequality(&self) -> FilterExpression {
+        match self {
+            FilterExpression::Condition(condition) => condition.null_safe_inequality(),
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => FilterExpression::Group {
+                operator: operator.clone(),
+                expressions: expressions
+                    .iter()
+                    .map(FilterExpression::null_safe_inequality)
+                    .collect(),
+            },
+            FilterExpression::Not {
+                expression,
+                null_safe,
+            } => FilterExpression::Not {
+                expression: Box::new(expression.null_safe_inequality()),
+                null_safe: *null_safe,
+            },
+        }
+    }
+
+    /// Takes the days of every date range on a `TimestampTz` column in `time_zone`, an IANA
+    /// name such as `Australia/Sydney`
+    ///
+    /// See `FilterCondition::in_time_zone`.
+    pub fn in_time_zone(&self, time_zone: &str) -> FilterExpression {
+        match self {
+            FilterExpression::Condition(condition) => {
+                FilterExpression::Condition(condition.in_time_zone(time_zone))
+            }
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => FilterExpression::Group {
+                operator: operator.clone(),
+                expressions: expressions
+                    .iter()
+                    .map(|expression| expression.in_time_zone(time_zone))
+                    .collect(),
+            },
+            FilterExpression::Not {
+                expression,
+                null_safe,
+            } => FilterExpression::Not {
+                expression: Box::new(expression.in_time_zone(time_zone)),
+                null_safe: *null_safe,
+            },
+        }
+    }
+
+    /// Compares text conditions on `columns` with accents stripped by `function`
+    ///
+    /// See `FilterCondition::unaccent`.
+    pub fn unaccent(&self, columns: &[String], function: &str) -> FilterExpression {
+        match self {
+            FilterExpression::Condition(condition) => match condition {
+                FilterCondition::TextValue { column, .. }
+                | FilterCondition::VarcharValue { column, .. }
+                | FilterCondition::CharValue { column, .. }
+                    if columns.contains(column) =>
+                {
+                    FilterExpression::Condition(condition.unaccent(function))
+                }
+                _ => self.clone(),
+            },
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => FilterExpression::Group {
+                operator: operator.clone(),
+                expressions: expressions
+                    .iter()
+                    .map(|expression| expression.unaccent(columns, function))
+                    .collect(),
+            },
+            FilterExpression::Not {
+                expression,
+                null_safe,
+            } => FilterExpression::Not {
+                expression: Box::new(expression.unaccent(columns, function)),
+                null_safe: *null_safe,
+            },
+        }
+    }
+
+    /// Negates `expression` with SQL `NOT`; also available as `!expression`
+    pub fn negate(expression: FilterExpression) -> Self {
+        FilterExpression::Not {
+            expression: Box::new(expression),
+            null_safe: false,
+        }
+    }
+
+    /// Negates `expression`, also matching rows where it is NULL
+    pub fn negate_null_safe(expression: FilterExpression) -> Self {
+        FilterExpression::Not {
+            expression: Box::new(expression),
+            null_safe: true,
+        }
+    }
+}
+
+impl std::ops::Not for FilterExpression {
+    type Output = FilterExpression;
+
+    fn not(self) -> Self::Output {
+        FilterExpression::negate(self)
+    }
 }
 
 impl fmt::Display for FilterExpression {
@@ -179,6 +425,164 @@ impl fmt::Display for FilterExpression {
                         .join(&format!(" {} ", operator))
                 )
             }
+            FilterExpression::Not {
+                expression,
+                null_safe: false,
+            } => write!(f, "NOT ({})", expression),
+            FilterExpression::Not {
+                expression,
+                null_safe: true,
+            } => write!(f, "({}) IS NOT TRUE", expression),
+        }
+    }
+}
+
+/// The type of the column a `FilterCondition::DateRange` applies to, which sets how its values
+/// are cast and where a day starts and ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateColumnType {
+    Date,
+    Timestamp,
+    TimestampTz,
+}
+
+impl DateColumnType {
+    fn cast(&self) -> &'static str {
+        match self {
+            DateColumnType::Date => "date",
+            DateColumnType::Timestamp => "timestamp",
+            DateColumnType::TimestampTz => "timestamptz",
+        }
+    }
+}
+
+/// A field of a date or timestamp that `EXTRACT` compares as an integer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Year,
+    /// 1 to 4
+    Quarter,
+    /// 1 to 12
+    Month,
+    /// ISO week of the year, 1 to 53
+    Week,
+    /// Day of the month, 1 to 31
+    Day,
+    /// Day of the week from Sunday (0) to Saturday (6)
+    DayOfWeek,
+    /// Day of the week from Monday (1) to Sunday (7)
+    IsoDayOfWeek,
+    /// 1 to 366
+    DayOfYear,
+    /// 0 to 23
+    Hour,
+    /// 0 to 59
+    Minute,
+    /// 0 to 60, truncated to whole seconds
+    Second,
+}
+
+impl DateField {
+    pub(crate) const ALL: [DateField; 11] = [
+        DateField::Year,
+        DateField::Quarter,
+        DateField::Month,
+        DateField::Week,
+        DateField::Day,
+        DateField::DayOfWeek,
+        DateField::IsoDayOfWeek,
+        DateField::DayOfYear,
+        DateField::Hour,
+        DateField::Minute,
+        DateField::Second,
+    ];
+
+    /// The field as written in `EXTRACT`, e.g. `ISODOW`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateField::Year => "YEAR",
+            DateField::Quarter => "QUARTER",
+            DateField::Month => "MONTH",
+            DateField::Week => "WEEK",
+            DateField::Day => "DAY",
+            DateField::DayOfWeek => "DOW",
+            DateField::IsoDayOfWeek => "ISODOW",
+            DateField::DayOfYear => "DOY",
+            DateField::Hour => "HOUR",
+            DateField::Minute => "MINUTE",
+            DateField::Second => "SECOND",
+        }
+    }
+
+    pub fn parse(field: &str) -> Result<Self> {
+        let name = field.trim().to_uppercase();
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == name)
+            .ok_or_else(|| eyre::eyre!("Invalid date field: {}", field))
+    }
+
+    /// Whether the field is part of the time of day, which dates do not have
+    pub fn is_time_of_day(&self) -> bool {
+        matches!(
+            self,
+            DateField::Hour | DateField::Minute | DateField::Second
+        )
+    }
+
+    /// The values the field can take, or `None` for years
+    pub(crate) fn range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        match self {
+            DateField::Year => None,
+            DateField::Quarter => Some(1..=4),
+            DateField::Month => Some(1..=12),
+            DateField::Week => Some(1..=53),
+            DateField::Day => Some(1..=31),
+            DateField::DayOfWeek => Some(0..=6),
+            DateField::IsoDayOfWeek => Some(1..=7),
+            DateField::DayOfYear => Some(1..=366),
+            DateField::Hour => Some(0..=23),
+            DateField::Minute => Some(0..=59),
+            DateField::Second => Some(0..=60),
+        }
+    }
+}
+
+/// A function applied to a date column before it is compared, written in JSON filters as the
+/// prefix of the operator, e.g. `EXTRACT(HOUR) BETWEEN` or `DATE_TRUNC(MONTH) =`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFunction {
+    /// `EXTRACT(field FROM column)`
+    Extract(DateField),
+    /// `date_trunc(unit, column)`
+    Trunc(DateUnit),
+}
+
+impl DateFunction {
+    /// Parses `EXTRACT(field)` or `DATE_TRUNC(unit)`, in any case
+    pub fn parse(function: &str) -> Result<Self> {
+        let invalid = || eyre::eyre!("Invalid date function: {}", function);
+        let (name, argument) = function
+            .trim()
+            .strip_suffix(')')
+            .and_then(|function| function.split_once('('))
+            .ok_or_else(invalid)?;
+
+        match name.trim().to_uppercase().as_str() {
+            "EXTRACT" => Ok(DateFunction::Extract(DateField::parse(argument)?)),
+            "DATE_TRUNC" => DateUnit::parse(&argument.trim().to_lowercase())
+                .map(DateFunction::Trunc)
+                .ok_or_else(|| eyre::eyre!("Invalid date_trunc unit: {}", argument)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for DateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateFunction::Extract(field) => write!(f, "EXTRACT({})", field.as_str()),
+            DateFunction::Trunc(unit) => write!(f, "DATE_TRUNC({})", unit.as_str().to_uppercase()),
         }
     }
 }
@@ -191,8 +595,44 @@ pub enum DateRangeType {
     DateOnly(String),
     /// Custom date range
     Range { start: String, end: String },
-    /// Relative date expression
-    Relative(String),
+    /// Relative date, e.g. `last 7 days`
+    Relative(RelativeDate),
+}
+
+/// Which ends of a `FilterCondition::Between` range are included, written as in PostgreSQL
+/// range literals: `[]`, `()`, `[)` or `(]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BetweenBounds {
+    Inclusive,
+    Exclusive,
+    StartInclusive,
+    EndInclusive,
+}
+
+impl BetweenBounds {
+    pub fn parse(bounds: &str) -> Result<Self> {
+        match bounds {
+            "[]" => Ok(BetweenBounds::Inclusive),
+            "()" => Ok(BetweenBounds::Exclusive),
+            "[)" => Ok(BetweenBounds::StartInclusive),
+            "(]" => Ok(BetweenBounds::EndInclusive),
+            _ => Err(eyre::eyre!("Invalid bounds: {}", bounds)),
+        }
+    }
+
+    fn start_operator(&self) -> &'static str {
+        match self {
+            BetweenBounds::Inclusive | BetweenBounds::StartInclusive => ">=",
+            BetweenBounds::Exclusive | BetweenBounds::EndInclusive => ">",
+        }
+    }
+
+    fn end_operator(&self) -> &'static str {
+        match self {
+            BetweenBounds::Inclusive | BetweenBounds::EndInclusive => "<=",
+            BetweenBounds::Exclusive | BetweenBounds::StartInclusive => "<",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -213,11 +653,41 @@ pub enum FilterCondition {
         operator: FilterOperator,
         value: Option<String>,
     },
+    // citext columns, which compare case-insensitively without LOWER()
+    CiTextValue {
+        column: String,
+        operator: FilterOperator,
+        value: Option<String>,
+    },
+    // Text compared with the accents of both sides stripped by `function`, e.g. `unaccent` or
+    // an immutable wrapper of it used in an expression index
+    UnaccentText {
+        column: String,
+        operator: FilterOperator,
+        value: String,
+        function: String,
+    },
 
     // Date ranges
     DateRange {
         column: String,
         range_type: DateRangeType,
+        column_type: DateColumnType,
+        /// IANA time zone the days of a `TimestampTz` column are taken in, instead of the
+        /// session time zone
+        time_zone: Option<String>,
+    },
+
+    // EXTRACT or date_trunc of a date column compared with one value, the values of IN or the
+    // two bounds of BETWEEN
+    DateFunction {
+        column: String,
+        function: DateFunction,
+        operator: FilterOperator,
+        values: Vec<SqlParam>,
+        column_type: DateColumnType,
+        /// IANA time zone a `TimestampTz` column is converted to before the function applies
+        time_zone: Option<String>,
     },
 
     // Multi-value conditions for IN/NOT IN
@@ -227,6 +697,25 @@ pub enum FilterCondition {
         values: Vec<String>,
     },
 
+    // Ranges over ordered types, text bounds are cast to `cast` when bound
+    Between {
+        column: String,
+        operator: FilterOperator,
+        start: SqlParam,
+        end: SqlParam,
+        bounds: BetweenBounds,
+        cast: Option<&'static str>,
+    },
+
+    // Trigram matches of text columns, by the similarity threshold of pg_trgm unless
+    // `threshold` is given
+    Similarity {
+        column: String,
+        operator: FilterOperator,
+        value: String,
+        threshold: Option<f64>,
+    },
+
     // Numeric Types
     SmallIntValue {
         column: String,
@@ -253,6 +742,11 @@ pub enum FilterCondition {
         operator: FilterOperator,
         value: Option<f64>,
     },
+    NumericValue {
+        column: String,
+        operator: FilterOperator,
+        value: Option<String>,
+    },
 
     // Date/Time Types
     DateValue {
@@ -366,33 +860,331 @@ pub enum FilterCondition {
         operator: FilterOperator,
         value: Option<String>,
     },
+
+    // Rows of another table related by `keys`, pairs of a related column and the column of the
+    // filtered table it equals, matched by an EXISTS or COUNT subquery; see the `relation` module
+    Related {
+        table: String,
+        keys: Vec<(String, String)>,
+        rows: RelatedRows,
+        filter: Option<Box<FilterExpression>>,
+        /// Definitions of the related columns, in place of those of the filtered table for IN
+        /// lists
+        column_defs: Vec<ColumnDef>,
+    },
+}
+
+/// A value bound as a query parameter when SQL is rendered with placeholders
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlParam {
+    Text(String),
+    SmallInt(i16),
+    Integer(i32),
+    BigInt(i64),
+    Real(f32),
+    DoublePrecision(f64),
+    Boolean(bool),
+    TextArray(Vec<String>),
+}
+
+/// Collects the values bound while rendering SQL with numbered placeholders
+///
+/// Text values bound for non-text columns are rendered with an explicit cast, e.g. `$1::uuid`,
+/// so a driver can send every `SqlParam::Text` as text.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_filters::filtering::{FilterBuilder, FilterCondition, FilterOperator, QueryParams, SqlParam};
+///
+/// let mut params = QueryParams::new();
+/// let sql = FilterBuilder::new()
+///     .add_condition(FilterCondition::text("name", FilterOperator::Equal, Some("John")))
+///     .add_condition(FilterCondition::integer("age", FilterOperator::GreaterThan, Some(18)))
+///     .build_with_params(&mut params)
+///     .unwrap();
+///
+/// assert_eq!(sql, " WHERE (name = $1 AND age > $2)");
+/// assert_eq!(
+///     params.values,
+///     vec![SqlParam::Text("John".to_string()), SqlParam::Integer(18)]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryParams {
+    pub values: Vec<SqlParam>,
+    offset: usize,
+    column_defs: HashMap<String, ColumnDef>,
+    markers: bool,
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Numbers placeholders after `offset`, for queries that already bind `$1` to `$offset`
+    pub fn starting_after(offset: usize) -> Self {
+        Self {
+            offset,
+            ..Self::default()
+        }
+    }
+
+    /// Casts IN list values to the type of their column, e.g. `id IN ($1::uuid, $2::uuid)`, and
+    /// lowers the IN lists of text columns when matching is case-insensitive
+    pub fn with_column_defs(mut self, column_defs: &HashMap<&'static str, ColumnDef>) -> Self {
+        self.add_column_defs(column_defs);
+        self
+    }
+
+    pub(crate) fn add_column_defs(&mut self, column_defs: &HashMap<&'static str, ColumnDef>) {
+        self.column_defs
+            .extend(by_column_name(column_defs.values()));
+    }
+
+    fn push(&mut self, value: SqlParam, cast: Option<&str>) -> String {
+        self.values.push(value);
+        let index = self.offset + self.values.len();
+        let placeholder = if self.markers {
+            format!("{}{}{}", PARAM_MARKER, index, PARAM_MARKER)
+        } else {
+            format!("${}", index)
+        };
+        match cast {
+            Some(cast) => format!("{}::{}", placeholder, cast),
+            None => placeholder,
+        }
+    }
+}
+
+/// `column_defs` by the name of their column
+fn by_column_name<'a>(
+    column_defs: impl IntoIterator<Item = &'a ColumnDef>,
+) -> HashMap<String, ColumnDef> {
+    column_defs
+        .into_iter()
+        .map(|column_def| (column_def.get_column_name(), column_def.clone()))
+        .collect()
+}
+
+/// Stands in for a placeholder when rendering `SqlPart`s. Postgres rejects NUL in query text,
+/// so it cannot come from a column name or an inline literal.
+const PARAM_MARKER: char = '\0';
+
+/// Renders values either inline as escaped literals or as placeholders bound to `QueryParams`
+pub(crate) struct SqlValues<'a> {
+    params: Option<&'a mut QueryParams>,
+    /// Definitions of the columns of the filtered table, or of a related table in its subquery
+    column_defs: HashMap<String, ColumnDef>,
+}
+
+impl<'a> SqlValues<'a> {
+    pub(crate) fn inline() -> Self {
+        Self {
+            params: None,
+            column_defs: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn bound(params: &'a mut QueryParams) -> Self {
+        Self {
+            column_defs: params.column_defs.clone(),
+            params: Some(params),
+        }
+    }
+
+    /// Adds the definitions of columns not already known, e.g. from a `FilterBuilder`
+    fn add_column_defs(&mut self, column_defs: &HashMap<String, ColumnDef>) {
+        for (column, column_def) in column_defs {
+            self.column_defs
+                .entry(column.clone())
+                .or_insert_with(|| column_def.clone());
+        }
+    }
+
+    pub(crate) fn is_bound(&self) -> bool {
+        self.params.is_some()
+    }
+
+    fn column_cast(&self, column: &str) -> Option<&'static str> {
+        self.column_defs.get(column).and_then(ColumnDef::param_cast)
+    }
+
+    /// Whether `column` is known to be a text, varchar or char column
+    fn is_text_column(&self, column: &str) -> bool {
+        matches!(
+            self.column_defs.get(column),
+            Some(ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_))
+        )
+    }
+
+    /// A quoted string literal, cast to `cast` when bound
+    pub(crate) fn text(&mut self, value: &str, cast: Option<&str>) -> String {
+        match &mut self.params {
+            Some(params) => params.push(SqlParam::Text(value.to_string()), cast),
+            None => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    /// A string literal cast to `cast`, e.g. `('2024-12-29')::date` or `$1::date`
+    pub(crate) fn cast(&mut self, value: &str, cast: &str) -> String {
+        if self.is_bound() {
+            self.text(value, Some(cast))
+        } else {
+            format!("({})::{}", self.text(value, None), cast)
+        }
+    }
+
+    /// An unquoted literal such as a number or boolean
+    pub(crate) fn raw<T: fmt::Display>(&mut self, value: T, param: SqlParam) -> String {
+        match &mut self.params {
+            Some(params) => params.push(param, None),
+            None => value.to_string(),
+        }
+    }
+
+    /// Any parameter, with text cast to `cast` when bound
+    pub(crate) fn param(&mut self, param: &SqlParam, cast: Option<&str>) -> String {
+        match param {
+            SqlParam::Text(value) => self.text(value, cast),
+            SqlParam::SmallInt(value) => self.raw(value, param.clone()),
+            SqlParam::Integer(value) => self.raw(value, param.clone()),
+            SqlParam::BigInt(value) => self.raw(value, param.clone()),
+            SqlParam::Real(value) => self.raw(value, param.clone()),
+            SqlParam::DoublePrecision(value) => self.raw(value, param.clone()),
+            SqlParam::Boolean(value) => self.raw(value, param.clone()),
+            SqlParam::TextArray(items) => self.text_array(items),
+        }
+    }
+
+    /// Renders with `column_defs` in place of the column definitions of the filtered table, for
+    /// the columns of a related table
+    pub(crate) fn with_column_defs<T>(
+        &mut self,
+        column_defs: &[ColumnDef],
+        render: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let outer = std::mem::replace(&mut self.column_defs, by_column_name(column_defs));
+        let result = render(self);
+        self.column_defs = outer;
+        result
+    }
+
+    pub(crate) fn text_array(&mut self, values: &[String]) -> String {
+        match &mut self.params {
+            Some(params) => params.push(SqlParam::TextArray(values.to_vec()), Some("text[]")),
+            None => format!(
+                "ARRAY[{}]::text[]",
+                values
+                    .iter()
+                    .map(|v| format!("'{}'", v.replace('\'', "''")))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+/// A piece of rendered SQL, or a value bound between two pieces
+#[cfg(any(feature = "sqlx", feature = "diesel", feature = "sea-query"))]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SqlPart {
+    Sql(String),
+    Param(SqlParam),
+}
+
+/// Renders with a marker in place of each placeholder, and splits the SQL at the markers into
+/// `SqlPart`s in the order the values appear
+#[cfg(any(feature = "sqlx", feature = "diesel", feature = "sea-query"))]
+pub(crate) fn render_parts(
+    column_defs: &HashMap<&'static str, ColumnDef>,
+    render: impl FnOnce(&mut SqlValues) -> Result<String>,
+) -> Result<Vec<SqlPart>> {
+    let mut params = QueryParams::new().with_column_defs(column_defs);
+    params.markers = true;
+    let sql = render(&mut SqlValues::bound(&mut params))?;
+
+    let mut values = params.values.into_iter().map(Some).collect::<Vec<_>>();
+    let mut parts = Vec::new();
+    // Pieces alternate between SQL and the number of a value
+    for (i, piece) in sql.split(PARAM_MARKER).enumerate() {
+        if i % 2 == 0 {
+            if !piece.is_empty() {
+                parts.push(SqlPart::Sql(piece.to_string()));
+            }
+            continue;
+        }
+        let value = piece
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| values.get_mut(index))
+            .and_then(Option::take)
+            .ok_or_else(|| eyre::eyre!("SQL must not contain NUL characters"))?;
+        parts.push(SqlPart::Param(value));
+    }
+    if values.iter().any(Option::is_some) {
+        return Err(eyre::eyre!("SQL must not contain NUL characters"));
+    }
+    Ok(parts)
 }
 
 impl FilterCondition {
-    fn format_value<T: fmt::Display>(
+    fn format_value<T: fmt::Display + Copy>(
         column: &str,
         operator: &FilterOperator,
         value: Option<T>,
+        param: fn(T) -> SqlParam,
+        values: &mut SqlValues,
     ) -> String {
-        match value {
-            Some(v) => format!("{} {} {}", column, operator.as_sql(), v),
-            None => format!("{} {}", column, operator.as_sql()),
-        }
-    }
-
-    fn format_string_value(column: &str, operator: &FilterOperator, value: Option<&str>) -> String {
         match value {
             Some(v) => format!(
-                "{} {} '{}'",
+                "{} {} {}",
                 column,
                 operator.as_sql(),
-                v.replace('\'', "''")
+                values.raw(v, param(v))
             ),
-            None => format!("{} {}", column, operator.as_sql()),
+            None => Self::format_null(column, operator),
+        }
+    }
+
+    /// A condition without a value, where the distinct operators compare against NULL
+    fn format_null(column: &str, operator: &FilterOperator) -> String {
+        match operator {
+            FilterOperator::IsDistinctFrom => format!("{} IS NOT NULL", column),
+            FilterOperator::IsNotDistinctFrom => format!("{} IS NULL", column),
+            _ => format!("{} {}", column, operator.as_sql()),
+        }
+    }
+
+    fn format_string_value(
+        column: &str,
+        operator: &FilterOperator,
+        value: Option<&str>,
+        cast: Option<&str>,
+        values: &mut SqlValues,
+    ) -> String {
+        match value {
+            Some(v) => format!("{} {} {}", column, operator.as_sql(), values.text(v, cast)),
+            None => Self::format_null(column, operator),
         }
     }
 
     pub fn to_sql(&self, case_insensitive: bool) -> Result<String> {
+        self.render(case_insensitive, &mut SqlValues::inline())
+    }
+
+    /// Renders the condition with numbered placeholders, binding its values to `params`
+    pub fn to_sql_with_params(
+        &self,
+        case_insensitive: bool,
+        params: &mut QueryParams,
+    ) -> Result<String> {
+        self.render(case_insensitive, &mut SqlValues::bound(params))
+    }
+
+    pub(crate) fn render(&self, case_insensitive: bool, values: &mut SqlValues) -> Result<String> {
         match self {
             FilterCondition::TextValue {
                 column,
@@ -410,94 +1202,473 @@ impl FilterCondition {
                 value,
             } => match value {
                 Some(v) => {
-                    let formatted_value = operator.format_value(&v.replace('\'', "''"));
+                    let formatted_value = values.text(&operator.format_value(v), None);
                     if case_insensitive {
                         Ok(format!(
-                            "LOWER({}) {} LOWER('{}')",
+                            "LOWER({}) {} LOWER({})",
                             column,
                             operator.as_sql(),
                             formatted_value
                         ))
                     } else {
                         Ok(format!(
-                            "{} {} '{}'",
+                            "{} {} {}",
                             column,
                             operator.as_sql(),
                             formatted_value
                         ))
                     }
                 }
-                None => Ok(format!("{} {}", column, operator.as_sql())),
-            },
-
-            FilterCondition::DateRange { column, range_type } => match range_type {
-                DateRangeType::Exact(timestamp) => Ok(format!("{} = '{}'", column, timestamp)),
-                DateRangeType::DateOnly(date) => Ok(format!(
-                    "{} >= '{} 00:00:00' AND {} < ('{}')::date + interval '1 day'",
-                    column, date, column, date
-                )),
-                DateRangeType::Range { start, end } => {
-                    Ok(format!("{} BETWEEN '{}' AND '{}'", column, start, end))
-                }
-                DateRangeType::Relative(expr) => Ok(format!("{} {} {}", column, ">", expr)),
+                None => Ok(Self::format_null(column, operator)),
             },
 
-            FilterCondition::ArrayContains {
+            // json has no equality operator, so both sides are compared as jsonb
+            FilterCondition::JsonValue {
+                column,
+                operator,
+                value,
+            } => match value {
+                Some(v) => Ok(format!(
+                    "{}::jsonb {} {}",
+                    column,
+                    operator.as_sql(),
+                    values.cast(v, "jsonb")
+                )),
+                None => Ok(Self::format_null(column, operator)),
+            },
+
+            FilterCondition::CiTextValue {
+                column,
+                operator,
+                value,
+            } => match value {
+                // Bound values are cast, as citext = text compares as text
+                Some(v) => Ok(format!(
+                    "{} {} {}",
+                    column,
+                    operator.as_sql(),
+                    values.text(&operator.format_value(v), self.value_cast())
+                )),
+                None => Ok(Self::format_null(column, operator)),
+            },
+
+            FilterCondition::UnaccentText {
+                column,
+                operator,
+                value,
+                function,
+            } => {
+                if !is_function_name(function) {
+                    return Err(eyre::eyre!("Invalid unaccent function: {}", function));
+                }
+                let formatted_value = values.text(&operator.format_value(value), None);
+                if case_insensitive {
+                    Ok(format!(
+                        "LOWER({}({})) {} LOWER({}({}))",
+                        function,
+                        column,
+                        operator.as_sql(),
+                        function,
+                        formatted_value
+                    ))
+                } else {
+                    Ok(format!(
+                        "{}({}) {} {}({})",
+                        function,
+                        column,
+                        operator.as_sql(),
+                        function,
+                        formatted_value
+                    ))
+                }
+            }
+
+            FilterCondition::DateRange {
+                column,
+                range_type,
+                column_type,
+                time_zone,
+            } => match range_type {
+                DateRangeType::Exact(timestamp) => Ok(format!(
+                    "{} = {}",
+                    column,
+                    values.text(timestamp, Some(column_type.cast()))
+                )),
+                DateRangeType::DateOnly(date) if *column_type == DateColumnType::Date => {
+                    Ok(format!("{} = {}", column, values.text(date, Some("date"))))
+                }
+                DateRangeType::DateOnly(date) if *column_type == DateColumnType::TimestampTz => {
+                    match time_zone {
+                        // Local midnight of both days in the zone, so that a day lasts 23 or
+                        // 25 hours across a DST change
+                        Some(zone) => {
+                            let start = values.cast(date, "timestamp");
+                            let start_zone = values.text(zone, Some("text"));
+                            let end = values.cast(date, "timestamp");
+                            let end_zone = values.text(zone, Some("text"));
+                            Ok(format!(
+                                "{} >= {} AT TIME ZONE {} AND {} < ({} + interval '1 day') AT TIME ZONE {}",
+                                column, start, start_zone, column, end, end_zone
+                            ))
+                        }
+                        // Midnight in the session time zone, so that days follow DST changes
+                        None => {
+                            let start = values.cast(date, "timestamptz");
+                            let end = values.cast(date, "timestamptz");
+                            Ok(format!(
+                                "{} >= {} AND {} < {} + interval '1 day'",
+                                column, start, column, end
+                            ))
+                        }
+                    }
+                }
+                DateRangeType::DateOnly(date) => {
+                    let (start, end) = if values.is_bound() {
+                        (
+                            values.text(date, Some("date")),
+                            values.text(date, Some("date")),
+                        )
+                    } else {
+                        (
+                            values.text(&format!("{} 00:00:00", date), None),
+                            format!("({})::date", values.text(date, None)),
+                        )
+                    };
+                    Ok(format!(
+                        "{} >= {} AND {} < {} + interval '1 day'",
+                        column, start, column, end
+                    ))
+                }
+                DateRangeType::Range { start, end } => {
+                    let whole_end_day = *column_type != DateColumnType::Date && dates::is_date(end);
+                    // Bounds without an offset are local times in the zone
+                    let mut bound = |value: &str| match time_zone {
+                        Some(zone)
+                            if *column_type == DateColumnType::TimestampTz
+                                && !dates::has_offset(value) =>
+                        {
+                            let local = values.cast(value, "timestamp");
+                            format!("{} AT TIME ZONE {}", local, values.text(zone, Some("text")))
+                        }
+                        _ => values.text(value, Some(column_type.cast())),
+                    };
+                    let start = bound(start);
+                    // A date-only end includes the whole of that day, as in `DateOnly`
+                    if whole_end_day {
+                        let end = match time_zone {
+                            Some(zone) if *column_type == DateColumnType::TimestampTz => {
+                                let end = values.cast(end, "timestamp");
+                                let zone = values.text(zone, Some("text"));
+                                format!("({} + interval '1 day') AT TIME ZONE {}", end, zone)
+                            }
+                            _ if *column_type == DateColumnType::TimestampTz => {
+                                format!("{} + interval '1 day'", values.cast(end, "timestamptz"))
+                            }
+                            _ => format!("{} + interval '1 day'", values.cast(end, "date")),
+                        };
+                        return Ok(format!("{} >= {} AND {} < {}", column, start, column, end));
+                    }
+                    let end = bound(end);
+                    Ok(format!("{} BETWEEN {} AND {}", column, start, end))
+                }
+                DateRangeType::Relative(relative) => {
+                    let clock = Clock {
+                        now: match column_type {
+                            DateColumnType::Date => "CURRENT_DATE",
+                            DateColumnType::Timestamp => "LOCALTIMESTAMP",
+                            DateColumnType::TimestampTz => "now()",
+                        },
+                        time_zone: time_zone.as_deref(),
+                    };
+                    Ok(relative.to_sql(column, &clock, values))
+                }
+            },
+
+            FilterCondition::DateFunction {
+                column,
+                function,
+                operator,
+                values: params,
+                column_type,
+                time_zone,
+            } => {
+                let local = |values: &mut SqlValues| match time_zone {
+                    Some(zone) if *column_type == DateColumnType::TimestampTz => format!(
+                        "{} AT TIME ZONE {}",
+                        column,
+                        values.text(zone, Some("text"))
+                    ),
+                    _ => column.clone(),
+                };
+                let (function, cast) = match function {
+                    // Seconds have a fractional part, which is dropped to compare whole seconds
+                    DateFunction::Extract(DateField::Second) => (
+                        format!("FLOOR(EXTRACT(SECOND FROM {}))", local(values)),
+                        None,
+                    ),
+                    DateFunction::Extract(field) => (
+                        format!("EXTRACT({} FROM {})", field.as_str(), local(values)),
+                        None,
+                    ),
+                    DateFunction::Trunc(unit) => {
+                        let unit = values.text(unit.as_str(), Some("text"));
+                        let column = local(values);
+                        // A column converted to a time zone is a local timestamp
+                        let cast = match time_zone {
+                            Some(_) if *column_type == DateColumnType::TimestampTz => "timestamp",
+                            _ => column_type.cast(),
+                        };
+                        (format!("date_trunc({}, {})", unit, column), Some(cast))
+                    }
+                };
+
+                let params = params
+                    .iter()
+                    .map(|param| values.param(param, cast))
+                    .collect::<Vec<_>>();
+                match (operator, params.as_slice()) {
+                    (FilterOperator::In | FilterOperator::NotIn, [_, ..]) => Ok(format!(
+                        "{} {} ({})",
+                        function,
+                        operator.as_sql(),
+                        params.join(", ")
+                    )),
+                    (FilterOperator::Between | FilterOperator::NotBetween, [start, end]) => Ok(
+                        format!("{} {} {} AND {}", function, operator.as_sql(), start, end),
+                    ),
+                    (
+                        FilterOperator::Equal
+                        | FilterOperator::NotEqual
+                        | FilterOperator::IsDistinctFrom
+                        | FilterOperator::IsNotDistinctFrom
+                        | FilterOperator::GreaterThan
+                        | FilterOperator::GreaterThanOrEqual
+                        | FilterOperator::LessThan
+                        | FilterOperator::LessThanOrEqual,
+                        [value],
+                    ) => Ok(format!("{} {} {}", function, operator.as_sql(), value)),
+                    _ => Err(eyre::eyre!(
+                        "Invalid {} values for {} {}",
+                        operator.as_sql(),
+                        function,
+                        column
+                    )),
+                }
+            }
+
+            FilterCondition::ArrayContains {
                 column,
                 operator: _,
                 value,
             } => {
-                let values = value
+                let items = value
                     .split(',')
-                    .map(|v| format!("'{}'", v.trim().replace('\'', "''")))
-                    .collect::<Vec<_>>()
-                    .join(",");
-                Ok(format!("{} @> ARRAY[{}]::text[]", column, values))
+                    .map(|v| v.trim().to_string())
+                    .collect::<Vec<_>>();
+                Ok(format!("{} @> {}", column, values.text_array(&items)))
             }
             FilterCondition::ArrayOverlap {
                 column,
                 operator: _,
-                values,
-            } => {
-                let formatted_values = values
-                    .iter()
-                    .map(|v| format!("'{}'", v.replace('\'', "''")))
-                    .collect::<Vec<_>>()
-                    .join(",");
-                Ok(format!("{} && ARRAY[{}]::text[]", column, formatted_values))
-            }
+                values: items,
+            } => Ok(format!("{} && {}", column, values.text_array(items))),
 
             // Never apply case sensitivity to non-text types
             FilterCondition::UuidValue {
                 column,
                 operator,
                 value,
-            } => match value {
-                Some(v) => Ok(format!(
-                    "{} {} '{}'",
-                    column,
-                    operator.as_sql(),
-                    v.replace('\'', "''")
-                )),
-                None => Ok(format!("{} {}", column, operator.as_sql())),
-            },
+            } => Ok(Self::format_string_value(
+                column,
+                operator,
+                value.as_deref(),
+                Some("uuid"),
+                values,
+            )),
 
             FilterCondition::InValues {
                 column,
                 operator,
-                values,
+                values: items,
             } => {
-                let formatted_values = values
+                // Only known text columns are lowered; citext folds case on its own
+                let cast = values.column_cast(column);
+                let lower = case_insensitive && values.is_text_column(column);
+                let formatted_values = items
                     .iter()
-                    .map(|v| format!("'{}'", v.replace('\'', "''")))
+                    .map(|v| {
+                        let value = values.text(v, cast);
+                        if lower {
+                            format!("LOWER({})", value)
+                        } else {
+                            value
+                        }
+                    })
                     .collect::<Vec<_>>()
                     .join(", ");
-                Ok(format!(
-                    "{} {} ({})",
-                    column,
-                    operator.as_sql(),
-                    formatted_values
-                ))
+                if lower {
+                    Ok(format!(
+                        "LOWER({}) {} ({})",
+                        column,
+                        operator.as_sql(),
+                        formatted_values
+                    ))
+                } else {
+                    Ok(format!(
+                        "{} {} ({})",
+                        column,
+                        operator.as_sql(),
+                        formatted_values
+                    ))
+                }
+            }
+
+            FilterCondition::Related {
+                table,
+                keys,
+                rows,
+                filter,
+                column_defs,
+            } => {
+                if keys.is_empty() {
+                    return Err(eyre::eyre!("Relation {} has no join keys", table));
+                }
+                let mut conditions = keys
+                    .iter()
+                    .map(|(related_column, column)| format!("{} = {}", related_column, column))
+                    .collect::<Vec<_>>();
+                let filter_sql = match filter {
+                    Some(filter) => values.with_column_defs(column_defs, |values| {
+                        filter.render(case_insensitive, values)
+                    })?,
+                    None => String::new(),
+                };
+
+                match rows {
+                    RelatedRows::Any | RelatedRows::None => {
+                        if !filter_sql.is_empty() {
+                            conditions.push(filter_sql);
+                        }
+                        let exists = if *rows == RelatedRows::None {
+                            "NOT EXISTS"
+                        } else {
+                            "EXISTS"
+                        };
+                        Ok(format!(
+                            "{} (SELECT 1 FROM {} WHERE {})",
+                            exists,
+                            table,
+                            conditions.join(" AND ")
+                        ))
+                    }
+                    // No related row fails the filter; rows where it is NULL fail it too
+                    RelatedRows::All => {
+                        if filter_sql.is_empty() {
+                            return Err(eyre::eyre!("ALL requires a filter on {}", table));
+                        }
+                        let filter_sql = match filter.as_deref() {
+                            Some(FilterExpression::Group { .. }) => filter_sql,
+                            _ => format!("({})", filter_sql),
+                        };
+                        conditions.push(format!("{} IS NOT TRUE", filter_sql));
+                        Ok(format!(
+                            "NOT EXISTS (SELECT 1 FROM {} WHERE {})",
+                            table,
+                            conditions.join(" AND ")
+                        ))
+                    }
+                    RelatedRows::Count(operator, count) => {
+                        if crate::relation::count_operator(operator.as_sql()).is_none() {
+                            return Err(eyre::eyre!("Invalid count operator: {}", operator));
+                        }
+                        if !filter_sql.is_empty() {
+                            conditions.push(filter_sql);
+                        }
+                        Ok(format!(
+                            "(SELECT COUNT(*) FROM {} WHERE {}) {} {}",
+                            table,
+                            conditions.join(" AND "),
+                            operator.as_sql(),
+                            values.raw(count, SqlParam::BigInt(*count))
+                        ))
+                    }
+                }
+            }
+
+            FilterCondition::Similarity {
+                column,
+                operator,
+                value,
+                threshold,
+            } => {
+                let value = values.text(value, None);
+                // Word similarity compares the value with the words of the column
+                match (operator, threshold) {
+                    (FilterOperator::Similar, None) => Ok(format!("{} % {}", column, value)),
+                    (FilterOperator::WordSimilar, None) => Ok(format!("{} <% {}", value, column)),
+                    (FilterOperator::Similar, Some(threshold)) => Ok(format!(
+                        "similarity({}, {}) >= {}",
+                        column,
+                        value,
+                        values.param(&SqlParam::DoublePrecision(*threshold), None)
+                    )),
+                    (FilterOperator::WordSimilar, Some(threshold)) => Ok(format!(
+                        "word_similarity({}, {}) >= {}",
+                        value,
+                        column,
+                        values.param(&SqlParam::DoublePrecision(*threshold), None)
+                    )),
+                    _ => Err(eyre::eyre!(
+                        "Invalid similarity operator for {}: {}",
+                        column,
+                        operator
+                    )),
+                }
+            }
+
+            FilterCondition::Between {
+                column,
+                operator,
+                start,
+                end,
+                bounds,
+                cast,
+            } => {
+                let start = values.param(start, *cast);
+                let end = values.param(end, *cast);
+                let symmetric = matches!(
+                    operator,
+                    FilterOperator::BetweenSymmetric | FilterOperator::NotBetweenSymmetric
+                );
+                match (bounds, symmetric) {
+                    (BetweenBounds::Inclusive, _) => Ok(format!(
+                        "{} {} {} AND {}",
+                        column,
+                        operator.as_sql(),
+                        start,
+                        end
+                    )),
+                    (_, true) => Err(eyre::eyre!(
+                        "BETWEEN SYMMETRIC requires inclusive bounds for {}",
+                        column
+                    )),
+                    _ => {
+                        let range = format!(
+                            "({} {} {} AND {} {} {})",
+                            column,
+                            bounds.start_operator(),
+                            start,
+                            column,
+                            bounds.end_operator(),
+                            end
+                        );
+                        if *operator == FilterOperator::NotBetween {
+                            Ok(format!("NOT {}", range))
+                        } else {
+                            Ok(range)
+                        }
+                    }
+                }
             }
 
             // Numeric types
@@ -505,29 +1676,72 @@ impl FilterCondition {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_value(column, operator, *value)),
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::SmallInt,
+                values,
+            )),
             FilterCondition::IntegerValue {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_value(column, operator, *value)),
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::Integer,
+                values,
+            )),
             FilterCondition::BigIntValue {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_value(column, operator, *value)),
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::BigInt,
+                values,
+            )),
             FilterCondition::RealValue {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_value(column, operator, *value)),
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::Real,
+                values,
+            )),
             FilterCondition::DoublePrecisionValue {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_value(column, operator, *value)),
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::DoublePrecision,
+                values,
+            )),
+
+            // Boolean values
+            FilterCondition::BooleanValue {
+                column,
+                operator,
+                value,
+            } => Ok(Self::format_value(
+                column,
+                operator,
+                *value,
+                SqlParam::Boolean,
+                values,
+            )),
 
-            // Date/Time types
+            // Date/Time and other types are bound as text and cast to the column type
             FilterCondition::DateValue {
                 column,
                 operator,
@@ -557,21 +1771,8 @@ impl FilterCondition {
                 column,
                 operator,
                 value,
-            } => Ok(Self::format_string_value(
-                column,
-                operator,
-                value.as_deref(),
-            )),
-
-            // Boolean values
-            FilterCondition::BooleanValue {
-                column,
-                operator,
-                value,
-            } => Ok(Self::format_value(column, operator, *value)),
-
-            // Other types
-            FilterCondition::JsonValue {
+            }
+            | FilterCondition::NumericValue {
                 column,
                 operator,
                 value,
@@ -619,9 +1820,37 @@ impl FilterCondition {
                 column,
                 operator,
                 value.as_deref(),
+                self.value_cast(),
+                values,
             )),
         }
     }
+
+    /// The SQL type a text parameter is cast to when bound for this condition
+    fn value_cast(&self) -> Option<&'static str> {
+        match self {
+            FilterCondition::DateValue { .. } => Some("date"),
+            FilterCondition::TimeValue { .. } => Some("time"),
+            FilterCondition::TimeTzValue { .. } => Some("timetz"),
+            FilterCondition::TimestampValue { .. } => Some("timestamp"),
+            FilterCondition::TimestampTzValue { .. } => Some("timestamptz"),
+            FilterCondition::IntervalValue { .. } => Some("interval"),
+            FilterCondition::NumericValue { .. } => Some("numeric"),
+            FilterCondition::JsonValue { .. } => Some("json"),
+            FilterCondition::JsonbValue { .. } => Some("jsonb"),
+            FilterCondition::InetValue { .. } => Some("inet"),
+            FilterCondition::CidrValue { .. } => Some("cidr"),
+            FilterCondition::MacAddrValue { .. } => Some("macaddr"),
+            FilterCondition::MacAddr8Value { .. } => Some("macaddr8"),
+            FilterCondition::UuidValue { .. } => Some("uuid"),
+            FilterCondition::ByteAValue { .. } => Some("bytea"),
+            FilterCondition::MoneyValue { .. } => Some("money"),
+            FilterCondition::XmlValue { .. } => Some("xml"),
+            FilterCondition::CiTextValue { .. } => Some("citext"),
+            _ => None,
+        }
+    }
+
     pub fn text(column: &str, operator: FilterOperator, value: Option<&str>) -> Self {
         FilterCondition::TextValue {
             column: column.to_string(),
@@ -638,6 +1867,14 @@ impl FilterCondition {
         }
     }
 
+    pub fn citext(column: &str, operator: FilterOperator, value: Option<&str>) -> Self {
+        FilterCondition::CiTextValue {
+            column: column.to_string(),
+            operator,
+            value: value.map(ToString::to_string),
+        }
+    }
+
     pub fn double(column: &str, operator: FilterOperator, value: Option<f64>) -> Self {
         FilterCondition::DoublePrecisionValue {
             column: column.to_string(),
@@ -678,6 +1915,35 @@ impl FilterCondition {
         }
     }
 
+    /// An inclusive range; use `ColumnDef::to_between_condition` for other bounds and for
+    /// date/time columns, whose text bounds need a cast when bound
+    pub fn between(column: &str, operator: FilterOperator, start: SqlParam, end: SqlParam) -> Self {
+        FilterCondition::Between {
+            column: column.to_string(),
+            operator,
+            start,
+            end,
+            bounds: BetweenBounds::Inclusive,
+            cast: None,
+        }
+    }
+
+    /// A trigram match of `value`, `Similar` or `WordSimilar`; `threshold` overrides the
+    /// similarity threshold of `pg_trgm`
+    pub fn similarity(
+        column: &str,
+        operator: FilterOperator,
+        value: &str,
+        threshold: Option<f64>,
+    ) -> Self {
+        FilterCondition::Similarity {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+            threshold,
+        }
+    }
+
     pub fn uuid(column: &str, operator: FilterOperator, value: Option<&str>) -> Self {
         FilterCondition::UuidValue {
             column: column.to_string(),
@@ -687,33 +1953,184 @@ impl FilterCondition {
     }
 
     pub fn date_exact(column: &str, timestamp: &str) -> Self {
-        FilterCondition::DateRange {
-            column: column.to_string(),
-            range_type: DateRangeType::Exact(timestamp.to_string()),
-        }
+        Self::date_condition(
+            column,
+            DateColumnType::Timestamp,
+            DateRangeType::Exact(timestamp.to_string()),
+        )
     }
 
     pub fn date_only(column: &str, date: &str) -> Self {
-        FilterCondition::DateRange {
-            column: column.to_string(),
-            range_type: DateRangeType::DateOnly(date.to_string()),
-        }
+        Self::date_condition(
+            column,
+            DateColumnType::Timestamp,
+            DateRangeType::DateOnly(date.to_string()),
+        )
     }
 
     pub fn date_range(column: &str, start: &str, end: &str) -> Self {
-        FilterCondition::DateRange {
-            column: column.to_string(),
-            range_type: DateRangeType::Range {
+        Self::date_condition(
+            column,
+            DateColumnType::Timestamp,
+            DateRangeType::Range {
                 start: start.to_string(),
                 end: end.to_string(),
             },
-        }
+        )
+    }
+
+    pub fn relative_date(column: &str, relative: RelativeDate) -> Self {
+        Self::date_condition(
+            column,
+            DateColumnType::Timestamp,
+            DateRangeType::Relative(relative),
+        )
     }
 
-    pub fn relative_date(column: &str, expr: &str) -> Self {
+    /// A date range condition on a column of any date type; the other date constructors assume
+    /// a `timestamp` column, and `ColumnDef::to_date_condition` also validates the values
+    pub fn date_condition(
+        column: &str,
+        column_type: DateColumnType,
+        range_type: DateRangeType,
+    ) -> Self {
         FilterCondition::DateRange {
             column: column.to_string(),
-            range_type: DateRangeType::Relative(expr.to_string()),
+            range_type,
+            column_type,
+            time_zone: None,
+        }
+    }
+
+    /// The condition with the days of a `TimestampTz` date range, or the column of a date
+    /// function, taken in `time_zone`, unless it already has a time zone
+    pub fn in_time_zone(&self, time_zone: &str) -> FilterCondition {
+        let mut condition = self.clone();
+        if let FilterCondition::Related {
+            filter: Some(filter),
+            ..
+        } = &mut condition
+        {
+            **filter = filter.in_time_zone(time_zone);
+        }
+        if let FilterCondition::DateRange {
+            column_type: DateColumnType::TimestampTz,
+            time_zone: zone @ None,
+            ..
+        }
+        | FilterCondition::DateFunction {
+            column_type: DateColumnType::TimestampTz,
+            time_zone: zone @ None,
+            ..
+        } = &mut condition
+        {
+            *zone = Some(time_zone.to_string());
+        }
+        condition
+    }
+
+    /// The condition comparing text with accents stripped from both sides by `function`, such
+    /// as `unaccent`
+    ///
+    /// Only text, varchar and char conditions with a value change. `function` may be schema
+    /// qualified, e.g. `public.immutable_unaccent`.
+    pub fn unaccent(&self, function: &str) -> FilterCondition {
+        match self {
+            FilterCondition::TextValue {
+                column,
+                operator,
+                value: Some(value),
+            }
+            | FilterCondition::VarcharValue {
+                column,
+                operator,
+                value: Some(value),
+            }
+            | FilterCondition::CharValue {
+                column,
+                operator,
+                value: Some(value),
+            } => FilterCondition::UnaccentText {
+                column: column.clone(),
+                operator: operator.clone(),
+                value: value.clone(),
+                function: function.to_string(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// The condition as an expression that also matches NULL values for `!=` and `NOT IN`
+    pub fn null_safe_inequality(&self) -> FilterExpression {
+        let mut condition = self.clone();
+        match &mut condition {
+            FilterCondition::InValues {
+                column,
+                operator: FilterOperator::NotIn,
+                ..
+            } => {
+                let is_null = FilterCondition::text(column, FilterOperator::IsNull, None);
+                FilterExpression::or(vec![
+                    FilterExpression::Condition(condition),
+                    FilterExpression::Condition(is_null),
+                ])
+            }
+            FilterCondition::Related {
+                filter: Some(filter),
+                ..
+            } => {
+                **filter = filter.null_safe_inequality();
+                FilterExpression::Condition(condition)
+            }
+            _ => {
+                if let Some(operator) = condition.operator_mut() {
+                    if *operator == FilterOperator::NotEqual {
+                        *operator = FilterOperator::IsDistinctFrom;
+                    }
+                }
+                FilterExpression::Condition(condition)
+            }
+        }
+    }
+
+    /// The operator of the condition, or `None` for date ranges and related rows
+    pub fn operator_mut(&mut self) -> Option<&mut FilterOperator> {
+        match self {
+            FilterCondition::TextValue { operator, .. }
+            | FilterCondition::VarcharValue { operator, .. }
+            | FilterCondition::CharValue { operator, .. }
+            | FilterCondition::CiTextValue { operator, .. }
+            | FilterCondition::UnaccentText { operator, .. }
+            | FilterCondition::InValues { operator, .. }
+            | FilterCondition::Between { operator, .. }
+            | FilterCondition::Similarity { operator, .. }
+            | FilterCondition::DateFunction { operator, .. }
+            | FilterCondition::SmallIntValue { operator, .. }
+            | FilterCondition::IntegerValue { operator, .. }
+            | FilterCondition::BigIntValue { operator, .. }
+            | FilterCondition::RealValue { operator, .. }
+            | FilterCondition::DoublePrecisionValue { operator, .. }
+            | FilterCondition::NumericValue { operator, .. }
+            | FilterCondition::DateValue { operator, .. }
+            | FilterCondition::TimeValue { operator, .. }
+            | FilterCondition::TimeTzValue { operator, .. }
+            | FilterCondition::TimestampValue { operator, .. }
+            | FilterCondition::TimestampTzValue { operator, .. }
+            | FilterCondition::IntervalValue { operator, .. }
+            | FilterCondition::BooleanValue { operator, .. }
+            | FilterCondition::InetValue { operator, .. }
+            | FilterCondition::CidrValue { operator, .. }
+            | FilterCondition::MacAddrValue { operator, .. }
+            | FilterCondition::MacAddr8Value { operator, .. }
+            | FilterCondition::UuidValue { operator, .. }
+            | FilterCondition::JsonValue { operator, .. }
+            | FilterCondition::JsonbValue { operator, .. }
+            | FilterCondition::ArrayContains { operator, .. }
+            | FilterCondition::ArrayOverlap { operator, .. }
+            | FilterCondition::ByteAValue { operator, .. }
+            | FilterCondition::MoneyValue { operator, .. }
+            | FilterCondition::XmlValue { operator, .. } => Some(operator),
+            FilterCondition::DateRange { .. } | FilterCondition::Related { .. } => None,
         }
     }
 }
@@ -731,6 +2148,14 @@ impl fmt::Display for FilterCondition {
 pub struct FilterBuilder {
     pub root: Option<FilterExpression>,
     pub case_insensitive: bool,
+    pub null_safe_inequality: bool,
+    pub time_zone: Option<String>,
+    /// Columns whose text conditions ignore accents
+    pub unaccent_columns: Vec<String>,
+    /// Function stripping accents for `unaccent_columns`, `unaccent` by default
+    pub unaccent_function: String,
+    /// Definitions of the columns of `with_column_defs`, by column name
+    column_defs: HashMap<String, ColumnDef>,
 }
 
 impl FilterBuilder {
@@ -738,6 +2163,11 @@ impl FilterBuilder {
         Self {
             root: None,
             case_insensitive: false,
+            null_safe_inequality: false,
+            time_zone: None,
+            unaccent_columns: Vec::new(),
+            unaccent_function: "unaccent".to_string(),
+            column_defs: HashMap::new(),
         }
     }
 
@@ -746,25 +2176,72 @@ impl FilterBuilder {
         self
     }
 
-    pub fn add_condition(self, condition: FilterCondition) -> Self {
-        self.add_expression(FilterExpression::Condition(condition))
+    /// Makes `!=` and `NOT IN` conditions also match rows where the column is NULL
+    ///
+    /// See `FilterExpression::null_safe_inequality`.
+    pub fn null_safe_inequality(mut self, value: bool) -> Self {
+        self.null_safe_inequality = value;
+        self
     }
 
-    pub fn add_expression(mut self, expression: FilterExpression) -> Self {
-        match &self.root {
-            None => {
-                self.root = Some(expression);
-            }
-            Some(existing) => {
-                self.root = Some(FilterExpression::Group {
-                    operator: LogicalOperator::And,
-                    expressions: vec![existing.clone(), expression],
+    /// Takes the days of date filters on `TimestampTz` columns in an IANA time zone such as
+    /// `Australia/Sydney`, instead of the session time zone
+    ///
+    /// The name is checked for its form when the filter is built, but whether the zone exists is
+    /// only checked by PostgreSQL when the query runs. See `FilterExpression::in_time_zone`.
+    pub fn time_zone(mut self, value: impl Into<String>) -> Self {
+        self.time_zone = Some(value.into());
+        self
+    }
+
+    /// Makes text conditions on `columns` ignore accents, so `Zoe` matches `Zoë`
+    ///
+    /// Needs the `unaccent` extension. See `FilterExpression::unaccent`.
+    pub fn unaccent<S: AsRef<str>>(mut self, columns: &[S]) -> Self {
+        self.unaccent_columns = columns.iter().map(|c| c.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Strips accents with `function` instead of `unaccent`, e.g. an immutable wrapper that an
+    /// expression index can use
+    pub fn unaccent_function(mut self, function: impl Into<String>) -> Self {
+        self.unaccent_function = function.into();
+        self
+    }
+
+    /// Describes the filtered columns, so that case-insensitive matching only lowers the IN
+    /// lists of text columns, and bound IN list values are cast to the type of their column
+    ///
+    /// `from_json_filters` and `FilteringOptions` pass their column definitions.
+    pub fn with_column_defs(mut self, column_defs: &HashMap<&str, ColumnDef>) -> Self {
+        self.column_defs = by_column_name(column_defs.values());
+        self
+    }
+
+    pub fn add_condition(self, condition: FilterCondition) -> Self {
+        self.add_expression(FilterExpression::Condition(condition))
+    }
+
+    pub fn add_expression(mut self, expression: FilterExpression) -> Self {
+        match &self.root {
+            None => {
+                self.root = Some(expression);
+            }
+            Some(existing) => {
+                self.root = Some(FilterExpression::Group {
+                    operator: LogicalOperator::And,
+                    expressions: vec![existing.clone(), expression],
                 });
             }
         }
         self
     }
 
+    /// Adds the negation of `expression`
+    pub fn not(self, expression: FilterExpression) -> Self {
+        self.add_expression(FilterExpression::negate(expression))
+    }
+
     pub fn group(mut self, operator: LogicalOperator, expressions: Vec<FilterExpression>) -> Self {
         let group = FilterExpression::Group {
             operator,
@@ -788,108 +2265,333 @@ impl FilterBuilder {
         filters: &[JsonFilter],
         case_insensitive: bool,
         column_defs: &HashMap<&str, ColumnDef>,
+    ) -> Result<Self> {
+        Self::from_json_filters_with_relations(
+            filters,
+            case_insensitive,
+            column_defs,
+            &HashMap::new(),
+        )
+    }
+
+    /// Like `from_json_filters`, also filtering by the rows of `relations`, as named in
+    /// filters such as `items.sku`; see the `relation` module
+    pub fn from_json_filters_with_relations(
+        filters: &[JsonFilter],
+        case_insensitive: bool,
+        column_defs: &HashMap<&str, ColumnDef>,
+        relations: &HashMap<&str, Relation>,
     ) -> Result<Self> {
         if filters.is_empty() {
             return Ok(Self::new());
         }
 
-        fn create_condition(
-            filter: &JsonFilter,
+        fn string_condition(
+            column: &str,
+            operator: &str,
+            value: &str,
             column_defs: &HashMap<&str, ColumnDef>,
-        ) -> FilterCondition {
-            match column_defs.get(filter.n.as_str()) {
-                Some(ColumnDef::TextArray(_)) => match filter.f.to_uppercase().as_str() {
+        ) -> Result<FilterCondition> {
+            let condition = match column_defs.get(column) {
+                Some(ColumnDef::TextArray(_)) => match operator.to_uppercase().as_str() {
                     "CONTAINS" => FilterCondition::ArrayContains {
-                        column: filter.n.clone(),
+                        column: column.to_string(),
                         operator: FilterOperator::Contains,
-                        value: filter.v.clone(),
+                        value: value.to_string(),
                     },
                     "OVERLAPS" => FilterCondition::ArrayOverlap {
-                        column: filter.n.clone(),
+                        column: column.to_string(),
                         operator: FilterOperator::Overlaps,
-                        values: filter.v.split(',').map(|s| s.trim().to_string()).collect(),
+                        values: value.split(',').map(|s| s.trim().to_string()).collect(),
                     },
                     _ => FilterCondition::ArrayContains {
-                        column: filter.n.clone(),
+                        column: column.to_string(),
                         operator: FilterOperator::Contains,
-                        value: filter.v.clone(),
+                        value: value.to_string(),
                     },
                 },
-                Some(ColumnDef::Timestamp(name)) => {
+                Some(
+                    column_def @ (ColumnDef::Date(_)
+                    | ColumnDef::Timestamp(_)
+                    | ColumnDef::TimestampTz(_)),
+                ) => {
                     // Handle special date filter formats
-                    match filter.f.to_uppercase().as_str() {
-                        "DATE_ONLY" => FilterCondition::date_only(name, &filter.v),
-                        "DATE_RANGE" => {
-                            // Expect format: "start,end"
-                            let parts: Vec<&str> = filter.v.split(',').collect();
-                            if parts.len() == 2 {
-                                FilterCondition::date_range(name, parts[0], parts[1])
-                            } else {
-                                FilterCondition::date_exact(name, &filter.v)
-                            }
+                    let range_type = match operator.to_uppercase().as_str() {
+                        "DATE_ONLY" => DateRangeType::DateOnly(value.to_string()),
+                        // Expect format: "start,end"
+                        "DATE_RANGE" => match value.split(',').collect::<Vec<_>>().as_slice() {
+                            [start, end] => DateRangeType::Range {
+                                start: start.trim().to_string(),
+                                end: end.trim().to_string(),
+                            },
+                            _ => DateRangeType::Exact(value.to_string()),
+                        },
+                        "RELATIVE" => DateRangeType::Relative(value.parse()?),
+                        // Standard operators compare the column with a validated value
+                        _ => {
+                            return column_def
+                                .to_filter_condition(parse_operator(operator).as_sql(), value)
                         }
-                        "RELATIVE" => FilterCondition::relative_date(name, &filter.v),
-                        // Default timestamp handling for standard operators
-                        _ => FilterCondition::timestamp(
-                            name,
-                            parse_operator(&filter.f),
-                            Some(&filter.v),
-                        ),
-                    }
+                    };
+                    return column_def.to_date_condition(range_type);
                 }
                 Some(ColumnDef::Uuid(_)) => {
-                    FilterCondition::uuid(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                    FilterCondition::uuid(column, parse_operator(operator), Some(value))
                 }
                 Some(ColumnDef::Integer(_)) => {
-                    if let Ok(num) = filter.v.parse::<i32>() {
-                        FilterCondition::integer(&filter.n, parse_operator(&filter.f), Some(num))
+                    if let Ok(num) = value.parse::<i32>() {
+                        FilterCondition::integer(column, parse_operator(operator), Some(num))
                     } else {
-                        FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                        FilterCondition::text(column, parse_operator(operator), Some(value))
                     }
                 }
                 Some(ColumnDef::BigInt(_)) => {
-                    if let Ok(num) = filter.v.parse::<i64>() {
+                    if let Ok(num) = value.parse::<i64>() {
                         FilterCondition::BigIntValue {
-                            column: filter.n.clone(),
-                            operator: parse_operator(&filter.f),
+                            column: column.to_string(),
+                            operator: parse_operator(operator),
                             value: Some(num),
                         }
                     } else {
-                        FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                        FilterCondition::text(column, parse_operator(operator), Some(value))
                     }
                 }
                 Some(ColumnDef::DoublePrecision(_)) => {
-                    if let Ok(num) = filter.v.parse::<f64>() {
-                        FilterCondition::double(&filter.n, parse_operator(&filter.f), Some(num))
+                    if let Ok(num) = value.parse::<f64>() {
+                        FilterCondition::double(column, parse_operator(operator), Some(num))
                     } else {
-                        FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                        FilterCondition::text(column, parse_operator(operator), Some(value))
                     }
                 }
                 Some(ColumnDef::Boolean(_)) => {
-                    if let Ok(bool_val) = filter.v.parse::<bool>() {
-                        FilterCondition::boolean(
-                            &filter.n,
-                            parse_operator(&filter.f),
-                            Some(bool_val),
-                        )
+                    if let Ok(bool_val) = value.parse::<bool>() {
+                        FilterCondition::boolean(column, parse_operator(operator), Some(bool_val))
                     } else {
-                        FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                        FilterCondition::text(column, parse_operator(operator), Some(value))
                     }
                 }
                 Some(ColumnDef::Text(_)) | Some(ColumnDef::Varchar(_)) => {
-                    FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v))
+                    FilterCondition::text(column, parse_operator(operator), Some(value))
+                }
+                Some(ColumnDef::CiText(_)) => {
+                    FilterCondition::citext(column, parse_operator(operator), Some(value))
+                }
+                _ => FilterCondition::text(column, parse_operator(operator), Some(value)),
+            };
+            Ok(condition)
+        }
+
+        fn create_condition(
+            filter: &JsonFilter,
+            column_defs: &HashMap<&str, ColumnDef>,
+            relations: &HashMap<&str, Relation>,
+        ) -> Result<FilterCondition> {
+            if !column_defs.contains_key(filter.n.as_str()) {
+                if let Some((relation, column)) = find_relation(&filter.n, relations) {
+                    return relation.json_condition(filter, column);
+                }
+            }
+
+            if split_date_function(&filter.f).is_some() {
+                return match column_defs.get(filter.n.as_str()) {
+                    Some(column_def) => date_function_condition(filter, column_def),
+                    None => Err(eyre::eyre!(
+                        "{} requires a known column: {}",
+                        filter.f,
+                        filter.n
+                    )),
+                };
+            }
+
+            let operator = filter.f.to_uppercase();
+            let is_array_column = matches!(
+                column_defs.get(filter.n.as_str()),
+                Some(ColumnDef::TextArray(_))
+            );
+
+            let null_operator = match (operator.as_str(), &filter.v) {
+                ("IS NULL", _) | ("=" | "IS NOT DISTINCT FROM", Value::Null) => {
+                    Some(FilterOperator::IsNull)
+                }
+                ("IS NOT NULL", _) | ("!=" | "IS DISTINCT FROM", Value::Null) => {
+                    Some(FilterOperator::IsNotNull)
+                }
+                _ => None,
+            };
+            if let Some(null_operator) = null_operator {
+                return match column_defs.get(filter.n.as_str()) {
+                    Some(column_def) => column_def.to_filter_condition(null_operator.as_sql(), ""),
+                    None => Ok(FilterCondition::text(&filter.n, null_operator, None)),
+                };
+            }
+
+            let between = parse_operator(&operator);
+            if between.is_between() {
+                return match column_defs.get(filter.n.as_str()) {
+                    Some(column_def) => between_condition(filter, between, column_def),
+                    None => Err(eyre::eyre!(
+                        "{} requires a known column: {}",
+                        filter.f,
+                        filter.n
+                    )),
+                };
+            }
+
+            let similarity = parse_operator(&operator);
+            if matches!(
+                similarity,
+                FilterOperator::Similar | FilterOperator::WordSimilar
+            ) {
+                return match column_defs.get(filter.n.as_str()) {
+                    Some(column_def) => similarity_condition(filter, similarity, column_def),
+                    None => Err(eyre::eyre!(
+                        "{} requires a known column: {}",
+                        filter.f,
+                        filter.n
+                    )),
+                };
+            }
+
+            match &filter.v {
+                Value::Null => Err(eyre::eyre!(
+                    "{} requires a value for {}",
+                    filter.f,
+                    filter.n
+                )),
+                Value::Array(items) => {
+                    let items = items
+                        .iter()
+                        .map(scalar_text)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| {
+                            eyre::eyre!(
+                                "Expected an array of numbers, booleans or strings for {}",
+                                filter.n
+                            )
+                        })?;
+
+                    match operator.as_str() {
+                        "IN" | "NOT IN" if !items.is_empty() => {
+                            match column_defs.get(filter.n.as_str()) {
+                                Some(column_def) => {
+                                    column_def.to_in_condition(parse_operator(&operator), items)
+                                }
+                                None => Ok(FilterCondition::InValues {
+                                    column: filter.n.clone(),
+                                    operator: parse_operator(&operator),
+                                    values: items,
+                                }),
+                            }
+                        }
+                        "OVERLAPS" if is_array_column && !items.is_empty() => {
+                            Ok(FilterCondition::ArrayOverlap {
+                                column: filter.n.clone(),
+                                operator: FilterOperator::Overlaps,
+                                values: items,
+                            })
+                        }
+                        "CONTAINS" if is_array_column && !items.is_empty() => {
+                            if items.iter().any(|item| item.contains(',')) {
+                                return Err(eyre::eyre!(
+                                    "CONTAINS values cannot contain commas for {}",
+                                    filter.n
+                                ));
+                            }
+                            Ok(FilterCondition::ArrayContains {
+                                column: filter.n.clone(),
+                                operator: FilterOperator::Contains,
+                                value: items.join(","),
+                            })
+                        }
+                        "DATE_RANGE" if items.len() == 2 => {
+                            string_condition(&filter.n, "DATE_RANGE", &items.join(","), column_defs)
+                        }
+                        _ => Err(eyre::eyre!(
+                            "Unexpected array for {} {}",
+                            filter.n,
+                            filter.f
+                        )),
+                    }
+                }
+                Value::Object(range) if operator == "DATE_RANGE" => {
+                    match (
+                        range.get("start").and_then(scalar_text),
+                        range.get("end").and_then(scalar_text),
+                    ) {
+                        (Some(start), Some(end)) => string_condition(
+                            &filter.n,
+                            "DATE_RANGE",
+                            &format!("{},{}", start, end),
+                            column_defs,
+                        ),
+                        _ => Err(eyre::eyre!(
+                            "DATE_RANGE expects an object with start and end for {}",
+                            filter.n
+                        )),
+                    }
+                }
+                Value::Object(_) => Err(eyre::eyre!(
+                    "Unexpected object for {} {}",
+                    filter.n,
+                    filter.f
+                )),
+                value => match scalar_text(value) {
+                    // Comma-separated IN lists
+                    Some(text) if matches!(operator.as_str(), "IN" | "NOT IN") => {
+                        match column_defs.get(filter.n.as_str()) {
+                            Some(column_def) => column_def.to_filter_condition(&operator, &text),
+                            None => Ok(FilterCondition::InValues {
+                                column: filter.n.clone(),
+                                operator: parse_operator(&operator),
+                                values: text.split(',').map(|v| v.trim().to_string()).collect(),
+                            }),
+                        }
+                    }
+                    Some(text) => string_condition(&filter.n, &filter.f, &text, column_defs),
+                    None => Err(eyre::eyre!("Invalid value for {}", filter.n)),
+                },
+            }
+        }
+
+        // "NOT <operator>" negates any operator; NOT IN, NOT BETWEEN and NOT EXISTS keep their
+        // own rendering
+        fn create_expression(
+            filter: &JsonFilter,
+            column_defs: &HashMap<&str, ColumnDef>,
+            relations: &HashMap<&str, Relation>,
+        ) -> Result<FilterExpression> {
+            match filter.f.to_uppercase().strip_prefix("NOT ") {
+                Some(operator)
+                    if operator != "IN"
+                        && operator != "EXISTS"
+                        && !operator.starts_with("BETWEEN") =>
+                {
+                    let negated = JsonFilter {
+                        n: filter.n.clone(),
+                        f: operator.to_string(),
+                        v: filter.v.clone(),
+                        c: None,
+                    };
+                    Ok(FilterExpression::negate(FilterExpression::Condition(
+                        create_condition(&negated, column_defs, relations)?,
+                    )))
                 }
-                _ => FilterCondition::text(&filter.n, parse_operator(&filter.f), Some(&filter.v)),
+                _ => Ok(FilterExpression::Condition(create_condition(
+                    filter,
+                    column_defs,
+                    relations,
+                )?)),
             }
         }
 
         // Always start with the first filter as the base condition
-        let first_condition =
-            FilterExpression::Condition(create_condition(&filters[0], column_defs));
+        let first_condition = create_expression(&filters[0], column_defs, relations)?;
 
         // If only one filter, just return it
         if filters.len() == 1 {
-            let mut builder = Self::new().case_insensitive(case_insensitive);
+            let mut builder = Self::new()
+                .case_insensitive(case_insensitive)
+                .with_column_defs(column_defs);
             builder.root = Some(first_condition);
             return Ok(builder);
         }
@@ -899,7 +2601,7 @@ impl FilterBuilder {
         let mut current_or_group: Vec<FilterExpression> = Vec::new();
 
         for filter in &filters[1..] {
-            let condition = FilterExpression::Condition(create_condition(filter, column_defs));
+            let condition = create_expression(filter, column_defs, relations)?;
 
             match filter.c.as_deref() {
                 Some("OR") => {
@@ -935,7 +2637,9 @@ impl FilterBuilder {
             });
         }
 
-        let mut builder = Self::new().case_insensitive(case_insensitive);
+        let mut builder = Self::new()
+            .case_insensitive(case_insensitive)
+            .with_column_defs(column_defs);
 
         if result.len() == 1 {
             builder.root = result.pop();
@@ -950,10 +2654,53 @@ impl FilterBuilder {
     }
 
     pub fn build(&self) -> Result<String> {
-        match &self.root {
+        self.render(&mut SqlValues::inline())
+    }
+
+    /// Builds the WHERE clause with numbered placeholders, binding its values to `params`
+    pub fn build_with_params(&self, params: &mut QueryParams) -> Result<String> {
+        self.render(&mut SqlValues::bound(params))
+    }
+
+    /// Builds the WHERE clause as SQL pieces and the values bound between them
+    #[cfg(feature = "sqlx")]
+    pub(crate) fn build_parts(
+        &self,
+        column_defs: &HashMap<&'static str, ColumnDef>,
+    ) -> Result<Vec<SqlPart>> {
+        render_parts(column_defs, |values| self.render(values))
+    }
+
+    /// The root expression, rewritten for null-safe inequality, the time zone and accents
+    pub(crate) fn expression(&self) -> Result<Option<Cow<'_, FilterExpression>>> {
+        let root = match &self.root {
+            Some(root) => root,
+            None => return Ok(None),
+        };
+
+        let mut expression = Cow::Borrowed(root);
+        if self.null_safe_inequality {
+            expression = Cow::Owned(expression.null_safe_inequality());
+        }
+        if let Some(time_zone) = &self.time_zone {
+            if !dates::is_time_zone(time_zone) {
+                return Err(eyre::eyre!("Invalid time zone: {}", time_zone));
+            }
+            expression = Cow::Owned(expression.in_time_zone(time_zone));
+        }
+        if !self.unaccent_columns.is_empty() {
+            expression =
+                Cow::Owned(expression.unaccent(&self.unaccent_columns, &self.unaccent_function));
+        }
+        Ok(Some(expression))
+    }
+
+    fn render(&self, values: &mut SqlValues) -> Result<String> {
+        values.add_column_defs(&self.column_defs);
+        match self.expression()? {
             None => Ok(String::new()),
             Some(expression) => {
-                let sql = expression.to_sql(self.case_insensitive)?;
+                let sql = expression.render(self.case_insensitive, values)?;
                 if sql.is_empty() {
                     Ok(String::new())
                 } else {
@@ -964,23 +2711,181 @@ impl FilterBuilder {
     }
 }
 
+/// A BETWEEN condition for a JSON filter whose value is `[start, end]`, `"start,end"` or
+/// `{"start": ..., "end": ..., "bounds": "[)"}`, with inclusive bounds by default
+pub(crate) fn between_condition(
+    filter: &JsonFilter,
+    operator: FilterOperator,
+    column_def: &ColumnDef,
+) -> Result<FilterCondition> {
+    let (start, end, bounds) = match &filter.v {
+        Value::Array(items) if items.len() == 2 => {
+            (scalar_text(&items[0]), scalar_text(&items[1]), None)
+        }
+        Value::Object(range) => (
+            range.get("start").and_then(scalar_text),
+            range.get("end").and_then(scalar_text),
+            range.get("bounds"),
+        ),
+        value => match scalar_text(value)
+            .as_deref()
+            .and_then(|text| text.split_once(','))
+        {
+            Some((start, end)) => (Some(start.to_string()), Some(end.to_string()), None),
+            None => (None, None, None),
+        },
+    };
+
+    let bounds = match bounds {
+        None => BetweenBounds::Inclusive,
+        Some(Value::String(bounds)) => BetweenBounds::parse(bounds)?,
+        Some(bounds) => return Err(eyre::eyre!("Invalid bounds: {}", bounds)),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) => column_def.to_between_condition(operator, &start, &end, bounds),
+        _ => Err(eyre::eyre!(
+            "{} expects a start and an end for {}",
+            filter.f,
+            filter.n
+        )),
+    }
+}
+
+/// A trigram condition for a JSON filter whose value is the text to match, or an object with
+/// the text as `value` and an optional `threshold` between 0 and 1
+pub(crate) fn similarity_condition(
+    filter: &JsonFilter,
+    operator: FilterOperator,
+    column_def: &ColumnDef,
+) -> Result<FilterCondition> {
+    let (value, threshold) = match &filter.v {
+        Value::String(value) => (Some(value.clone()), None),
+        Value::Object(similarity) => (
+            similarity
+                .get("value")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            similarity.get("threshold"),
+        ),
+        _ => (None, None),
+    };
+
+    let threshold = match threshold {
+        None => None,
+        Some(threshold) => Some(
+            threshold
+                .as_f64()
+                .ok_or_else(|| eyre::eyre!("Invalid threshold: {}", threshold))?,
+        ),
+    };
+    match value {
+        Some(value) => column_def.to_similarity_condition(operator, &value, threshold),
+        None => Err(eyre::eyre!(
+            "{} expects a string or an object with a string value for {}",
+            filter.f,
+            filter.n
+        )),
+    }
+}
+
+/// Whether `name` is a plain or schema-qualified function name such as `public.unaccent`
+fn is_function_name(name: &str) -> bool {
+    name.len() <= 128
+        && name.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Splits an operator such as `EXTRACT(HOUR) BETWEEN` into the function and the comparison
+pub(crate) fn split_date_function(operator: &str) -> Option<(&str, &str)> {
+    let operator = operator.trim();
+    let upper = operator.to_uppercase();
+    if !upper.starts_with("EXTRACT(") && !upper.starts_with("DATE_TRUNC(") {
+        return None;
+    }
+    operator
+        .find(')')
+        .map(|end| (&operator[..=end], operator[end + 1..].trim()))
+}
+
+/// The comparison of a date function and the number of values it takes
+pub(crate) fn date_function_operator(
+    operator: &str,
+) -> Option<(FilterOperator, std::ops::RangeInclusive<usize>)> {
+    let single = 1..=1;
+    match operator.trim().to_uppercase().as_str() {
+        "=" => Some((FilterOperator::Equal, single)),
+        "!=" | "<>" => Some((FilterOperator::NotEqual, single)),
+        "IS DISTINCT FROM" => Some((FilterOperator::IsDistinctFrom, single)),
+        "IS NOT DISTINCT FROM" => Some((FilterOperator::IsNotDistinctFrom, single)),
+        ">" => Some((FilterOperator::GreaterThan, single)),
+        ">=" => Some((FilterOperator::GreaterThanOrEqual, single)),
+        "<" => Some((FilterOperator::LessThan, single)),
+        "<=" => Some((FilterOperator::LessThanOrEqual, single)),
+        "IN" => Some((FilterOperator::In, 1..=usize::MAX)),
+        "NOT IN" => Some((FilterOperator::NotIn, 1..=usize::MAX)),
+        "BETWEEN" => Some((FilterOperator::Between, 2..=2)),
+        "NOT BETWEEN" => Some((FilterOperator::NotBetween, 2..=2)),
+        _ => None,
+    }
+}
+
+/// A date function condition for a JSON filter whose value is one value, a list as an array or
+/// comma-separated string, or `[start, end]`
+pub(crate) fn date_function_condition(
+    filter: &JsonFilter,
+    column_def: &ColumnDef,
+) -> Result<FilterCondition> {
+    let (function, operator) = split_date_function(&filter.f)
+        .ok_or_else(|| eyre::eyre!("Invalid date function: {}", filter.f))?;
+    let values = match &filter.v {
+        Value::Array(items) => items.iter().map(scalar_text).collect::<Option<Vec<_>>>(),
+        value => scalar_text(value).map(|text| {
+            text.split(',')
+                .map(|item| item.trim().to_string())
+                .collect()
+        }),
+    }
+    .ok_or_else(|| eyre::eyre!("Invalid value for {} {}", filter.n, filter.f))?;
+
+    column_def.to_date_function_condition(DateFunction::parse(function)?, operator, &values)
+}
+
+/// Escapes the LIKE wildcards in a value that should match literally
+pub(crate) fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 fn parse_operator(op: &str) -> FilterOperator {
     match op.to_uppercase().as_str() {
         "LIKE" => FilterOperator::Like,
+        "ILIKE" => FilterOperator::ILike,
         "=" => FilterOperator::Equal,
         "!=" => FilterOperator::NotEqual,
+        "IS DISTINCT FROM" => FilterOperator::IsDistinctFrom,
+        "IS NOT DISTINCT FROM" => FilterOperator::IsNotDistinctFrom,
         ">" => FilterOperator::GreaterThan,
         ">=" => FilterOperator::GreaterThanOrEqual,
         "<" => FilterOperator::LessThan,
         "<=" => FilterOperator::LessThanOrEqual,
         "IN" => FilterOperator::In,
         "NOT IN" => FilterOperator::NotIn,
+        "BETWEEN" => FilterOperator::Between,
+        "NOT BETWEEN" => FilterOperator::NotBetween,
+        "BETWEEN SYMMETRIC" => FilterOperator::BetweenSymmetric,
+        "NOT BETWEEN SYMMETRIC" => FilterOperator::NotBetweenSymmetric,
         "IS NULL" => FilterOperator::IsNull,
         "IS NOT NULL" => FilterOperator::IsNotNull,
         "STARTS WITH" => FilterOperator::StartsWith,
         "ENDS WITH" => FilterOperator::EndsWith,
         "CONTAINS" => FilterOperator::Contains,
         "OVERLAPS" => FilterOperator::Overlaps,
+        "SIMILAR" => FilterOperator::Similar,
+        "WORD_SIMILAR" => FilterOperator::WordSimilar,
         "DATE_ONLY" => FilterOperator::DateEqual,
         "DATE_RANGE" => FilterOperator::DateRange,
         "RELATIVE" => FilterOperator::RelativeDate,
@@ -997,6 +2902,7 @@ impl Default for FilterBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
         let mut columns = HashMap::new();
@@ -1025,13 +2931,13 @@ mod tests {
             JsonFilter {
                 n: "property_full_address".to_string(),
                 f: "LIKE".to_string(),
-                v: "%James%".to_string(),
+                v: "%James%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "client_name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%James%".to_string(),
+                v: "%James%".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1051,19 +2957,19 @@ mod tests {
             JsonFilter {
                 n: "name".to_string(),
                 f: "=".to_string(),
-                v: "value1".to_string(),
+                v: "value1".into(),
                 c: None,
             },
             JsonFilter {
                 n: "email".to_string(),
                 f: "=".to_string(),
-                v: "value2".to_string(),
+                v: "value2".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "client_name".to_string(),
                 f: "=".to_string(),
-                v: "value3".to_string(),
+                v: "value3".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1083,19 +2989,19 @@ mod tests {
             JsonFilter {
                 n: "name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%John%".to_string(),
+                v: "%John%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "18".to_string(),
+                v: "18".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "LIKE".to_string(),
-                v: "%York%".to_string(),
+                v: "%York%".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1115,31 +3021,31 @@ mod tests {
             JsonFilter {
                 n: "status".to_string(),
                 f: "=".to_string(),
-                v: "active".to_string(),
+                v: "active".into(),
                 c: None,
             },
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "21".to_string(),
+                v: "21".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "=".to_string(),
-                v: "New York".to_string(),
+                v: "New York".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "=".to_string(),
-                v: "London".to_string(),
+                v: "London".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "department".to_string(),
                 f: "=".to_string(),
-                v: "Sales".to_string(),
+                v: "Sales".into(),
                 c: Some("AND".to_string()),
             },
         ];
@@ -1159,13 +3065,13 @@ mod tests {
             JsonFilter {
                 n: "name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%John%".to_string(),
+                v: "%John%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "email".to_string(),
                 f: "LIKE".to_string(),
-                v: "%gmail.com".to_string(),
+                v: "%gmail.com".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1194,13 +3100,13 @@ mod tests {
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "25".to_string(),
+                v: "25".into(),
                 c: None,
             },
             JsonFilter {
                 n: "salary".to_string(),
                 f: "<".to_string(),
-                v: "50000".to_string(),
+                v: "50000".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1216,7 +3122,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "name".to_string(),
             f: "LIKE".to_string(),
-            v: "%John%".to_string(),
+            v: "%John%".into(),
             c: None,
         }];
 
@@ -1241,7 +3147,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "id".to_string(),
             f: "=".to_string(),
-            v: "123e4567-e89b-12d3-a456-426614174000".to_string(),
+            v: "123e4567-e89b-12d3-a456-426614174000".into(),
             c: None,
         }];
 
@@ -1258,19 +3164,19 @@ mod tests {
             JsonFilter {
                 n: "id".to_string(),
                 f: "=".to_string(),
-                v: "123e4567-e89b-12d3-a456-426614174000".to_string(),
+                v: "123e4567-e89b-12d3-a456-426614174000".into(),
                 c: None,
             },
             JsonFilter {
                 n: "name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%John%".to_string(),
+                v: "%John%".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "25".to_string(),
+                v: "25".into(),
                 c: Some("AND".to_string()),
             },
         ];
@@ -1289,7 +3195,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "is_active".to_string(),
             f: "=".to_string(),
-            v: "true".to_string(),
+            v: "true".into(),
             c: None,
         }];
 
@@ -1304,7 +3210,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "created_at".to_string(),
             f: ">".to_string(),
-            v: "2024-01-01 00:00:00".to_string(),
+            v: "2024-01-01 00:00:00".into(),
             c: None,
         }];
 
@@ -1319,7 +3225,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "unknown_column".to_string(),
             f: "=".to_string(),
-            v: "test".to_string(),
+            v: "test".into(),
             c: None,
         }];
 
@@ -1338,7 +3244,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "CONTAINS".to_string(),
-            v: "EPC".to_string(),
+            v: "EPC".into(),
             c: None,
         }];
 
@@ -1349,7 +3255,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "CONTAINS".to_string(),
-            v: "EPC,Search".to_string(),
+            v: "EPC,Search".into(),
             c: None,
         }];
 
@@ -1368,7 +3274,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "OVERLAPS".to_string(),
-            v: "EPC".to_string(),
+            v: "EPC".into(),
             c: None,
         }];
 
@@ -1379,7 +3285,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "OVERLAPS".to_string(),
-            v: "EPC,Search".to_string(),
+            v: "EPC,Search".into(),
             c: None,
         }];
 
@@ -1398,19 +3304,19 @@ mod tests {
             JsonFilter {
                 n: "services".to_string(),
                 f: "CONTAINS".to_string(),
-                v: "EPC".to_string(),
+                v: "EPC".into(),
                 c: None,
             },
             JsonFilter {
                 n: "status".to_string(),
                 f: "=".to_string(),
-                v: "active".to_string(),
+                v: "active".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "services".to_string(),
                 f: "OVERLAPS".to_string(),
-                v: "Search,Valuation".to_string(),
+                v: "Search,Valuation".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -1432,7 +3338,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "CONTAINS".to_string(),
-            v: "EPC's,Search & Valuation".to_string(),
+            v: "EPC's,Search & Valuation".into(),
             c: None,
         }];
 
@@ -1453,7 +3359,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "services".to_string(),
             f: "CONTAINS".to_string(),
-            v: "".to_string(),
+            v: "".into(),
             c: None,
         }];
 
@@ -1471,7 +3377,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "created_at".to_string(),
             f: "=".to_string(),
-            v: "2024-12-29 15:30:00".to_string(),
+            v: "2024-12-29 15:30:00".into(),
             c: None,
         }];
 
@@ -1488,7 +3394,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "created_at".to_string(),
             f: "DATE_ONLY".to_string(),
-            v: "2024-12-29".to_string(),
+            v: "2024-12-29".into(),
             c: None,
         }];
 
@@ -1508,7 +3414,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "created_at".to_string(),
             f: "DATE_RANGE".to_string(),
-            v: "2024-12-29 00:00:00,2024-12-29 23:59:59".to_string(),
+            v: "2024-12-29 00:00:00,2024-12-29 23:59:59".into(),
             c: None,
         }];
 
@@ -1528,12 +3434,32 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "created_at".to_string(),
             f: "RELATIVE".to_string(),
-            v: "now() - interval '1 day'".to_string(),
+            v: "last 7 days".into(),
             c: None,
         }];
 
         let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
-        assert_eq!(sql, " WHERE created_at > now() - interval '1 day'");
+        assert_eq!(
+            sql,
+            " WHERE created_at >= LOCALTIMESTAMP - ('7 days')::interval AND created_at < LOCALTIMESTAMP"
+        );
+
+        let mut params = QueryParams::new();
+        let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?
+            .build_with_params(&mut params)?;
+        assert_eq!(
+            sql,
+            " WHERE created_at >= LOCALTIMESTAMP - $1::interval AND created_at < LOCALTIMESTAMP"
+        );
+        assert_eq!(params.values, vec![SqlParam::Text("7 days".to_string())]);
+
+        let injection = vec![JsonFilter {
+            n: "created_at".to_string(),
+            f: "RELATIVE".to_string(),
+            v: "now() - interval '1 day' OR 1=1".into(),
+            c: None,
+        }];
+        assert!(FilterBuilder::from_json_filters(&injection, true, &columns).is_err());
         Ok(())
     }
 
@@ -1547,13 +3473,13 @@ mod tests {
             JsonFilter {
                 n: "created_at".to_string(),
                 f: "DATE_ONLY".to_string(),
-                v: "2024-12-29".to_string(),
+                v: "2024-12-29".into(),
                 c: None,
             },
             JsonFilter {
                 n: "updated_at".to_string(),
                 f: "RELATIVE".to_string(),
-                v: "now() - interval '1 hour'".to_string(),
+                v: "older than 1 hour".into(),
                 c: Some("AND".to_string()),
             },
         ];
@@ -1561,46 +3487,1156 @@ mod tests {
         let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
         assert_eq!(
             sql,
-            " WHERE (created_at >= '2024-12-29 00:00:00' AND created_at < ('2024-12-29')::date + interval '1 day' AND updated_at > now() - interval '1 hour')"
+            " WHERE (created_at >= '2024-12-29 00:00:00' AND created_at < ('2024-12-29')::date + interval '1 day' AND updated_at < LOCALTIMESTAMP - ('1 hour')::interval)"
         );
         Ok(())
     }
 
     #[test]
-    fn test_case_insensitive_operators() -> Result<()> {
+    fn test_date_filters_by_column_type() -> Result<()> {
         let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        columns.insert("logged_at", ColumnDef::TimestampTz("logged_at"));
 
-        // Test different case variations of LIKE
-        let operators = vec!["LIKE", "like", "Like", "LiKe"];
+        let filter = |n: &str, f: &str, v: &str| JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v: v.into(),
+            c: None,
+        };
+        let sql = |filter: JsonFilter| {
+            FilterBuilder::from_json_filters(&[filter], true, &columns).and_then(|b| b.build())
+        };
 
-        for op in operators {
-            let filters = vec![JsonFilter {
-                n: "name".to_string(),
-                f: op.to_string(),
-                v: "%John%".to_string(),
-                c: None,
-            }];
+        assert_eq!(
+            sql(filter("birthday", "DATE_ONLY", "2024-02-29"))?,
+            " WHERE birthday = '2024-02-29'"
+        );
+        assert_eq!(
+            sql(filter("birthday", "DATE_RANGE", "2024-01-01,2024-01-31"))?,
+            " WHERE birthday BETWEEN '2024-01-01' AND '2024-01-31'"
+        );
+        assert_eq!(
+            sql(filter("logged_at", "DATE_ONLY", "2024-12-29"))?,
+            " WHERE logged_at >= ('2024-12-29')::timestamptz AND logged_at < ('2024-12-29')::timestamptz + interval '1 day'"
+        );
+        assert_eq!(
+            sql(filter("logged_at", "DATE_RANGE", "2024-12-01,2024-12-29"))?,
+            " WHERE logged_at >= '2024-12-01' AND logged_at < ('2024-12-29')::timestamptz + interval '1 day'"
+        );
+        assert_eq!(
+            sql(filter("created_at", "DATE_RANGE", "2024-12-01 09:00:00,2024-12-29"))?,
+            " WHERE created_at >= '2024-12-01 09:00:00' AND created_at < ('2024-12-29')::date + interval '1 day'"
+        );
+        assert_eq!(
+            sql(filter(
+                "created_at",
+                "DATE_RANGE",
+                "2024-12-01,2024-12-29 12:00:00"
+            ))?,
+            " WHERE created_at BETWEEN '2024-12-01' AND '2024-12-29 12:00:00'"
+        );
+        assert_eq!(
+            sql(filter("logged_at", ">=", "2024-12-29T15:30:00+01:00"))?,
+            " WHERE logged_at >= '2024-12-29T15:30:00+01:00'"
+        );
 
-            let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
-            assert_eq!(sql, " WHERE LOWER(name) LIKE LOWER('%John%')");
-        }
+        let mut params = QueryParams::new();
+        let bound = FilterBuilder::from_json_filters(
+            &[filter("logged_at", "DATE_ONLY", "2024-12-29")],
+            true,
+            &columns,
+        )?
+        .build_with_params(&mut params)?;
+        assert_eq!(
+            bound,
+            " WHERE logged_at >= $1::timestamptz AND logged_at < $2::timestamptz + interval '1 day'"
+        );
 
-        // Test different case variations of CONTAINS for array
-        let operators = vec!["CONTAINS", "contains", "Contains", "CoNtAiNs"];
-        columns.insert("services", ColumnDef::TextArray("services"));
+        let mut params = QueryParams::new();
+        let bound = FilterBuilder::from_json_filters(
+            &[filter("created_at", "DATE_RANGE", "2024-12-01,2024-12-29")],
+            true,
+            &columns,
+        )?
+        .build_with_params(&mut params)?;
+        assert_eq!(
+            bound,
+            " WHERE created_at >= $1::timestamp AND created_at < $2::date + interval '1 day'"
+        );
+        Ok(())
+    }
 
-        for op in operators {
+    #[test]
+    fn test_date_filters_in_time_zone() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        columns.insert("logged_at", ColumnDef::TimestampTz("logged_at"));
+
+        let filter = |n: &str, f: &str, v: &str| JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v: v.into(),
+            c: None,
+        };
+        let builder = |filter: JsonFilter| {
+            FilterBuilder::from_json_filters(&[filter], true, &columns)
+                .map(|builder| builder.time_zone("Australia/Sydney"))
+        };
+
+        assert_eq!(
+            builder(filter("logged_at", "DATE_ONLY", "2024-12-29"))?.build()?,
+            " WHERE logged_at >= ('2024-12-29')::timestamp AT TIME ZONE 'Australia/Sydney' AND logged_at < (('2024-12-29')::timestamp + interval '1 day') AT TIME ZONE 'Australia/Sydney'"
+        );
+        assert_eq!(
+            builder(filter(
+                "logged_at",
+                "DATE_RANGE",
+                "2024-12-29 09:00:00,2024-12-29T17:00:00Z"
+            ))?
+            .build()?,
+            " WHERE logged_at BETWEEN ('2024-12-29 09:00:00')::timestamp AT TIME ZONE 'Australia/Sydney' AND '2024-12-29T17:00:00Z'"
+        );
+        assert_eq!(
+            builder(filter("logged_at", "DATE_RANGE", "2024-12-01,2024-12-29"))?.build()?,
+            " WHERE logged_at >= ('2024-12-01')::timestamp AT TIME ZONE 'Australia/Sydney' AND logged_at < (('2024-12-29')::timestamp + interval '1 day') AT TIME ZONE 'Australia/Sydney'"
+        );
+        assert_eq!(
+            builder(filter("logged_at", "RELATIVE", "today"))?.build()?,
+            " WHERE logged_at >= (date_trunc('day', (now() AT TIME ZONE 'Australia/Sydney'))) AT TIME ZONE 'Australia/Sydney' AND logged_at < (date_trunc('day', (now() AT TIME ZONE 'Australia/Sydney')) + ('1 day')::interval) AT TIME ZONE 'Australia/Sydney'"
+        );
+        // Days of date columns have no time zone
+        assert_eq!(
+            builder(filter("birthday", "DATE_ONLY", "2024-12-29"))?.build()?,
+            " WHERE birthday = '2024-12-29'"
+        );
+
+        let mut params = QueryParams::new();
+        let sql = builder(filter("logged_at", "DATE_ONLY", "2024-12-29"))?
+            .build_with_params(&mut params)?;
+        assert_eq!(
+            sql,
+            " WHERE logged_at >= $1::timestamp AT TIME ZONE $2::text AND logged_at < ($3::timestamp + interval '1 day') AT TIME ZONE $4::text"
+        );
+        assert_eq!(params.values[1], SqlParam::Text("Australia/Sydney".into()));
+
+        let mut params = QueryParams::new();
+        let sql = builder(filter("logged_at", "DATE_RANGE", "2024-12-01,2024-12-29"))?
+            .build_with_params(&mut params)?;
+        assert_eq!(
+            sql,
+            " WHERE logged_at >= $1::timestamp AT TIME ZONE $2::text AND logged_at < ($3::timestamp + interval '1 day') AT TIME ZONE $4::text"
+        );
+
+        assert!(builder(filter("logged_at", "DATE_ONLY", "2024-12-29"))?
+            .time_zone("Australia/Sydney'; --")
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_date_values() {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+
+        for (n, f, v) in [
+            ("created_at", "=", "yesterday"),
+            ("created_at", ">", "2024-12-29'; DROP TABLE users; --"),
+            ("created_at", "DATE_ONLY", "2024-12-29 10:00:00"),
+            ("created_at", "DATE_RANGE", "2024-12-01,2024-13-01"),
+            ("birthday", "=", "2024-12-29 10:00:00"),
+            ("birthday", "DATE_ONLY", "2023-02-29"),
+            ("created_at", "IN", "2024-12-29,tomorrow"),
+            ("birthday", "NOT IN", "2024-02-30"),
+        ] {
             let filters = vec![JsonFilter {
-                n: "services".to_string(),
-                f: op.to_string(),
-                v: "EPC".to_string(),
+                n: n.to_string(),
+                f: f.to_string(),
+                v: v.into(),
                 c: None,
             }];
-
-            let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
-            assert_eq!(sql, " WHERE services @> ARRAY['EPC']::text[]");
+            assert!(
+                FilterBuilder::from_json_filters(&filters, true, &columns).is_err(),
+                "{} {} {}",
+                n,
+                f,
+                v
+            );
         }
 
+        let filters = vec![JsonFilter {
+            n: "birthday".to_string(),
+            f: "IN".to_string(),
+            v: serde_json::json!(["2024-12-29", "2024-12-29'; DROP TABLE users; --"]),
+            c: None,
+        }];
+        assert!(FilterBuilder::from_json_filters(&filters, true, &columns).is_err());
+    }
+
+    #[test]
+    fn test_date_function_filters() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        columns.insert("logged_at", ColumnDef::TimestampTz("logged_at"));
+
+        let filter = |n: &str, f: &str, v: Value| JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v,
+            c: None,
+        };
+        let sql = |filter: JsonFilter| {
+            FilterBuilder::from_json_filters(&[filter], true, &columns)?.build()
+        };
+
+        assert_eq!(
+            sql(filter("created_at", "EXTRACT(ISODOW) IN", json!([6, 7])))?,
+            " WHERE EXTRACT(ISODOW FROM created_at) IN (6, 7)"
+        );
+        assert_eq!(
+            sql(filter("birthday", "extract(month) =", json!(3)))?,
+            " WHERE EXTRACT(MONTH FROM birthday) = 3"
+        );
+        assert_eq!(
+            sql(filter("created_at", "EXTRACT(HOUR) BETWEEN", json!("9,17")))?,
+            " WHERE EXTRACT(HOUR FROM created_at) BETWEEN 9 AND 17"
+        );
+        assert_eq!(
+            sql(filter("created_at", "EXTRACT(SECOND) =", json!(30)))?,
+            " WHERE FLOOR(EXTRACT(SECOND FROM created_at)) = 30"
+        );
+        assert_eq!(
+            sql(filter(
+                "created_at",
+                "DATE_TRUNC(MONTH) =",
+                json!("2024-03-01")
+            ))?,
+            " WHERE date_trunc('month', created_at) = '2024-03-01'"
+        );
+        assert_eq!(
+            sql(filter("logged_at", "EXTRACT(YEAR) >=", json!(2020)))?,
+            " WHERE EXTRACT(YEAR FROM logged_at) >= 2020"
+        );
+
+        let condition = FilterCondition::DateFunction {
+            column: "logged_at".to_string(),
+            function: DateFunction::Trunc(DateUnit::Day),
+            operator: FilterOperator::NotIn,
+            values: vec![SqlParam::Text("2024-12-25".to_string())],
+            column_type: DateColumnType::TimestampTz,
+            time_zone: None,
+        };
+        assert_eq!(
+            condition.to_sql(false)?,
+            "date_trunc('day', logged_at) NOT IN ('2024-12-25')"
+        );
+
+        let mut params = QueryParams::new();
+        let builder = FilterBuilder::from_json_filters(
+            &[filter("logged_at", "EXTRACT(HOUR) BETWEEN", json!([9, 17]))],
+            true,
+            &columns,
+        )?
+        .time_zone("Australia/Sydney");
+        assert_eq!(
+            builder.build()?,
+            " WHERE EXTRACT(HOUR FROM logged_at AT TIME ZONE 'Australia/Sydney') BETWEEN 9 AND 17"
+        );
+        assert_eq!(
+            builder.build_with_params(&mut params)?,
+            " WHERE EXTRACT(HOUR FROM logged_at AT TIME ZONE $1::text) BETWEEN $2 AND $3"
+        );
+        assert_eq!(params.values[1], SqlParam::Integer(9));
+
+        assert_eq!(
+            FilterBuilder::from_json_filters(
+                &[filter(
+                    "logged_at",
+                    "DATE_TRUNC(week) =",
+                    json!("2024-12-23")
+                )],
+                true,
+                &columns,
+            )?
+            .time_zone("UTC")
+            .build()?,
+            " WHERE date_trunc('week', logged_at AT TIME ZONE 'UTC') = '2024-12-23'"
+        );
+
+        let mut params = QueryParams::new();
+        assert_eq!(
+            FilterBuilder::from_json_filters(
+                &[filter(
+                    "logged_at",
+                    "DATE_TRUNC(week) =",
+                    json!("2024-12-23")
+                )],
+                true,
+                &columns,
+            )?
+            .time_zone("UTC")
+            .build_with_params(&mut params)?,
+            " WHERE date_trunc($1::text, logged_at AT TIME ZONE $2::text) = $3::timestamp"
+        );
         Ok(())
     }
+
+    #[test]
+    fn test_invalid_date_functions() {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+
+        for (n, f, v, message) in [
+            (
+                "name",
+                "EXTRACT(MONTH) =",
+                json!(3),
+                "Date functions are only supported on date and timestamp columns: name",
+            ),
+            (
+                "birthday",
+                "EXTRACT(HOUR) =",
+                json!(9),
+                "EXTRACT(HOUR) is not supported on date column: birthday",
+            ),
+            (
+                "birthday",
+                "EXTRACT(MONTH) =",
+                json!(13),
+                "Invalid value for EXTRACT(MONTH) birthday: 13",
+            ),
+            (
+                "birthday",
+                "EXTRACT(EPOCH) =",
+                json!(0),
+                "Invalid date field: EPOCH",
+            ),
+            (
+                "birthday",
+                "DATE_TRUNC(decade) =",
+                json!("2020-01-01"),
+                "Invalid date_trunc unit: decade",
+            ),
+            (
+                "birthday",
+                "DATE_TRUNC(month) =",
+                json!("2024-03-01'; --"),
+                "Invalid value for DATE_TRUNC(MONTH) birthday: 2024-03-01'; --",
+            ),
+            (
+                "birthday",
+                "EXTRACT(MONTH) LIKE",
+                json!("3"),
+                "Invalid operator for EXTRACT(MONTH): LIKE",
+            ),
+            (
+                "birthday",
+                "EXTRACT(MONTH) BETWEEN",
+                json!([3]),
+                "Invalid number of values for EXTRACT(MONTH) BETWEEN: birthday",
+            ),
+        ] {
+            let error = FilterBuilder::from_json_filters(
+                &[JsonFilter {
+                    n: n.to_string(),
+                    f: f.to_string(),
+                    v,
+                    c: None,
+                }],
+                true,
+                &columns,
+            )
+            .err()
+            .unwrap();
+            assert_eq!(error.to_string(), message, "{}", f);
+        }
+    }
+
+    #[test]
+    fn test_similarity_filters() -> Result<()> {
+        let columns = setup_test_columns();
+        let filter = |n: &str, f: &str, v: Value| JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v,
+            c: None,
+        };
+        let sql = |filter: JsonFilter| {
+            FilterBuilder::from_json_filters(&[filter], true, &columns)?.build()
+        };
+
+        assert_eq!(
+            sql(filter("name", "SIMILAR", json!("o'brien")))?,
+            " WHERE name % 'o''brien'"
+        );
+        assert_eq!(
+            sql(filter("name", "word_similar", json!("jon")))?,
+            " WHERE 'jon' <% name"
+        );
+        assert_eq!(
+            sql(filter(
+                "name",
+                "SIMILAR",
+                json!({"value": "jon", "threshold": 0.3})
+            ))?,
+            " WHERE similarity(name, 'jon') >= 0.3"
+        );
+
+        let condition =
+            FilterCondition::similarity("name", FilterOperator::WordSimilar, "jon", Some(0.6));
+        let mut params = QueryParams::new();
+        assert_eq!(
+            condition.to_sql_with_params(false, &mut params)?,
+            "word_similarity($1, name) >= $2"
+        );
+        assert_eq!(params.values.len(), 2);
+
+        for (n, v, message) in [
+            (
+                "age",
+                json!("42"),
+                "Similarity is only supported on text columns: age",
+            ),
+            (
+                "name",
+                json!({"value": "jon", "threshold": 1.5}),
+                "Similarity threshold must be between 0 and 1: 1.5",
+            ),
+            (
+                "name",
+                json!({"value": "jon", "threshold": "high"}),
+                "Invalid threshold: \"high\"",
+            ),
+            (
+                "name",
+                json!(42),
+                "SIMILAR expects a string or an object with a string value for name",
+            ),
+        ] {
+            let error = sql(filter(n, "SIMILAR", v)).err().unwrap();
+            assert_eq!(error.to_string(), message);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_insensitive_in_and_citext() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("email", ColumnDef::CiText("email"));
+        let sql = |json: &str, case_insensitive: bool| {
+            let filter: JsonFilter = serde_json::from_str(json)?;
+            FilterBuilder::from_json_filters(&[filter], case_insensitive, &columns)?.build()
+        };
+
+        assert_eq!(
+            sql(r#"{"n": "name", "f": "IN", "v": ["Bob", "Alice"]}"#, true)?,
+            " WHERE LOWER(name) IN (LOWER('Bob'), LOWER('Alice'))"
+        );
+        assert_eq!(
+            sql(r#"{"n": "name", "f": "NOT IN", "v": "Bob, Alice"}"#, true)?,
+            " WHERE LOWER(name) NOT IN (LOWER('Bob'), LOWER('Alice'))"
+        );
+        assert_eq!(
+            sql(r#"{"n": "name", "f": "IN", "v": ["Bob"]}"#, false)?,
+            " WHERE name IN ('Bob')"
+        );
+        assert_eq!(
+            sql(r#"{"n": "age", "f": "IN", "v": "18,21"}"#, true)?,
+            " WHERE age IN ('18', '21')"
+        );
+        assert_eq!(
+            sql(
+                r#"{"n": "email", "f": "IN", "v": ["Bob@example.com"]}"#,
+                true
+            )?,
+            " WHERE email IN ('Bob@example.com')"
+        );
+        assert_eq!(
+            sql(r#"{"n": "email", "f": "=", "v": "Bob@example.com"}"#, true)?,
+            " WHERE email = 'Bob@example.com'"
+        );
+        assert_eq!(
+            sql(r#"{"n": "email", "f": "STARTS WITH", "v": "bob"}"#, true)?,
+            " WHERE email LIKE 'bob%'"
+        );
+
+        let mut params = QueryParams::new().with_column_defs(&HashMap::from([
+            ("name", ColumnDef::Text("name")),
+            ("email", ColumnDef::CiText("email")),
+        ]));
+        let builder = FilterBuilder::new().case_insensitive(true).group(
+            LogicalOperator::And,
+            vec![
+                FilterExpression::Condition(
+                    ColumnDef::Text("name").to_filter_condition("IN", "Bob")?,
+                ),
+                FilterExpression::Condition(
+                    ColumnDef::CiText("email").to_filter_condition("NOT IN", "a@b.c")?,
+                ),
+                FilterExpression::Condition(
+                    ColumnDef::CiText("email").to_filter_condition("!=", "x@y.z")?,
+                ),
+            ],
+        );
+        assert_eq!(
+            builder.build_with_params(&mut params)?,
+            " WHERE (LOWER(name) IN (LOWER($1)) AND email NOT IN ($2::citext) AND email != $3::citext)"
+        );
+
+        // IN lists are only lowered when the column is known to be text
+        assert_eq!(
+            FilterCondition::in_values("name", FilterOperator::In, vec!["Bob"]).to_sql(true)?,
+            "name IN ('Bob')"
+        );
+        let builder = FilterBuilder::new()
+            .case_insensitive(true)
+            .add_condition(FilterCondition::in_values(
+                "age",
+                FilterOperator::In,
+                vec!["1", "2"],
+            ))
+            .add_condition(FilterCondition::in_values(
+                "id",
+                FilterOperator::NotIn,
+                vec!["123e4567-e89b-12d3-a456-426614174000"],
+            ));
+        assert_eq!(
+            builder.build()?,
+            " WHERE (age IN ('1', '2') AND id NOT IN ('123e4567-e89b-12d3-a456-426614174000'))"
+        );
+        assert_eq!(
+            builder.build_with_params(&mut QueryParams::new())?,
+            " WHERE (age IN ($1, $2) AND id NOT IN ($3))"
+        );
+
+        let builder = FilterBuilder::new()
+            .case_insensitive(true)
+            .with_column_defs(&HashMap::from([
+                ("name", ColumnDef::Text("name")),
+                ("id", ColumnDef::Uuid("id")),
+                ("email", ColumnDef::CiText("email")),
+            ]))
+            .group(
+                LogicalOperator::And,
+                vec![
+                    FilterExpression::Condition(FilterCondition::in_values(
+                        "name",
+                        FilterOperator::In,
+                        vec!["Bob"],
+                    )),
+                    FilterExpression::Condition(FilterCondition::in_values(
+                        "id",
+                        FilterOperator::In,
+                        vec!["123e4567-e89b-12d3-a456-426614174000"],
+                    )),
+                    FilterExpression::Condition(FilterCondition::in_values(
+                        "email",
+                        FilterOperator::NotIn,
+                        vec!["a@b.c"],
+                    )),
+                ],
+            );
+        assert_eq!(
+            builder.build()?,
+            " WHERE (LOWER(name) IN (LOWER('Bob')) AND id IN ('123e4567-e89b-12d3-a456-426614174000') AND email NOT IN ('a@b.c'))"
+        );
+        assert_eq!(
+            builder.build_with_params(&mut QueryParams::new())?,
+            " WHERE (LOWER(name) IN (LOWER($1)) AND id IN ($2::uuid) AND email NOT IN ($3::citext))"
+        );
+
+        // Without a definition the column may not be text, so its IN list is not lowered
+        assert_eq!(
+            sql(r#"{"n": "nickname", "f": "IN", "v": ["Bob"]}"#, true)?,
+            " WHERE nickname IN ('Bob')"
+        );
+        assert_eq!(
+            sql(r#"{"n": "nickname", "f": "NOT IN", "v": "Bob, Al"}"#, true)?,
+            " WHERE nickname NOT IN ('Bob', 'Al')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unaccent() -> Result<()> {
+        let name = |operator: FilterOperator, value: Option<&str>| {
+            FilterExpression::Condition(FilterCondition::text("name", operator, value))
+        };
+        let builder = FilterBuilder::new().unaccent(&["name"]).group(
+            LogicalOperator::And,
+            vec![
+                name(FilterOperator::Equal, Some("Zoë")),
+                name(FilterOperator::Like, Some("%müller%")),
+                FilterExpression::Condition(FilterCondition::text(
+                    "city",
+                    FilterOperator::Equal,
+                    Some("Köln"),
+                )),
+                name(FilterOperator::IsNotNull, None),
+            ],
+        );
+        assert_eq!(
+            builder.build()?,
+            " WHERE (unaccent(name) = unaccent('Zoë') AND unaccent(name) LIKE unaccent('%müller%') AND city = 'Köln' AND name IS NOT NULL)"
+        );
+        assert_eq!(
+            builder.clone().case_insensitive(true).build()?,
+            " WHERE (LOWER(unaccent(name)) = LOWER(unaccent('Zoë')) AND LOWER(unaccent(name)) LIKE LOWER(unaccent('%müller%')) AND LOWER(city) = LOWER('Köln') AND name IS NOT NULL)"
+        );
+
+        let builder = FilterBuilder::new()
+            .unaccent(&["name"])
+            .unaccent_function("public.immutable_unaccent")
+            .null_safe_inequality(true)
+            .add_expression(name(FilterOperator::NotEqual, Some("José")));
+        let mut params = QueryParams::new();
+        assert_eq!(
+            builder.build_with_params(&mut params)?,
+            " WHERE public.immutable_unaccent(name) IS DISTINCT FROM public.immutable_unaccent($1)"
+        );
+        assert_eq!(params.values, vec![SqlParam::Text("José".to_string())]);
+
+        let condition =
+            FilterCondition::varchar("name", FilterOperator::StartsWith, Some("jo")).unaccent("f");
+        assert_eq!(condition.to_sql(false)?, "f(name) LIKE f('jo%')");
+        assert_eq!(
+            FilterCondition::text("name", FilterOperator::ILike, Some("zoe"))
+                .unaccent("unaccent")
+                .to_sql(true)?,
+            "LOWER(unaccent(name)) ILIKE LOWER(unaccent('zoe'))"
+        );
+
+        let error = FilterCondition::text("name", FilterOperator::Equal, Some("x"))
+            .unaccent("unaccent(name); --")
+            .to_sql(false)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid unaccent function: unaccent(name); --"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_insensitive_operators() -> Result<()> {
+        let mut columns = setup_test_columns();
+
+        // Test different case variations of LIKE
+        let operators = vec!["LIKE", "like", "Like", "LiKe"];
+
+        for op in operators {
+            let filters = vec![JsonFilter {
+                n: "name".to_string(),
+                f: op.to_string(),
+                v: "%John%".into(),
+                c: None,
+            }];
+
+            let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
+            assert_eq!(sql, " WHERE LOWER(name) LIKE LOWER('%John%')");
+        }
+
+        // Test different case variations of CONTAINS for array
+        let operators = vec!["CONTAINS", "contains", "Contains", "CoNtAiNs"];
+        columns.insert("services", ColumnDef::TextArray("services"));
+
+        for op in operators {
+            let filters = vec![JsonFilter {
+                n: "services".to_string(),
+                f: op.to_string(),
+                v: "EPC".into(),
+                c: None,
+            }];
+
+            let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?.build()?;
+            assert_eq!(sql, " WHERE services @> ARRAY['EPC']::text[]");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_expressions() -> Result<()> {
+        let status = FilterExpression::Condition(FilterCondition::text(
+            "status",
+            FilterOperator::Equal,
+            Some("archived"),
+        ));
+        let age = FilterExpression::Condition(FilterCondition::integer(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(18),
+        ));
+
+        let sql = FilterBuilder::new()
+            .not(FilterExpression::or(vec![status.clone(), age.clone()]))
+            .build()?;
+        assert_eq!(sql, " WHERE NOT (status = 'archived' OR age > 18)");
+
+        let sql = FilterBuilder::new()
+            .case_insensitive(true)
+            .add_expression(FilterExpression::negate_null_safe(status.clone()))
+            .add_expression(age.clone())
+            .build()?;
+        assert_eq!(
+            sql,
+            " WHERE ((LOWER(status) = LOWER('archived')) IS NOT TRUE AND age > 18)"
+        );
+
+        let mut params = QueryParams::new();
+        let sql = FilterBuilder::new()
+            .not(!age)
+            .build_with_params(&mut params)?;
+        assert_eq!(sql, " WHERE NOT (NOT (age > $1))");
+        assert_eq!(params.values, vec![SqlParam::Integer(18)]);
+
+        let sql = FilterBuilder::new()
+            .not(FilterExpression::and(vec![]))
+            .build()?;
+        assert_eq!(sql, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_operators() -> Result<()> {
+        let sql = FilterBuilder::new()
+            .case_insensitive(true)
+            .add_condition(FilterCondition::text(
+                "status",
+                FilterOperator::IsDistinctFrom,
+                Some("archived"),
+            ))
+            .add_condition(FilterCondition::uuid(
+                "id",
+                FilterOperator::IsNotDistinctFrom,
+                Some("123e4567-e89b-12d3-a456-426614174000"),
+            ))
+            .add_condition(FilterCondition::integer(
+                "age",
+                FilterOperator::IsDistinctFrom,
+                None,
+            ))
+            .build()?;
+        assert_eq!(
+            sql,
+            " WHERE ((LOWER(status) IS DISTINCT FROM LOWER('archived') AND id IS NOT DISTINCT FROM '123e4567-e89b-12d3-a456-426614174000') AND age IS NOT NULL)"
+        );
+
+        let columns = setup_test_columns();
+        let filters = vec![
+            JsonFilter {
+                n: "id".to_string(),
+                f: "is not distinct from".to_string(),
+                v: Value::Null,
+                c: None,
+            },
+            JsonFilter {
+                n: "property_full_address".to_string(),
+                f: "IS DISTINCT FROM".to_string(),
+                v: "Main St".into(),
+                c: Some("AND".to_string()),
+            },
+        ];
+        let sql = FilterBuilder::from_json_filters(&filters, false, &columns)?.build()?;
+        assert_eq!(
+            sql,
+            " WHERE (id IS NULL AND property_full_address IS DISTINCT FROM 'Main St')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_safe_inequality() -> Result<()> {
+        let status = FilterExpression::Condition(FilterCondition::text(
+            "status",
+            FilterOperator::NotEqual,
+            Some("archived"),
+        ));
+        let age = FilterExpression::Condition(FilterCondition::integer(
+            "age",
+            FilterOperator::NotEqual,
+            Some(18),
+        ));
+        let city = FilterExpression::Condition(FilterCondition::in_values(
+            "city",
+            FilterOperator::NotIn,
+            vec!["London", "Paris"],
+        ));
+        let name = FilterExpression::Condition(FilterCondition::text(
+            "name",
+            FilterOperator::Equal,
+            Some("John"),
+        ));
+        let builder = FilterBuilder::new()
+            .add_expression(FilterExpression::or(vec![status, age]))
+            .add_expression(city)
+            .not(name);
+
+        assert_eq!(
+            builder.build()?,
+            " WHERE (((status != 'archived' OR age != 18) AND city NOT IN ('London', 'Paris')) AND NOT (name = 'John'))"
+        );
+
+        let mut params = QueryParams::new();
+        let sql = builder
+            .null_safe_inequality(true)
+            .build_with_params(&mut params)?;
+        assert_eq!(
+            sql,
+            " WHERE (((status IS DISTINCT FROM $1 OR age IS DISTINCT FROM $2) AND (city NOT IN ($3, $4) OR city IS NULL)) AND NOT (name = $5))"
+        );
+        assert_eq!(params.values.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_between_filters() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        columns.insert("price", ColumnDef::Numeric("price"));
+        let filter = |n: &str, f: &str, v: Value| JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v,
+            c: None,
+        };
+
+        let cases = [
+            (
+                filter("age", "BETWEEN", serde_json::json!([18, 30])),
+                " WHERE age BETWEEN 18 AND 30",
+            ),
+            (
+                filter("salary", "not between", "1000,2000".into()),
+                " WHERE salary NOT BETWEEN 1000 AND 2000",
+            ),
+            (
+                filter("age", "BETWEEN SYMMETRIC", serde_json::json!([30, 18])),
+                " WHERE age BETWEEN SYMMETRIC 30 AND 18",
+            ),
+            (
+                filter("price", "BETWEEN", serde_json::json!(["9.99", 20])),
+                " WHERE price BETWEEN '9.99' AND '20'",
+            ),
+            (
+                filter(
+                    "birthday",
+                    "BETWEEN",
+                    serde_json::json!({"start": "2024-01-01", "end": "2024-02-01", "bounds": "[)"}),
+                ),
+                " WHERE (birthday >= '2024-01-01' AND birthday < '2024-02-01')",
+            ),
+            (
+                filter(
+                    "age",
+                    "NOT BETWEEN",
+                    serde_json::json!({"start": 18, "end": 30, "bounds": "()"}),
+                ),
+                " WHERE NOT (age > 18 AND age < 30)",
+            ),
+        ];
+        for (filter, expected) in cases {
+            let sql = FilterBuilder::from_json_filters(&[filter], true, &columns)?.build()?;
+            assert_eq!(sql, expected);
+        }
+
+        let mut params = QueryParams::new();
+        let sql = FilterBuilder::from_json_filters(
+            &[filter(
+                "birthday",
+                "BETWEEN",
+                serde_json::json!(["2024-01-01", "2024-12-31"]),
+            )],
+            false,
+            &columns,
+        )?
+        .build_with_params(&mut params)?;
+        assert_eq!(sql, " WHERE birthday BETWEEN $1::date AND $2::date");
+        assert_eq!(
+            params.values,
+            vec![
+                SqlParam::Text("2024-01-01".to_string()),
+                SqlParam::Text("2024-12-31".to_string())
+            ]
+        );
+
+        let errors = [
+            (
+                filter("age", "BETWEEN", serde_json::json!([30, 18])),
+                "Start 30 is greater than end 18 for age",
+            ),
+            (
+                filter("age", "BETWEEN", "18,old".into()),
+                "Invalid bound for age: old",
+            ),
+            (
+                filter("age", "BETWEEN", serde_json::json!([18])),
+                "BETWEEN expects a start and an end for age",
+            ),
+            (
+                filter(
+                    "age",
+                    "BETWEEN SYMMETRIC",
+                    serde_json::json!({"start": 18, "end": 30, "bounds": "[)"}),
+                ),
+                "BETWEEN SYMMETRIC requires inclusive bounds for age",
+            ),
+            (
+                filter(
+                    "age",
+                    "BETWEEN",
+                    serde_json::json!({"start": 18, "end": 30, "bounds": "[["}),
+                ),
+                "Invalid bounds: [[",
+            ),
+            (
+                filter("name", "BETWEEN", "a,b".into()),
+                "BETWEEN is only supported on numeric and date/time columns: name",
+            ),
+        ];
+        for (filter, expected) in errors {
+            let error = FilterBuilder::from_json_filters(&[filter], true, &columns).unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_filter_not_operators() -> Result<()> {
+        let filters = vec![
+            JsonFilter {
+                n: "status".to_string(),
+                f: "NOT IN".to_string(),
+                v: serde_json::json!(["a", "b"]),
+                c: None,
+            },
+            JsonFilter {
+                n: "name".to_string(),
+                f: "NOT LIKE".to_string(),
+                v: "%John%".into(),
+                c: None,
+            },
+            JsonFilter {
+                n: "created_at".to_string(),
+                f: "not date_only".to_string(),
+                v: "2024-12-29".into(),
+                c: Some("OR".to_string()),
+            },
+        ];
+
+        let sql =
+            FilterBuilder::from_json_filters(&filters, false, &setup_test_columns())?.build()?;
+        assert_eq!(
+            sql,
+            " WHERE (status NOT IN ('a', 'b') AND (NOT (name LIKE '%John%') OR NOT (created_at >= '2024-12-29 00:00:00' AND created_at < ('2024-12-29')::date + interval '1 day')))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_params() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("services", ColumnDef::TextArray("services"));
+
+        let filters = vec![
+            JsonFilter {
+                n: "id".to_string(),
+                f: "=".to_string(),
+                v: "123e4567-e89b-12d3-a456-426614174000".into(),
+                c: None,
+            },
+            JsonFilter {
+                n: "name".to_string(),
+                f: "STARTS WITH".to_string(),
+                v: "Jo".into(),
+                c: Some("AND".to_string()),
+            },
+            JsonFilter {
+                n: "services".to_string(),
+                f: "OVERLAPS".to_string(),
+                v: "EPC,Search".into(),
+                c: Some("AND".to_string()),
+            },
+            JsonFilter {
+                n: "created_at".to_string(),
+                f: "DATE_ONLY".to_string(),
+                v: "2024-12-29".into(),
+                c: Some("AND".to_string()),
+            },
+        ];
+
+        let mut params = QueryParams::new();
+        let sql = FilterBuilder::from_json_filters(&filters, true, &columns)?
+            .build_with_params(&mut params)?;
+        assert_eq!(
+            sql,
+            " WHERE (id = $1::uuid AND LOWER(name) LIKE LOWER($2) AND services && $3::text[] AND created_at >= $4::date AND created_at < $5::date + interval '1 day')"
+        );
+        assert_eq!(
+            params.values,
+            vec![
+                SqlParam::Text("123e4567-e89b-12d3-a456-426614174000".to_string()),
+                SqlParam::Text("Jo%".to_string()),
+                SqlParam::TextArray(vec!["EPC".to_string(), "Search".to_string()]),
+                SqlParam::Text("2024-12-29".to_string()),
+                SqlParam::Text("2024-12-29".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_params_in_values() -> Result<()> {
+        let mut params = QueryParams::new();
+        let sql = FilterBuilder::new()
+            .add_condition(FilterCondition::in_values(
+                "status",
+                FilterOperator::NotIn,
+                vec!["archived", "deleted"],
+            ))
+            .add_condition(FilterCondition::boolean(
+                "is_active",
+                FilterOperator::Equal,
+                Some(true),
+            ))
+            .build_with_params(&mut params)?;
+        assert_eq!(sql, " WHERE (status NOT IN ($1, $2) AND is_active = $3)");
+        assert_eq!(
+            params.values,
+            vec![
+                SqlParam::Text("archived".to_string()),
+                SqlParam::Text("deleted".to_string()),
+                SqlParam::Boolean(true),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_json_values() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("services", ColumnDef::TextArray("services"));
+
+        let cases = [
+            (r#"{"n": "age", "f": ">", "v": 18}"#, " WHERE age > 18"),
+            (
+                r#"{"n": "is_active", "f": "=", "v": true}"#,
+                " WHERE is_active = true",
+            ),
+            (
+                r#"{"n": "name", "f": "=", "v": null}"#,
+                " WHERE name IS NULL",
+            ),
+            (
+                r#"{"n": "name", "f": "!=", "v": null}"#,
+                " WHERE name IS NOT NULL",
+            ),
+            (r#"{"n": "name", "f": "IS NULL"}"#, " WHERE name IS NULL"),
+            (
+                r#"{"n": "age", "f": "IS NOT NULL"}"#,
+                " WHERE age IS NOT NULL",
+            ),
+            (
+                r#"{"n": "created_at", "f": "=", "v": null}"#,
+                " WHERE created_at IS NULL",
+            ),
+            (
+                r#"{"n": "city", "f": "IN", "v": ["London", "Paris, France"]}"#,
+                " WHERE city IN ('London', 'Paris, France')",
+            ),
+            (
+                r#"{"n": "services", "f": "OVERLAPS", "v": ["EPC", "Search, Valuation"]}"#,
+                " WHERE services && ARRAY['EPC','Search, Valuation']::text[]",
+            ),
+            (
+                r#"{"n": "services", "f": "CONTAINS", "v": ["EPC", "Search"]}"#,
+                " WHERE services @> ARRAY['EPC','Search']::text[]",
+            ),
+            (
+                r#"{"n": "created_at", "f": "DATE_RANGE", "v": ["2024-12-29 00:00:00", "2024-12-29 23:59:59"]}"#,
+                " WHERE created_at BETWEEN '2024-12-29 00:00:00' AND '2024-12-29 23:59:59'",
+            ),
+            (
+                r#"{"n": "created_at", "f": "DATE_RANGE", "v": {"start": "2024-12-29 00:00:00", "end": "2024-12-29 23:59:59"}}"#,
+                " WHERE created_at BETWEEN '2024-12-29 00:00:00' AND '2024-12-29 23:59:59'",
+            ),
+        ];
+
+        for (json, expected) in cases {
+            let filter: JsonFilter = serde_json::from_str(json).unwrap();
+            let sql = FilterBuilder::from_json_filters(&[filter], false, &columns)?.build()?;
+            assert_eq!(sql, expected, "{}", json);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_filter_new() -> Result<()> {
+        let columns = setup_test_columns();
+        let filters = [
+            JsonFilter::new("name", "LIKE", "%John%", None),
+            JsonFilter::new("age", ">", String::from("18"), Some("AND")),
+            JsonFilter::new("is_active", "=", true, Some("AND")),
+        ];
+        assert_eq!(filters[1].text(), Some("18".to_string()));
+
+        let sql = FilterBuilder::from_json_filters(&filters, false, &columns)?.build()?;
+        assert_eq!(
+            sql,
+            " WHERE (name LIKE '%John%' AND age > 18 AND is_active = true)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_json_value_errors() {
+        let mut columns = setup_test_columns();
+        columns.insert("services", ColumnDef::TextArray("services"));
+
+        let cases = [
+            (
+                r#"{"n": "age", "f": ">", "v": null}"#,
+                "> requires a value for age",
+            ),
+            (
+                r#"{"n": "age", "f": "=", "v": [1, 2]}"#,
+                "Unexpected array for age =",
+            ),
+            (
+                r#"{"n": "name", "f": "OVERLAPS", "v": ["a"]}"#,
+                "Unexpected array for name OVERLAPS",
+            ),
+            (
+                r#"{"n": "city", "f": "IN", "v": []}"#,
+                "Unexpected array for city IN",
+            ),
+            (
+                r#"{"n": "city", "f": "IN", "v": [["a"]]}"#,
+                "Expected an array of numbers, booleans or strings for city",
+            ),
+            (
+                r#"{"n": "services", "f": "CONTAINS", "v": ["a,b"]}"#,
+                "CONTAINS values cannot contain commas for services",
+            ),
+            (
+                r#"{"n": "created_at", "f": "DATE_RANGE", "v": {"start": "2024-12-29"}}"#,
+                "DATE_RANGE expects an object with start and end for created_at",
+            ),
+            (
+                r#"{"n": "age", "f": ">", "v": {"gt": 1}}"#,
+                "Unexpected object for age >",
+            ),
+        ];
+
+        for (json, message) in cases {
+            let filter: JsonFilter = serde_json::from_str(json).unwrap();
+            let error = FilterBuilder::from_json_filters(&[filter], false, &columns)
+                .err()
+                .unwrap();
+            assert_eq!(error.to_string(), message, "{}", json);
+        }
+    }
 }
@@ -0,0 +1,208 @@
+//! Introspection module
+//!
+//! This module builds a `column_defs` map for an existing table by querying
+//! `information_schema.columns` and `pg_type`, so admin tooling can point pg_filters at an
+//! arbitrary table without writing the column definitions by hand.
+//!
+//! Columns whose types have no matching `ColumnDef` are reported in `unsupported` rather than
+//! silently dropped.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use pg_filters::introspection::introspect_table;
+//!
+//! # async fn run(client: &tokio_postgres::Client) -> eyre::Result<()> {
+//! let table = introspect_table(client, "public", "person").await?;
+//!
+//! for column in &table.unsupported {
+//!     println!("{} has unsupported type {}", column.column, column.udt_name);
+//! }
+//!
+//! let column_defs = table.column_defs;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ColumnDef;
+use eyre::Result;
+use std::collections::HashMap;
+use tokio_postgres::GenericClient;
+
+const COLUMNS_QUERY: &str = "SELECT c.column_name::text, COALESCE(bt.typname, t.typname)::text \
+     FROM information_schema.columns c \
+     JOIN pg_catalog.pg_namespace n ON n.nspname = c.udt_schema \
+     JOIN pg_catalog.pg_type t ON t.typname = c.udt_name AND t.typnamespace = n.oid \
+     LEFT JOIN pg_catalog.pg_type bt ON t.typtype = 'd' AND bt.oid = t.typbasetype \
+     WHERE c.table_schema = $1 AND c.table_name = $2 \
+     ORDER BY c.ordinal_position";
+
+/// A column whose PostgreSQL type has no matching `ColumnDef`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedColumn {
+    /// Column name
+    pub column: String,
+    /// PostgreSQL type name as reported by `pg_type`, e.g. `_int4`
+    pub udt_name: String,
+}
+
+/// The result of introspecting a table
+#[derive(Debug, Clone)]
+pub struct TableColumns {
+    /// Column definitions for every column with a supported type
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+    /// Columns that were skipped because their type is not supported
+    pub unsupported: Vec<UnsupportedColumn>,
+}
+
+/// Maps a PostgreSQL `udt_name` onto a `ColumnDef` for the given column
+///
+/// Returns `None` when the type has no matching `ColumnDef`.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_filters::introspection::column_def_for_udt;
+/// use pg_filters::ColumnDef;
+///
+/// assert!(matches!(column_def_for_udt("age", "int4"), Some(ColumnDef::Integer("age"))));
+/// assert!(column_def_for_udt("ids", "_int4").is_none());
+/// ```
+pub fn column_def_for_udt(column: &'static str, udt_name: &str) -> Option<ColumnDef> {
+    let column_def = match udt_name {
+        "int2" => ColumnDef::SmallInt(column),
+        "int4" => ColumnDef::Integer(column),
+        "int8" => ColumnDef::BigInt(column),
+        "float4" => ColumnDef::Real(column),
+        "float8" => ColumnDef::DoublePrecision(column),
+        "numeric" => ColumnDef::Numeric(column),
+        "text" => ColumnDef::Text(column),
+        "varchar" => ColumnDef::Varchar(column),
+        "bpchar" => ColumnDef::Char(column),
+        "citext" => ColumnDef::CiText(column),
+        "date" => ColumnDef::Date(column),
+        "time" => ColumnDef::Time(column),
+        "timetz" => ColumnDef::TimeTz(column),
+        "timestamp" => ColumnDef::Timestamp(column),
+        "timestamptz" => ColumnDef::TimestampTz(column),
+        "interval" => ColumnDef::Interval(column),
+        "bool" => ColumnDef::Boolean(column),
+        "inet" => ColumnDef::Inet(column),
+        "cidr" => ColumnDef::Cidr(column),
+        "macaddr" => ColumnDef::MacAddr(column),
+        "macaddr8" => ColumnDef::MacAddr8(column),
+        "uuid" => ColumnDef::Uuid(column),
+        "json" => ColumnDef::Json(column),
+        "jsonb" => ColumnDef::Jsonb(column),
+        "_text" => ColumnDef::TextArray(column),
+        "bytea" => ColumnDef::ByteA(column),
+        "money" => ColumnDef::Money(column),
+        "xml" => ColumnDef::Xml(column),
+        _ => return None,
+    };
+    Some(column_def)
+}
+
+/// Builds column definitions for `schema.table` by querying `information_schema.columns`
+///
+/// Domains are resolved to their base type before mapping. Column names are leaked to obtain
+/// the `&'static str` that `ColumnDef` requires, so this is intended to be called once per table
+/// at startup rather than per request.
+///
+/// Returns an error if the table has no visible columns.
+pub async fn introspect_table<C: GenericClient>(
+    client: &C,
+    schema: &str,
+    table: &str,
+) -> Result<TableColumns> {
+    let rows = client.query(COLUMNS_QUERY, &[&schema, &table]).await?;
+    if rows.is_empty() {
+        return Err(eyre::eyre!("Table not found: {}.{}", schema, table));
+    }
+
+    let columns = rows
+        .iter()
+        .map(|row| (row.get::<usize, String>(0), row.get::<usize, String>(1)))
+        .collect::<Vec<(String, String)>>();
+
+    Ok(table_columns(columns))
+}
+
+fn table_columns(columns: Vec<(String, String)>) -> TableColumns {
+    let mut column_defs = HashMap::new();
+    let mut unsupported = Vec::new();
+
+    for (column, udt_name) in columns {
+        let name: &'static str = Box::leak(column.into_boxed_str());
+        match column_def_for_udt(name, &udt_name) {
+            Some(column_def) => {
+                column_defs.insert(name, column_def);
+            }
+            None => unsupported.push(UnsupportedColumn {
+                column: name.to_string(),
+                udt_name,
+            }),
+        }
+    }
+
+    TableColumns {
+        column_defs,
+        unsupported,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_def_for_udt() {
+        assert!(matches!(
+            column_def_for_udt("age", "int4"),
+            Some(ColumnDef::Integer("age"))
+        ));
+        assert!(matches!(
+            column_def_for_udt("created_at", "timestamptz"),
+            Some(ColumnDef::TimestampTz("created_at"))
+        ));
+        assert!(matches!(
+            column_def_for_udt("tags", "_text"),
+            Some(ColumnDef::TextArray("tags"))
+        ));
+        assert!(matches!(
+            column_def_for_udt("email", "citext"),
+            Some(ColumnDef::CiText("email"))
+        ));
+        assert!(matches!(
+            column_def_for_udt("data", "jsonb"),
+            Some(ColumnDef::Jsonb("data"))
+        ));
+        assert!(matches!(
+            column_def_for_udt("total", "numeric"),
+            Some(ColumnDef::Numeric("total"))
+        ));
+        assert!(column_def_for_udt("ids", "_int4").is_none());
+    }
+
+    #[test]
+    fn test_table_columns_reports_unsupported() {
+        let table = table_columns(vec![
+            ("id".to_string(), "uuid".to_string()),
+            ("name".to_string(), "varchar".to_string()),
+            ("location".to_string(), "point".to_string()),
+        ]);
+
+        assert_eq!(table.column_defs.len(), 2);
+        assert!(matches!(
+            table.column_defs.get("name"),
+            Some(ColumnDef::Varchar("name"))
+        ));
+        assert_eq!(
+            table.unsupported,
+            vec![UnsupportedColumn {
+                column: "location".to_string(),
+                udt_name: "point".to_string(),
+            }]
+        );
+    }
+}
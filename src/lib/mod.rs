@@ -1,17 +1,48 @@
 use eyre::Result;
 use std::collections::HashMap;
 
+#[cfg(feature = "actix")]
+pub mod actix_extract;
+#[cfg(feature = "axum")]
+pub mod axum_extract;
+mod dates;
+#[cfg(feature = "diesel")]
+pub mod diesel_filter;
+#[cfg(feature = "tokio-postgres")]
+pub mod executor;
 pub mod filtering;
+#[cfg(feature = "introspection")]
+pub mod introspection;
+pub mod object_filter;
+pub mod odata;
 pub mod pagination;
+pub mod query_string;
+pub mod relation;
+pub mod relative_date;
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub mod request;
+pub mod rsql;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+#[cfg(feature = "sea-query")]
+pub mod sea_query_filter;
+pub mod search;
 pub mod sorting;
+#[cfg(feature = "sqlx")]
+pub mod sqlx_builder;
+#[cfg(feature = "typescript")]
+pub mod typescript;
 
 use crate::filtering::{
+    date_function_operator, BetweenBounds, DateColumnType, DateFunction, DateRangeType,
     FilterBuilder, FilterCondition, FilterExpression, FilterOperator, JsonFilter, LogicalOperator,
+    QueryParams, SqlParam,
 };
 use crate::pagination::Paginate;
+use crate::relative_date::DateUnit;
 use crate::sorting::{SortedColumn, Sorting};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ColumnDef {
     // Numeric Types
     SmallInt(&'static str),
@@ -19,11 +50,13 @@ pub enum ColumnDef {
     BigInt(&'static str),
     Real(&'static str),
     DoublePrecision(&'static str),
+    Numeric(&'static str),
 
     // Character Types
     Text(&'static str),
     Varchar(&'static str),
     Char(&'static str),
+    CiText(&'static str),
 
     // Date/Time Types
     Date(&'static str),
@@ -67,12 +100,15 @@ impl ColumnDef {
         let op = match operator.to_uppercase().as_str() {
             "=" => FilterOperator::Equal,
             "!=" => FilterOperator::NotEqual,
+            "IS DISTINCT FROM" => FilterOperator::IsDistinctFrom,
+            "IS NOT DISTINCT FROM" => FilterOperator::IsNotDistinctFrom,
             ">" => FilterOperator::GreaterThan,
             ">=" => FilterOperator::GreaterThanOrEqual,
             "<" => FilterOperator::LessThan,
             "<=" => FilterOperator::LessThanOrEqual,
             "LIKE" => FilterOperator::Like,
             "NOT LIKE" => FilterOperator::NotLike,
+            "ILIKE" => FilterOperator::ILike,
             "IN" => FilterOperator::In,
             "NOT IN" => FilterOperator::NotIn,
             "IS NULL" => FilterOperator::IsNull,
@@ -81,9 +117,15 @@ impl ColumnDef {
             "ENDS WITH" => FilterOperator::EndsWith,
             "CONTAINS" => FilterOperator::Contains,
             "OVERLAPS" => FilterOperator::Overlaps,
+            "SIMILAR" => FilterOperator::Similar,
+            "WORD_SIMILAR" => FilterOperator::WordSimilar,
             _ => return Err(eyre::eyre!("Invalid operator: {}", operator)),
         };
 
+        if op == FilterOperator::Similar || op == FilterOperator::WordSimilar {
+            return self.to_similarity_condition(op, value, None);
+        }
+
         if op == FilterOperator::In || op == FilterOperator::NotIn {
             // Parse comma-separated values into a list
             let values = value
@@ -91,43 +133,61 @@ impl ColumnDef {
                 .map(|v| v.trim().to_string())
                 .collect::<Vec<String>>();
 
-            return Ok(FilterCondition::InValues {
-                column: self.get_column_name(),
-                operator: op,
-                values,
-            });
+            return self.to_in_condition(op, values);
+        }
+
+        if op != FilterOperator::IsNull
+            && op != FilterOperator::IsNotNull
+            && !dates::is_valid_for(self, value)
+        {
+            return Err(eyre::eyre!(
+                "Invalid {} value: {}",
+                self.param_cast().unwrap_or("date/time"),
+                value
+            ));
         }
 
+        // IS NULL and IS NOT NULL take no value
+        let value = match op {
+            FilterOperator::IsNull | FilterOperator::IsNotNull => None,
+            _ => Some(value),
+        };
+
         match self {
             // Character Types
             ColumnDef::Text(name) | ColumnDef::Varchar(name) | ColumnDef::Char(name) => {
                 Ok(FilterCondition::TextValue {
                     column: name.to_string(),
                     operator: op,
-                    value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                        None
-                    } else {
-                        Some(value.to_string())
-                    },
+                    value: value.map(str::to_string),
                 })
             }
+            ColumnDef::CiText(name) => Ok(FilterCondition::CiTextValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
+            }),
 
             // Text Array types
             ColumnDef::TextArray(name) => match op {
                 FilterOperator::Contains => Ok(FilterCondition::ArrayContains {
                     column: name.to_string(),
                     operator: op,
-                    value: value.to_string(),
+                    value: value.unwrap_or_default().to_string(),
                 }),
                 FilterOperator::Overlaps => Ok(FilterCondition::ArrayOverlap {
                     column: name.to_string(),
                     operator: op,
-                    values: value.split(',').map(|s| s.trim().to_string()).collect(),
+                    values: value
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .collect(),
                 }),
                 _ => Ok(FilterCondition::ArrayContains {
                     column: name.to_string(),
                     operator: FilterOperator::Contains,
-                    value: value.to_string(),
+                    value: value.unwrap_or_default().to_string(),
                 }),
             },
 
@@ -135,109 +195,121 @@ impl ColumnDef {
             ColumnDef::SmallInt(name) => Ok(FilterCondition::SmallIntValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(
-                        value
-                            .parse::<i16>()
-                            .map_err(|_| eyre::eyre!("Invalid small integer value: {}", value))?,
-                    )
-                },
+                value: value
+                    .map(|v| {
+                        v.parse::<i16>()
+                            .map_err(|_| eyre::eyre!("Invalid small integer value: {}", v))
+                    })
+                    .transpose()?,
             }),
             ColumnDef::Integer(name) => Ok(FilterCondition::IntegerValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(
-                        value
-                            .parse::<i32>()
-                            .map_err(|_| eyre::eyre!("Invalid integer value: {}", value))?,
-                    )
-                },
+                value: value
+                    .map(|v| {
+                        v.parse::<i32>()
+                            .map_err(|_| eyre::eyre!("Invalid integer value: {}", v))
+                    })
+                    .transpose()?,
             }),
             ColumnDef::BigInt(name) => Ok(FilterCondition::BigIntValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(
-                        value
-                            .parse::<i64>()
-                            .map_err(|_| eyre::eyre!("Invalid big integer value: {}", value))?,
-                    )
-                },
+                value: value
+                    .map(|v| {
+                        v.parse::<i64>()
+                            .map_err(|_| eyre::eyre!("Invalid big integer value: {}", v))
+                    })
+                    .transpose()?,
             }),
             ColumnDef::Real(name) | ColumnDef::DoublePrecision(name) => {
                 Ok(FilterCondition::DoublePrecisionValue {
                     column: name.to_string(),
                     operator: op,
-                    value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                        None
-                    } else {
-                        Some(
-                            value
-                                .parse::<f64>()
-                                .map_err(|_| eyre::eyre!("Invalid decimal value: {}", value))?,
-                        )
-                    },
+                    value: value
+                        .map(|v| {
+                            v.parse::<f64>()
+                                .map_err(|_| eyre::eyre!("Invalid decimal value: {}", v))
+                        })
+                        .transpose()?,
                 })
             }
 
+            ColumnDef::Numeric(name) => Ok(FilterCondition::NumericValue {
+                column: name.to_string(),
+                operator: op,
+                value: value
+                    .map(|v| {
+                        if is_decimal(v) {
+                            Ok(v.to_string())
+                        } else {
+                            Err(eyre::eyre!("Invalid numeric value: {}", v))
+                        }
+                    })
+                    .transpose()?,
+            }),
+
             // Boolean Type
             ColumnDef::Boolean(name) => Ok(FilterCondition::BooleanValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(
-                        value
-                            .parse::<bool>()
-                            .map_err(|_| eyre::eyre!("Invalid boolean value: {}", value))?,
-                    )
-                },
+                value: value
+                    .map(|v| {
+                        v.parse::<bool>()
+                            .map_err(|_| eyre::eyre!("Invalid boolean value: {}", v))
+                    })
+                    .transpose()?,
             }),
 
             // Date/Time Types
-            ColumnDef::Date(name)
-            | ColumnDef::Time(name)
-            | ColumnDef::TimeTz(name)
-            | ColumnDef::Timestamp(name)
-            | ColumnDef::TimestampTz(name)
-            | ColumnDef::Interval(name) => Ok(FilterCondition::TimestampValue {
+            ColumnDef::Date(name) => Ok(FilterCondition::DateValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::Time(name) => Ok(FilterCondition::TimeValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::TimeTz(name) => Ok(FilterCondition::TimeTzValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::Timestamp(name) => Ok(FilterCondition::TimestampValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::TimestampTz(name) => Ok(FilterCondition::TimestampTzValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::Interval(name) => Ok(FilterCondition::IntervalValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
             }),
 
             // UUID Type
             ColumnDef::Uuid(name) => Ok(FilterCondition::UuidValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
             }),
 
             // JSON Types
-            ColumnDef::Json(name) | ColumnDef::Jsonb(name) => Ok(FilterCondition::JsonbValue {
+            ColumnDef::Json(name) => Ok(FilterCondition::JsonValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
+            }),
+            ColumnDef::Jsonb(name) => Ok(FilterCondition::JsonbValue {
+                column: name.to_string(),
+                operator: op,
+                value: value.map(str::to_string),
             }),
 
             // Network Address Types
@@ -247,58 +319,539 @@ impl ColumnDef {
             | ColumnDef::MacAddr8(name) => Ok(FilterCondition::TextValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
             }),
 
             // Binary Data
             ColumnDef::ByteA(name) => Ok(FilterCondition::TextValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
             }),
 
             // Money Type
             ColumnDef::Money(name) => Ok(FilterCondition::TextValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
             }),
 
             // XML Type
             ColumnDef::Xml(name) => Ok(FilterCondition::TextValue {
                 column: name.to_string(),
                 operator: op,
-                value: if operator == "IS NULL" || operator == "IS NOT NULL" {
-                    None
-                } else {
-                    Some(value.to_string())
-                },
+                value: value.map(str::to_string),
             }),
         }
     }
 
-    fn get_column_name(&self) -> String {
+    /// A `pg_trgm` match of `value` on this text column
+    ///
+    /// `operator` is `Similar` or `WordSimilar`. Without a `threshold` the condition uses the
+    /// `%` or `<%` operator, which can use a trigram index and the `pg_trgm.similarity_threshold`
+    /// setting; with one it compares `similarity()` or `word_similarity()` to it.
+    pub fn to_similarity_condition(
+        &self,
+        operator: FilterOperator,
+        value: &str,
+        threshold: Option<f64>,
+    ) -> Result<FilterCondition> {
+        let column = self.get_column_name();
+        if !matches!(
+            self,
+            ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_)
+        ) {
+            return Err(eyre::eyre!(
+                "Similarity is only supported on text columns: {}",
+                column
+            ));
+        }
+        if !matches!(
+            operator,
+            FilterOperator::Similar | FilterOperator::WordSimilar
+        ) {
+            return Err(eyre::eyre!("Invalid similarity operator: {}", operator));
+        }
+        if let Some(threshold) = threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(eyre::eyre!(
+                    "Similarity threshold must be between 0 and 1: {}",
+                    threshold
+                ));
+            }
+        }
+
+        Ok(FilterCondition::similarity(
+            &column, operator, value, threshold,
+        ))
+    }
+
+    /// A BETWEEN condition on this column, with `start` and `end` parsed as the column type
+    ///
+    /// Only numeric and date/time columns are supported. Numeric bounds must be in order unless
+    /// `operator` is symmetric, and symmetric ranges must include both bounds.
+    pub fn to_between_condition(
+        &self,
+        operator: FilterOperator,
+        start: &str,
+        end: &str,
+        bounds: BetweenBounds,
+    ) -> Result<FilterCondition> {
+        let column = self.get_column_name();
+        let symmetric = matches!(
+            operator,
+            FilterOperator::BetweenSymmetric | FilterOperator::NotBetweenSymmetric
+        );
+        if !operator.is_between() {
+            return Err(eyre::eyre!("Invalid BETWEEN operator: {}", operator));
+        }
+        if symmetric && bounds != BetweenBounds::Inclusive {
+            return Err(eyre::eyre!(
+                "{} requires inclusive bounds for {}",
+                operator,
+                column
+            ));
+        }
+
+        let (start, end) = match self {
+            ColumnDef::SmallInt(_) => {
+                let (start, end) = parse_bounds::<i16>(&column, start, end, symmetric)?;
+                (SqlParam::SmallInt(start), SqlParam::SmallInt(end))
+            }
+            ColumnDef::Integer(_) => {
+                let (start, end) = parse_bounds::<i32>(&column, start, end, symmetric)?;
+                (SqlParam::Integer(start), SqlParam::Integer(end))
+            }
+            ColumnDef::BigInt(_) => {
+                let (start, end) = parse_bounds::<i64>(&column, start, end, symmetric)?;
+                (SqlParam::BigInt(start), SqlParam::BigInt(end))
+            }
+            ColumnDef::Real(_) | ColumnDef::DoublePrecision(_) => {
+                let (start, end) = parse_bounds::<f64>(&column, start, end, symmetric)?;
+                (
+                    SqlParam::DoublePrecision(start),
+                    SqlParam::DoublePrecision(end),
+                )
+            }
+            ColumnDef::Numeric(_) => {
+                let (start, end) = (start.trim(), end.trim());
+                if let Some(invalid) = [start, end].into_iter().find(|bound| !is_decimal(bound)) {
+                    return Err(eyre::eyre!("Invalid bound for {}: {}", column, invalid));
+                }
+                parse_bounds::<f64>(&column, start, end, symmetric)?;
+                (
+                    SqlParam::Text(start.to_string()),
+                    SqlParam::Text(end.to_string()),
+                )
+            }
+            ColumnDef::Date(_)
+            | ColumnDef::Time(_)
+            | ColumnDef::TimeTz(_)
+            | ColumnDef::Timestamp(_)
+            | ColumnDef::TimestampTz(_)
+            | ColumnDef::Interval(_)
+            | ColumnDef::Money(_) => {
+                let (start, end) = (start.trim(), end.trim());
+                if start.is_empty() || end.is_empty() {
+                    return Err(eyre::eyre!("Missing bound for {}", column));
+                }
+                if let Some(invalid) = [start, end]
+                    .into_iter()
+                    .find(|bound| !dates::is_valid_for(self, bound))
+                {
+                    return Err(eyre::eyre!("Invalid bound for {}: {}", column, invalid));
+                }
+                (
+                    SqlParam::Text(start.to_string()),
+                    SqlParam::Text(end.to_string()),
+                )
+            }
+            _ => {
+                return Err(eyre::eyre!(
+                    "{} is only supported on numeric and date/time columns: {}",
+                    operator,
+                    column
+                ))
+            }
+        };
+
+        Ok(FilterCondition::Between {
+            column,
+            operator,
+            start,
+            end,
+            bounds,
+            cast: self.param_cast(),
+        })
+    }
+
+    /// The type of a column that supports the date operators, `DATE_ONLY`, `DATE_RANGE` and
+    /// `RELATIVE`, and the date functions `EXTRACT` and `DATE_TRUNC`
+    ///
+    /// `Time` and `TimeTz` columns have no type: they have no day for the date operators, and
+    /// `date_trunc` has no `time` form.
+    pub fn date_column_type(&self) -> Option<DateColumnType> {
+        match self {
+            ColumnDef::Date(_) => Some(DateColumnType::Date),
+            ColumnDef::Timestamp(_) => Some(DateColumnType::Timestamp),
+            ColumnDef::TimestampTz(_) => Some(DateColumnType::TimestampTz),
+            _ => None,
+        }
+    }
+
+    /// A date range condition on this column, with its dates and timestamps validated
+    ///
+    /// Dates must be `YYYY-MM-DD`. Timestamps are ISO-8601 and may be dates, except on `Date`
+    /// columns where every value must be a date.
+    pub fn to_date_condition(&self, range_type: DateRangeType) -> Result<FilterCondition> {
+        let column = self.get_column_name();
+        let column_type = self.date_column_type().ok_or_else(|| {
+            eyre::eyre!(
+                "Date filters are only supported on date and timestamp columns: {}",
+                column
+            )
+        })?;
+
+        let invalid = match &range_type {
+            DateRangeType::DateOnly(date) => (!dates::is_date(date)).then_some(date),
+            DateRangeType::Exact(timestamp) => {
+                (!dates::is_valid_for(self, timestamp)).then_some(timestamp)
+            }
+            DateRangeType::Range { start, end } => [start, end]
+                .into_iter()
+                .find(|timestamp| !dates::is_valid_for(self, timestamp)),
+            DateRangeType::Relative(_) => None,
+        };
+        if let Some(invalid) = invalid {
+            return Err(eyre::eyre!("Invalid date for {}: {}", column, invalid));
+        }
+
+        Ok(FilterCondition::date_condition(
+            &column,
+            column_type,
+            range_type,
+        ))
+    }
+
+    /// Creates a condition comparing `EXTRACT(field FROM column)` or `date_trunc(unit, column)`
+    ///
+    /// Comparisons take one value, `IN` and `NOT IN` a list and `BETWEEN` two values. `EXTRACT`
+    /// values are integers within the field's range, e.g. 1 to 12 for `MONTH`; `date_trunc`
+    /// values are dates or timestamps of the column. Time-of-day fields and units are rejected on
+    /// `Date` columns, and `Time` and `TimeTz` columns are not supported; see `date_column_type`.
+    pub fn to_date_function_condition(
+        &self,
+        function: DateFunction,
+        operator: &str,
+        values: &[String],
+    ) -> Result<FilterCondition> {
+        let column = self.get_column_name();
+        let column_type = self.date_column_type().ok_or_else(|| {
+            eyre::eyre!(
+                "Date functions are only supported on date and timestamp columns: {}",
+                column
+            )
+        })?;
+
+        let time_of_day = match function {
+            DateFunction::Extract(field) => field.is_time_of_day(),
+            DateFunction::Trunc(unit) => matches!(unit, DateUnit::Minute | DateUnit::Hour),
+        };
+        if time_of_day && column_type == DateColumnType::Date {
+            return Err(eyre::eyre!(
+                "{} is not supported on date column: {}",
+                function,
+                column
+            ));
+        }
+
+        let (operator, count) = date_function_operator(operator)
+            .ok_or_else(|| eyre::eyre!("Invalid operator for {}: {}", function, operator))?;
+        if !count.contains(&values.len()) {
+            return Err(eyre::eyre!(
+                "Invalid number of values for {} {}: {}",
+                function,
+                operator.as_sql(),
+                column
+            ));
+        }
+
+        let values = values
+            .iter()
+            .map(|value| {
+                let value = value.trim();
+                match function {
+                    DateFunction::Extract(field) => value
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|number| field.range().is_none_or(|range| range.contains(number)))
+                        .map(SqlParam::Integer),
+                    DateFunction::Trunc(_) => {
+                        dates::is_valid_for(self, value).then(|| SqlParam::Text(value.to_string()))
+                    }
+                }
+                .ok_or_else(|| eyre::eyre!("Invalid value for {} {}: {}", function, column, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(FilterCondition::DateFunction {
+            column,
+            function,
+            operator,
+            values,
+            column_type,
+            time_zone: None,
+        })
+    }
+
+    /// The SQL type a text parameter is cast to when bound for this column
+    pub(crate) fn param_cast(&self) -> Option<&'static str> {
+        match self {
+            ColumnDef::SmallInt(_) => Some("smallint"),
+            ColumnDef::Integer(_) => Some("integer"),
+            ColumnDef::BigInt(_) => Some("bigint"),
+            ColumnDef::Real(_) => Some("real"),
+            ColumnDef::DoublePrecision(_) => Some("double precision"),
+            ColumnDef::Numeric(_) => Some("numeric"),
+            ColumnDef::Date(_) => Some("date"),
+            ColumnDef::Time(_) => Some("time"),
+            ColumnDef::TimeTz(_) => Some("timetz"),
+            ColumnDef::Timestamp(_) => Some("timestamp"),
+            ColumnDef::TimestampTz(_) => Some("timestamptz"),
+            ColumnDef::Interval(_) => Some("interval"),
+            ColumnDef::Boolean(_) => Some("boolean"),
+            ColumnDef::Inet(_) => Some("inet"),
+            ColumnDef::Cidr(_) => Some("cidr"),
+            ColumnDef::MacAddr(_) => Some("macaddr"),
+            ColumnDef::MacAddr8(_) => Some("macaddr8"),
+            ColumnDef::Uuid(_) => Some("uuid"),
+            ColumnDef::Json(_) => Some("json"),
+            ColumnDef::Jsonb(_) => Some("jsonb"),
+            ColumnDef::ByteA(_) => Some("bytea"),
+            ColumnDef::Money(_) => Some("money"),
+            ColumnDef::Xml(_) => Some("xml"),
+            ColumnDef::CiText(_) => Some("citext"),
+            ColumnDef::Text(_)
+            | ColumnDef::Varchar(_)
+            | ColumnDef::Char(_)
+            | ColumnDef::TextArray(_) => None,
+        }
+    }
+
+    /// The `JsonFilter` operators that are meaningful for this column type
+    pub fn json_operators(&self) -> &'static [&'static str] {
+        const ORDERED: &[&str] = &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            ">",
+            ">=",
+            "<",
+            "<=",
+            "IN",
+            "NOT IN",
+            "BETWEEN",
+            "NOT BETWEEN",
+            "BETWEEN SYMMETRIC",
+            "NOT BETWEEN SYMMETRIC",
+            "IS NULL",
+            "IS NOT NULL",
+        ];
+        const TEXT: &[&str] = &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            ">",
+            ">=",
+            "<",
+            "<=",
+            "LIKE",
+            "ILIKE",
+            "STARTS WITH",
+            "ENDS WITH",
+            "SIMILAR",
+            "WORD_SIMILAR",
+            "IN",
+            "NOT IN",
+            "IS NULL",
+            "IS NOT NULL",
+        ];
+        const TIMESTAMP: &[&str] = &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            ">",
+            ">=",
+            "<",
+            "<=",
+            "IN",
+            "NOT IN",
+            "BETWEEN",
+            "NOT BETWEEN",
+            "BETWEEN SYMMETRIC",
+            "NOT BETWEEN SYMMETRIC",
+            "IS NULL",
+            "IS NOT NULL",
+            "DATE_ONLY",
+            "DATE_RANGE",
+            "RELATIVE",
+        ];
+        const EQUALITY: &[&str] = &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            "IN",
+            "NOT IN",
+            "IS NULL",
+            "IS NOT NULL",
+        ];
+        const UNORDERED: &[&str] = &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            "IS NULL",
+            "IS NOT NULL",
+        ];
+        const ARRAY: &[&str] = &["CONTAINS", "OVERLAPS"];
+
+        match self {
+            ColumnDef::Text(_)
+            | ColumnDef::Varchar(_)
+            | ColumnDef::Char(_)
+            | ColumnDef::CiText(_) => TEXT,
+            ColumnDef::SmallInt(_)
+            | ColumnDef::Integer(_)
+            | ColumnDef::BigInt(_)
+            | ColumnDef::Real(_)
+            | ColumnDef::DoublePrecision(_)
+            | ColumnDef::Numeric(_)
+            | ColumnDef::Time(_)
+            | ColumnDef::TimeTz(_)
+            | ColumnDef::Interval(_)
+            | ColumnDef::Money(_) => ORDERED,
+            ColumnDef::Date(_) | ColumnDef::Timestamp(_) | ColumnDef::TimestampTz(_) => TIMESTAMP,
+            ColumnDef::Uuid(_)
+            | ColumnDef::Inet(_)
+            | ColumnDef::Cidr(_)
+            | ColumnDef::MacAddr(_)
+            | ColumnDef::MacAddr8(_) => EQUALITY,
+            ColumnDef::Boolean(_)
+            | ColumnDef::Json(_)
+            | ColumnDef::Jsonb(_)
+            | ColumnDef::ByteA(_)
+            | ColumnDef::Xml(_) => UNORDERED,
+            ColumnDef::TextArray(_) => ARRAY,
+        }
+    }
+
+    /// The `json_operators` grouped by the format of the value they expect, in order:
+    /// single values, lists, null checks, then the date operators
+    #[cfg(any(feature = "json-schema", feature = "typescript"))]
+    pub(crate) fn json_operator_groups(
+        &self,
+        allow_relative_dates: bool,
+    ) -> Vec<(Vec<&'static str>, ValueFormat)> {
+        let mut groups: Vec<(Vec<&'static str>, ValueFormat)> = Vec::new();
+        let mut dates = Vec::new();
+
+        for &operator in self.json_operators() {
+            let format = match operator {
+                "IN" | "NOT IN" | "CONTAINS" | "OVERLAPS" => ValueFormat::List,
+                "BETWEEN" | "NOT BETWEEN" | "BETWEEN SYMMETRIC" | "NOT BETWEEN SYMMETRIC" => {
+                    ValueFormat::Range
+                }
+                "IS NULL" | "IS NOT NULL" => ValueFormat::Ignored,
+                "DATE_ONLY" => {
+                    dates.push((vec![operator], ValueFormat::Date));
+                    continue;
+                }
+                "DATE_RANGE" => {
+                    dates.push((vec![operator], ValueFormat::DateRange));
+                    continue;
+                }
+                "RELATIVE" => {
+                    if allow_relative_dates {
+                        dates.push((vec![operator], ValueFormat::RelativeDate));
+                    }
+                    continue;
+                }
+                _ => ValueFormat::Single,
+            };
+            match groups.iter_mut().find(|(_, existing)| *existing == format) {
+                Some((operators, _)) => operators.push(operator),
+                None => groups.push((vec![operator], format)),
+            }
+        }
+
+        groups.extend(dates);
+        groups
+    }
+
+    /// The date functions of this column, `EXTRACT` and `DATE_TRUNC`, with the fields or units
+    /// they take in upper case; empty for columns without a `date_column_type`
+    #[cfg(any(feature = "json-schema", feature = "typescript"))]
+    pub(crate) fn json_date_functions(&self) -> Vec<(&'static str, Vec<String>)> {
+        let column_type = match self.date_column_type() {
+            Some(column_type) => column_type,
+            None => return Vec::new(),
+        };
+        let date = column_type == DateColumnType::Date;
+
+        let fields = crate::filtering::DateField::ALL
+            .into_iter()
+            .filter(|field| !(date && field.is_time_of_day()))
+            .map(|field| field.as_str().to_string())
+            .collect();
+        let units = DateUnit::ALL
+            .into_iter()
+            .filter(|unit| !(date && matches!(unit, DateUnit::Minute | DateUnit::Hour)))
+            .map(|unit| unit.as_str().to_uppercase())
+            .collect();
+        vec![("EXTRACT", fields), ("DATE_TRUNC", units)]
+    }
+
+    /// An `IN` or `NOT IN` condition on this column, for values that may contain commas, with
+    /// the values of date/time columns validated
+    pub(crate) fn to_in_condition(
+        &self,
+        operator: FilterOperator,
+        values: Vec<String>,
+    ) -> Result<FilterCondition> {
+        if let Some(invalid) = values
+            .iter()
+            .find(|value| !dates::is_valid_for(self, value))
+        {
+            return Err(eyre::eyre!(
+                "Invalid {} value: {}",
+                self.param_cast().unwrap_or("date/time"),
+                invalid
+            ));
+        }
+
+        Ok(FilterCondition::InValues {
+            column: self.get_column_name(),
+            operator,
+            values,
+        })
+    }
+
+    pub(crate) fn get_column_name(&self) -> String {
         match self {
             ColumnDef::Text(name)
             | ColumnDef::Varchar(name)
             | ColumnDef::Char(name)
+            | ColumnDef::CiText(name)
             | ColumnDef::SmallInt(name)
             | ColumnDef::Integer(name)
             | ColumnDef::BigInt(name)
             | ColumnDef::Real(name)
             | ColumnDef::DoublePrecision(name)
+            | ColumnDef::Numeric(name)
             | ColumnDef::Date(name)
             | ColumnDef::Time(name)
             | ColumnDef::TimeTz(name)
@@ -321,6 +874,96 @@ impl ColumnDef {
     }
 }
 
+/// Parses the bounds of a numeric range, which must be in order unless `symmetric`
+fn parse_bounds<T: std::str::FromStr + PartialOrd>(
+    column: &str,
+    start: &str,
+    end: &str,
+    symmetric: bool,
+) -> Result<(T, T)> {
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<T>()
+            .map_err(|_| eyre::eyre!("Invalid bound for {}: {}", column, value))
+    };
+    let (start_value, end_value) = (parse(start)?, parse(end)?);
+    if !symmetric && start_value > end_value {
+        return Err(eyre::eyre!(
+            "Start {} is greater than end {} for {}",
+            start.trim(),
+            end.trim(),
+            column
+        ));
+    }
+    Ok((start_value, end_value))
+}
+
+/// Whether `value` is a decimal such as `-12.50` or `1.5e3`
+///
+/// Unlike `f64` parsing this rejects `NaN` and `inf`, and values too large for `f64` such as
+/// `1e400`, so that range bounds can be ordered.
+fn is_decimal(value: &str) -> bool {
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+
+    let mantissa_valid =
+        digits(whole) && digits(fraction) && !(whole.is_empty() && fraction.is_empty());
+    let exponent_valid = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        !exponent.is_empty() && digits(exponent)
+    });
+    mantissa_valid && exponent_valid && value.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+/// The comparisons of date functions, grouped by the format of the value they take
+#[cfg(any(feature = "json-schema", feature = "typescript"))]
+pub(crate) const DATE_FUNCTION_OPERATOR_GROUPS: [(&[&str], ValueFormat); 3] = [
+    (
+        &[
+            "=",
+            "!=",
+            "IS DISTINCT FROM",
+            "IS NOT DISTINCT FROM",
+            ">",
+            ">=",
+            "<",
+            "<=",
+        ],
+        ValueFormat::Single,
+    ),
+    (&["IN", "NOT IN"], ValueFormat::List),
+    (&["BETWEEN", "NOT BETWEEN"], ValueFormat::Pair),
+];
+
+/// The format of a `JsonFilter` value for a group of operators
+#[cfg(any(feature = "json-schema", feature = "typescript"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ValueFormat {
+    /// A single value of the column type
+    Single,
+    /// An array or comma-separated string of values of the column type
+    List,
+    /// Not used and may be left out, e.g. for `IS NULL`
+    Ignored,
+    /// A `YYYY-MM-DD` date
+    Date,
+    /// Start and end values of the column type, comma-separated, as an array or as a
+    /// `{start, end, bounds}` object
+    Range,
+    /// Start and end values of the column type, comma-separated or as an array
+    Pair,
+    /// Start and end timestamps, comma-separated, as an array or as a `{start, end}` object
+    DateRange,
+    /// A relative date such as `last 7 days`
+    RelativeDate,
+}
+
 #[derive(Debug, Clone)]
 pub struct PaginationOptions {
     pub current_page: i64,
@@ -344,6 +987,13 @@ impl PaginationOptions {
 pub struct FilteringOptions {
     pub expressions: Vec<FilterExpression>,
     pub case_insensitive: bool,
+    pub null_safe_inequality: bool,
+    /// IANA time zone for the days of date filters on `TimestampTz` columns
+    pub time_zone: Option<String>,
+    /// Column names whose text filters ignore accents
+    pub unaccent_columns: Vec<String>,
+    /// Function stripping accents, `unaccent` by default
+    pub unaccent_function: String,
     pub column_defs: HashMap<&'static str, ColumnDef>,
 }
 
@@ -355,6 +1005,10 @@ impl FilteringOptions {
         Self {
             expressions,
             case_insensitive: true,
+            null_safe_inequality: false,
+            time_zone: None,
+            unaccent_columns: Vec::new(),
+            unaccent_function: "unaccent".to_string(),
             column_defs,
         }
     }
@@ -366,6 +1020,10 @@ impl FilteringOptions {
         Self {
             expressions,
             case_insensitive: false,
+            null_safe_inequality: false,
+            time_zone: None,
+            unaccent_columns: Vec::new(),
+            unaccent_function: "unaccent".to_string(),
             column_defs,
         }
     }
@@ -385,7 +1043,15 @@ impl FilteringOptions {
     }
 
     pub fn to_filter_builder(&self) -> Result<FilterBuilder> {
-        let mut builder = FilterBuilder::new().case_insensitive(self.case_insensitive);
+        let mut builder = FilterBuilder::new()
+            .case_insensitive(self.case_insensitive)
+            .null_safe_inequality(self.null_safe_inequality)
+            .unaccent(&self.unaccent_columns)
+            .unaccent_function(self.unaccent_function.clone())
+            .with_column_defs(&self.column_defs);
+        if let Some(time_zone) = &self.time_zone {
+            builder = builder.time_zone(time_zone.clone());
+        }
 
         // If there are multiple expressions, wrap them in a group with AND operator
         if self.expressions.len() > 1 {
@@ -469,6 +1135,29 @@ impl PgFilters {
         Ok(sql)
     }
 
+    /// Like `sql`, but filter values are rendered as numbered placeholders bound to `params`
+    ///
+    /// IN list values are cast to the type of their column in `column_defs`.
+    pub fn sql_with_params(&self, params: &mut QueryParams) -> Result<String> {
+        params.add_column_defs(&self.column_defs);
+        let mut sql = String::new();
+
+        if let Some(filters) = &self.filters {
+            sql.push_str(&filters.build_with_params(params)?);
+        }
+
+        if let Some(sorting) = &self.sorting {
+            sql.push_str(&sorting.sql_with_params(params));
+        }
+
+        if let Some(pagination) = &self.pagination {
+            sql.push(' ');
+            sql.push_str(&pagination.sql);
+        }
+
+        Ok(sql)
+    }
+
     pub fn count_sql(&self, schema: &str, table: &str) -> Result<String> {
         let mut sql = format!("SELECT COUNT(*) FROM {}.{}", schema, table);
         if let Some(filters) = &self.filters {
@@ -510,13 +1199,13 @@ mod tests {
             JsonFilter {
                 n: "property_full_address".to_string(),
                 f: "LIKE".to_string(),
-                v: "%James%".to_string(),
+                v: "%James%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "client_name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%James%".to_string(),
+                v: "%James%".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -537,19 +1226,19 @@ mod tests {
             JsonFilter {
                 n: "field1".to_string(),
                 f: "=".to_string(),
-                v: "value1".to_string(),
+                v: "value1".into(),
                 c: None,
             },
             JsonFilter {
                 n: "field2".to_string(),
                 f: "=".to_string(),
-                v: "value2".to_string(),
+                v: "value2".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "field3".to_string(),
                 f: "=".to_string(),
-                v: "value3".to_string(),
+                v: "value3".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -570,19 +1259,19 @@ mod tests {
             JsonFilter {
                 n: "name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%John%".to_string(),
+                v: "%John%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "18".to_string(),
+                v: "18".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "LIKE".to_string(),
-                v: "%York%".to_string(),
+                v: "%York%".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -603,31 +1292,31 @@ mod tests {
             JsonFilter {
                 n: "status".to_string(),
                 f: "=".to_string(),
-                v: "active".to_string(),
+                v: "active".into(),
                 c: None,
             },
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "21".to_string(),
+                v: "21".into(),
                 c: Some("AND".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "=".to_string(),
-                v: "New York".to_string(),
+                v: "New York".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "city".to_string(),
                 f: "=".to_string(),
-                v: "London".to_string(),
+                v: "London".into(),
                 c: Some("OR".to_string()),
             },
             JsonFilter {
                 n: "department".to_string(),
                 f: "=".to_string(),
-                v: "Sales".to_string(),
+                v: "Sales".into(),
                 c: Some("AND".to_string()),
             },
         ];
@@ -656,7 +1345,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "name".to_string(),
             f: "LIKE".to_string(),
-            v: "%John%".to_string(),
+            v: "%John%".into(),
             c: None,
         }];
 
@@ -673,13 +1362,13 @@ mod tests {
             JsonFilter {
                 n: "age".to_string(),
                 f: ">".to_string(),
-                v: "25".to_string(),
+                v: "25".into(),
                 c: None,
             },
             JsonFilter {
                 n: "salary".to_string(),
                 f: "<".to_string(),
-                v: "50000".to_string(),
+                v: "50000".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -690,6 +1379,119 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_numeric_column() -> Result<()> {
+        let price = ColumnDef::Numeric("price");
+        let condition = price.to_filter_condition(">=", "9.50")?;
+        assert_eq!(condition.to_sql(true)?, "price >= '9.50'");
+
+        let mut params = QueryParams::new();
+        assert_eq!(
+            condition.to_sql_with_params(false, &mut params)?,
+            "price >= $1::numeric"
+        );
+        assert!(price.to_filter_condition("=", "cheap").is_err());
+        for invalid in ["NaN", "inf", "-Infinity", "1e400", "1.2.3", "e5", "1e", ""] {
+            assert!(
+                price.to_filter_condition("=", invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+        for valid in ["-12.50", "+3", ".5", "1.", "1.5e3", "2E-10"] {
+            assert!(price.to_filter_condition("=", valid).is_ok());
+        }
+        assert!(price
+            .to_between_condition(
+                FilterOperator::Between,
+                "NaN",
+                "10",
+                BetweenBounds::Inclusive
+            )
+            .is_err());
+        assert!(price
+            .to_between_condition(
+                FilterOperator::Between,
+                "1",
+                "1e400",
+                BetweenBounds::Inclusive
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_column_conditions() -> Result<()> {
+        let cases = [
+            (
+                ColumnDef::Date("born"),
+                "=",
+                "2024-01-31",
+                "born = '2024-01-31'",
+                "born = $1::date",
+            ),
+            (
+                ColumnDef::Time("opens"),
+                ">=",
+                "09:30:00",
+                "opens >= '09:30:00'",
+                "opens >= $1::time",
+            ),
+            (
+                ColumnDef::TimeTz("opens_tz"),
+                "<",
+                "17:00:00+02",
+                "opens_tz < '17:00:00+02'",
+                "opens_tz < $1::timetz",
+            ),
+            (
+                ColumnDef::TimestampTz("seen"),
+                "<=",
+                "2024-01-31 14:30:00+00",
+                "seen <= '2024-01-31 14:30:00+00'",
+                "seen <= $1::timestamptz",
+            ),
+            (
+                ColumnDef::Interval("duration"),
+                ">",
+                "1 day",
+                "duration > '1 day'",
+                "duration > $1::interval",
+            ),
+            (
+                ColumnDef::Json("data"),
+                "=",
+                r#"{"a": 1}"#,
+                r#"data::jsonb = ('{"a": 1}')::jsonb"#,
+                "data::jsonb = $1::jsonb",
+            ),
+            (
+                ColumnDef::Jsonb("doc"),
+                "!=",
+                r#"{"a": 1}"#,
+                r#"doc != '{"a": 1}'"#,
+                "doc != $1::jsonb",
+            ),
+        ];
+        for (column_def, operator, value, inline, bound) in cases {
+            let condition = column_def.to_filter_condition(operator, value)?;
+            assert_eq!(condition.to_sql(true)?, inline);
+            assert_eq!(
+                condition.to_sql_with_params(true, &mut QueryParams::new())?,
+                bound
+            );
+
+            let name = column_def.get_column_name();
+            assert_eq!(
+                column_def
+                    .to_filter_condition("is null", "")?
+                    .to_sql(true)?,
+                format!("{} IS NULL", name)
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_case_sensitivity() -> Result<()> {
         let columns = setup_test_columns();
@@ -697,13 +1499,13 @@ mod tests {
             JsonFilter {
                 n: "name".to_string(),
                 f: "LIKE".to_string(),
-                v: "%John%".to_string(),
+                v: "%John%".into(),
                 c: None,
             },
             JsonFilter {
                 n: "email".to_string(),
                 f: "LIKE".to_string(),
-                v: "%gmail.com".to_string(),
+                v: "%gmail.com".into(),
                 c: Some("OR".to_string()),
             },
         ];
@@ -737,7 +1539,7 @@ mod tests {
         let filters = vec![JsonFilter {
             n: "id".to_string(),
             f: "=".to_string(),
-            v: uuid.to_string(),
+            v: uuid.into(),
             c: None,
         }];
 
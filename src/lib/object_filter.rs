@@ -0,0 +1,586 @@
+//! Object filter module
+//!
+//! This module parses filters in the operator-object dialect used by Hasura and MongoDB into a
+//! `FilterExpression` validated against `column_defs`:
+//!
+//! ```json
+//! {"age": {"_gte": 21}, "_or": [{"city": {"_eq": "London"}}, {"tags": {"_contains": ["a"]}}]}
+//! ```
+//!
+//! - keys are `column_defs` keys or the logical operators `_and`, `_or` (arrays of objects) and
+//!   `_not` (an object); all keys of an object are combined with AND
+//! - a column maps to an object of operators, also combined with AND, or to a value as a
+//!   shorthand for `_eq`
+//! - operators and logical operators may be prefixed with `$` instead of `_`
+//! - values are native JSON numbers, booleans, strings and arrays, parsed as the column type
+//!
+//! | operator        | value                   | condition                  |
+//! |-----------------|-------------------------|----------------------------|
+//! | `_eq`           | value, or `null`        | `=`, or `IS NULL`          |
+//! | `_neq` or `_ne` | value, or `null`        | `!=`, or `IS NOT NULL`     |
+//! | `_gt`           | value                   | `>`                        |
+//! | `_gte`          | value                   | `>=`                       |
+//! | `_lt`           | value                   | `<`                        |
+//! | `_lte`          | value                   | `<=`                       |
+//! | `_in`           | array                   | `IN`                       |
+//! | `_nin`          | array                   | `NOT IN`                   |
+//! | `_like`         | string                  | `LIKE`                     |
+//! | `_nlike`        | string                  | `NOT LIKE`                 |
+//! | `_ilike`        | string                  | `ILIKE`                    |
+//! | `_is_null`      | boolean                 | `IS NULL` or `IS NOT NULL` |
+//! | `_contains`     | array, on array columns | `@>`                       |
+//! | `_overlaps`     | array, on array columns | `&&`                       |
+//! | `_similar`      | string, or object       | `%`                        |
+//! | `_word_similar` | string, or object       | `<%`                       |
+//! | `_extract`      | object of fields        | `EXTRACT(field FROM col)`  |
+//! | `_date_trunc`   | object of units         | `date_trunc(unit, col)`    |
+//!
+//! `_extract` and `_date_trunc` take an object from a field, such as `isodow` or `hour`, or a
+//! unit, such as `month`, to the operators `_eq`, `_neq`, `_gt`, `_gte`, `_lt`, `_lte`, `_in`
+//! and `_nin` applied to the result, e.g. `{"_extract": {"isodow": {"_in": [6, 7]}}}` for
+//! weekends. They are only supported on date and timestamp columns.
+//!
+//! `_similar` and `_word_similar` take the string to fuzzy match, or an object with the string
+//! as `value` and a minimum similarity between 0 and 1 as `threshold`, e.g.
+//! `{"name": {"_similar": {"value": "jon", "threshold": 0.4}}}`. They need the `pg_trgm`
+//! extension and are only supported on text columns.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::FilterBuilder;
+//! use pg_filters::object_filter;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("age", ColumnDef::Integer("age"));
+//! columns.insert("city", ColumnDef::Text("city"));
+//! columns.insert("tags", ColumnDef::TextArray("tags"));
+//!
+//! let expression = object_filter::parse_filter_str(
+//!     r#"{"age": {"_gte": 21}, "_or": [{"city": {"_eq": "London"}}, {"tags": {"_contains": ["a"]}}]}"#,
+//!     &columns,
+//! )
+//! .unwrap();
+//! let sql = FilterBuilder::new().add_expression(expression).build().unwrap();
+//! assert_eq!(
+//!     sql,
+//!     " WHERE ((city = 'London' OR tags @> ARRAY['a']::text[]) AND age >= 21)"
+//! );
+//! ```
+
+use crate::filtering::{
+    DateFunction, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use crate::ColumnDef;
+use eyre::Result;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Parses an operator-object filter
+pub fn parse_filter(
+    filter: &Value,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    match filter {
+        Value::Object(object) => parse_object(object, column_defs),
+        _ => Err(eyre::eyre!("Expected a filter object, found {}", filter)),
+    }
+}
+
+/// Parses an operator-object filter from JSON
+pub fn parse_filter_str(
+    json: &str,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    let filter = serde_json::from_str::<Value>(json)
+        .map_err(|e| eyre::eyre!("Invalid filter JSON: {}", e))?;
+    parse_filter(&filter, column_defs)
+}
+
+/// The operator name without its `_` or `$` prefix
+fn operator_name(key: &str) -> Option<&str> {
+    key.strip_prefix('_').or_else(|| key.strip_prefix('$'))
+}
+
+fn parse_object(
+    object: &Map<String, Value>,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    if object.is_empty() {
+        return Err(eyre::eyre!("Filter objects cannot be empty"));
+    }
+
+    let mut operands = Vec::new();
+    for (key, value) in object {
+        if let Some(column_def) = column_defs.get(key.as_str()) {
+            operands.push(parse_column(key, column_def, value)?);
+            continue;
+        }
+
+        let expression = match operator_name(key) {
+            Some("and") => parse_list(key, LogicalOperator::And, value, column_defs)?,
+            Some("or") => parse_list(key, LogicalOperator::Or, value, column_defs)?,
+            Some("not") => FilterExpression::negate(parse_filter(value, column_defs)?),
+            _ => return Err(eyre::eyre!("Cannot filter by unknown column: {}", key)),
+        };
+        operands.push(expression);
+    }
+
+    Ok(FilterExpression::combine(LogicalOperator::And, operands))
+}
+
+fn parse_list(
+    key: &str,
+    operator: LogicalOperator,
+    value: &Value,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    match value {
+        Value::Array(filters) if !filters.is_empty() => {
+            let operands = filters
+                .iter()
+                .map(|filter| parse_filter(filter, column_defs))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(FilterExpression::combine(operator, operands))
+        }
+        _ => Err(eyre::eyre!(
+            "{} expects a non-empty array of filter objects",
+            key
+        )),
+    }
+}
+
+fn parse_column(column: &str, column_def: &ColumnDef, value: &Value) -> Result<FilterExpression> {
+    let operators = match value {
+        Value::Object(operators) => operators,
+        _ => return parse_operator(column, column_def, "_eq", value),
+    };
+    if operators.is_empty() {
+        return Err(eyre::eyre!("No operators given for {}", column));
+    }
+
+    let operands = operators
+        .iter()
+        .map(|(operator, value)| parse_operator(column, column_def, operator, value))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(FilterExpression::combine(LogicalOperator::And, operands))
+}
+
+fn parse_operator(
+    column: &str,
+    column_def: &ColumnDef,
+    key: &str,
+    value: &Value,
+) -> Result<FilterExpression> {
+    let condition = match operator_name(key) {
+        Some("eq") if value.is_null() => column_def.to_filter_condition("IS NULL", "")?,
+        Some("neq" | "ne") if value.is_null() => {
+            column_def.to_filter_condition("IS NOT NULL", "")?
+        }
+        Some("eq") => column_def.to_filter_condition("=", &scalar(column, value)?)?,
+        Some("neq" | "ne") => column_def.to_filter_condition("!=", &scalar(column, value)?)?,
+        Some("gt") => column_def.to_filter_condition(">", &scalar(column, value)?)?,
+        Some("gte") => column_def.to_filter_condition(">=", &scalar(column, value)?)?,
+        Some("lt") => column_def.to_filter_condition("<", &scalar(column, value)?)?,
+        Some("lte") => column_def.to_filter_condition("<=", &scalar(column, value)?)?,
+        Some("like") => column_def.to_filter_condition("LIKE", &string(column, key, value)?)?,
+        Some("nlike") => {
+            column_def.to_filter_condition("NOT LIKE", &string(column, key, value)?)?
+        }
+        Some("ilike") => column_def.to_filter_condition("ILIKE", &string(column, key, value)?)?,
+        Some("is_null") => match value {
+            Value::Bool(true) => column_def.to_filter_condition("IS NULL", "")?,
+            Value::Bool(false) => column_def.to_filter_condition("IS NOT NULL", "")?,
+            _ => return Err(eyre::eyre!("{} expects a boolean for {}", key, column)),
+        },
+        Some(name @ ("in" | "nin")) => {
+            let values = array(column, key, value)?;
+            for value in &values {
+                column_def.to_filter_condition("=", value)?;
+            }
+            FilterCondition::InValues {
+                column: column_def.get_column_name(),
+                operator: if name == "in" {
+                    FilterOperator::In
+                } else {
+                    FilterOperator::NotIn
+                },
+                values,
+            }
+        }
+        Some("contains") => {
+            array_column(column, key, column_def)?;
+            let values = array(column, key, value)?;
+            if values.iter().any(|value| value.contains(',')) {
+                return Err(eyre::eyre!(
+                    "{} values cannot contain commas for {}",
+                    key,
+                    column
+                ));
+            }
+            FilterCondition::ArrayContains {
+                column: column_def.get_column_name(),
+                operator: FilterOperator::Contains,
+                value: values.join(","),
+            }
+        }
+        Some("overlaps") => {
+            array_column(column, key, column_def)?;
+            FilterCondition::ArrayOverlap {
+                column: column_def.get_column_name(),
+                operator: FilterOperator::Overlaps,
+                values: array(column, key, value)?,
+            }
+        }
+        Some(name @ ("similar" | "word_similar")) => {
+            let operator = if name == "similar" {
+                FilterOperator::Similar
+            } else {
+                FilterOperator::WordSimilar
+            };
+            let (text, threshold) = match value {
+                Value::Object(similarity) => (
+                    similarity.get("value").unwrap_or(&Value::Null),
+                    similarity.get("threshold"),
+                ),
+                value => (value, None),
+            };
+            let threshold = match threshold {
+                Some(threshold) => Some(threshold.as_f64().ok_or_else(|| {
+                    eyre::eyre!("{} expects a numeric threshold for {}", key, column)
+                })?),
+                None => None,
+            };
+            column_def.to_similarity_condition(operator, &string(column, key, text)?, threshold)?
+        }
+        Some(name @ ("extract" | "date_trunc")) => {
+            return parse_date_function(column, column_def, key, name, value)
+        }
+        _ => return Err(eyre::eyre!("Unknown operator for {}: {}", column, key)),
+    };
+
+    Ok(FilterExpression::Condition(condition))
+}
+
+/// Parses the fields or units of `_extract` or `_date_trunc`, each with its own operators
+fn parse_date_function(
+    column: &str,
+    column_def: &ColumnDef,
+    key: &str,
+    name: &str,
+    value: &Value,
+) -> Result<FilterExpression> {
+    let arguments = match value {
+        Value::Object(arguments) if !arguments.is_empty() => arguments,
+        _ => {
+            return Err(eyre::eyre!(
+                "{} expects a non-empty object for {}",
+                key,
+                column
+            ))
+        }
+    };
+
+    let mut operands = Vec::new();
+    for (argument, operators) in arguments {
+        let function = DateFunction::parse(&format!("{}({})", name, argument))?;
+        let operators = match operators {
+            Value::Object(operators) if !operators.is_empty() => operators.clone(),
+            Value::Object(_) => return Err(eyre::eyre!("No operators given for {}", column)),
+            value => Map::from_iter([("_eq".to_string(), value.clone())]),
+        };
+        for (key, value) in &operators {
+            let (operator, values) = match operator_name(key) {
+                Some("eq") => ("=", vec![scalar(column, value)?]),
+                Some("neq" | "ne") => ("!=", vec![scalar(column, value)?]),
+                Some("gt") => (">", vec![scalar(column, value)?]),
+                Some("gte") => (">=", vec![scalar(column, value)?]),
+                Some("lt") => ("<", vec![scalar(column, value)?]),
+                Some("lte") => ("<=", vec![scalar(column, value)?]),
+                Some("in") => ("IN", array(column, key, value)?),
+                Some("nin") => ("NOT IN", array(column, key, value)?),
+                _ => {
+                    return Err(eyre::eyre!(
+                        "Unknown operator for {} {}: {}",
+                        function,
+                        column,
+                        key
+                    ))
+                }
+            };
+            let condition = column_def.to_date_function_condition(function, operator, &values)?;
+            operands.push(FilterExpression::Condition(condition));
+        }
+    }
+
+    Ok(FilterExpression::combine(LogicalOperator::And, operands))
+}
+
+/// The text of a number, boolean or string value
+fn scalar(column: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(boolean) => Ok(boolean.to_string()),
+        _ => Err(eyre::eyre!(
+            "Expected a number, boolean or string for {}, found {}",
+            column,
+            value
+        )),
+    }
+}
+
+fn string(column: &str, key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        _ => Err(eyre::eyre!("{} expects a string for {}", key, column)),
+    }
+}
+
+fn array(column: &str, key: &str, value: &Value) -> Result<Vec<String>> {
+    match value {
+        Value::Array(values) if !values.is_empty() => {
+            values.iter().map(|value| scalar(column, value)).collect()
+        }
+        _ => Err(eyre::eyre!(
+            "{} expects a non-empty array for {}",
+            key,
+            column
+        )),
+    }
+}
+
+fn array_column(column: &str, key: &str, column_def: &ColumnDef) -> Result<()> {
+    match column_def {
+        ColumnDef::TextArray(_) => Ok(()),
+        _ => Err(eyre::eyre!(
+            "{} is only supported on array columns: {}",
+            key,
+            column
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("city", ColumnDef::Text("city"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("score", ColumnDef::DoublePrecision("score"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("tags", ColumnDef::TextArray("tags"));
+        columns
+    }
+
+    fn filter_sql(filter: Value) -> Result<String> {
+        parse_filter(&filter, &setup_test_columns())?.to_sql(false)
+    }
+
+    #[test]
+    fn test_operators() -> Result<()> {
+        assert_eq!(filter_sql(json!({"age": {"_gte": 21}}))?, "age >= 21");
+        assert_eq!(filter_sql(json!({"age": {"$lt": 65}}))?, "age < 65");
+        assert_eq!(filter_sql(json!({"score": {"_gt": 1.5}}))?, "score > 1.5");
+        assert_eq!(filter_sql(json!({"active": true}))?, "active = true");
+        assert_eq!(filter_sql(json!({"name": {"_neq": "x"}}))?, "name != 'x'");
+        assert_eq!(filter_sql(json!({"name": null}))?, "name IS NULL");
+        assert_eq!(
+            filter_sql(json!({"name": {"_ne": null}}))?,
+            "name IS NOT NULL"
+        );
+        assert_eq!(
+            filter_sql(json!({"name": {"_is_null": false}}))?,
+            "name IS NOT NULL"
+        );
+        assert_eq!(
+            filter_sql(json!({"name": {"_ilike": "jo%"}}))?,
+            "name ILIKE 'jo%'"
+        );
+        assert_eq!(
+            filter_sql(json!({"city": {"_in": ["London", "Paris, France"]}}))?,
+            "city IN ('London', 'Paris, France')"
+        );
+        assert_eq!(
+            filter_sql(json!({"age": {"_nin": [1, 2]}}))?,
+            "age NOT IN ('1', '2')"
+        );
+        assert_eq!(
+            filter_sql(json!({"tags": {"_overlaps": ["a", "b,c"]}}))?,
+            "tags && ARRAY['a','b,c']::text[]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity() -> Result<()> {
+        assert_eq!(
+            filter_sql(json!({"name": {"_similar": "jon"}}))?,
+            "name % 'jon'"
+        );
+        assert_eq!(
+            filter_sql(json!({"name": {"$word_similar": {"value": "jon", "threshold": 0.4}}}))?,
+            "word_similarity('jon', name) >= 0.4"
+        );
+
+        for (filter, message) in [
+            (
+                json!({"age": {"_similar": "4"}}),
+                "Similarity is only supported on text columns: age",
+            ),
+            (
+                json!({"name": {"_similar": {"threshold": 0.4}}}),
+                "_similar expects a string for name",
+            ),
+            (
+                json!({"name": {"_similar": {"value": "jon", "threshold": "high"}}}),
+                "_similar expects a numeric threshold for name",
+            ),
+        ] {
+            assert_eq!(
+                parse_filter(&filter, &setup_test_columns())
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                message
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_functions() -> Result<()> {
+        let mut columns = setup_test_columns();
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        let sql = |filter: Value| parse_filter(&filter, &columns)?.to_sql(false);
+
+        assert_eq!(
+            sql(json!({"created_at": {"_extract": {"isodow": {"_in": [6, 7]}}}}))?,
+            "EXTRACT(ISODOW FROM created_at) IN (6, 7)"
+        );
+        assert_eq!(
+            sql(json!({"created_at": {"_extract": {"month": 3, "hour": {"_gte": 9, "_lt": 17}}}}))?,
+            "(EXTRACT(HOUR FROM created_at) >= 9 AND EXTRACT(HOUR FROM created_at) < 17 AND EXTRACT(MONTH FROM created_at) = 3)"
+        );
+        assert_eq!(
+            sql(json!({"created_at": {"$date_trunc": {"month": {"_eq": "2024-03-01"}}}}))?,
+            "date_trunc('month', created_at) = '2024-03-01'"
+        );
+
+        for (filter, message) in [
+            (
+                json!({"age": {"_extract": {"month": 3}}}),
+                "Date functions are only supported on date and timestamp columns: age",
+            ),
+            (
+                json!({"created_at": {"_extract": {"epoch": 0}}}),
+                "Invalid date field: epoch",
+            ),
+            (
+                json!({"created_at": {"_extract": {"month": {"_like": "3"}}}}),
+                "Unknown operator for EXTRACT(MONTH) created_at: _like",
+            ),
+            (
+                json!({"created_at": {"_extract": 3}}),
+                "_extract expects a non-empty object for created_at",
+            ),
+        ] {
+            let error = parse_filter(&filter, &columns).err().unwrap();
+            assert_eq!(error.to_string(), message, "{}", filter);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_operators() -> Result<()> {
+        assert_eq!(
+            filter_sql(json!({"age": {"_gte": 18, "_lt": 65}, "name": "x"}))?,
+            "((age >= 18 AND age < 65) AND name = 'x')"
+        );
+        assert_eq!(
+            filter_sql(json!({"_or": [{"age": 1}, {"$and": [{"name": "a"}, {"city": "b"}]}]}))?,
+            "(age = 1 OR (name = 'a' AND city = 'b'))"
+        );
+        assert_eq!(
+            filter_sql(json!({"_not": {"_or": [{"age": {"_gt": 1}}, {"name": null}]}}))?,
+            "NOT (age > 1 OR name IS NULL)"
+        );
+        assert_eq!(
+            filter_sql(json!({"_not": {"tags": {"_overlaps": ["a"]}}}))?,
+            "NOT (tags && ARRAY['a']::text[])"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_filter_str() -> Result<()> {
+        let expression = parse_filter_str(r#"{"age": {"_eq": 3}}"#, &setup_test_columns())?;
+        assert_eq!(expression.to_sql(false)?, "age = 3");
+
+        let error = parse_filter_str("{", &setup_test_columns()).err().unwrap();
+        assert!(error.to_string().starts_with("Invalid filter JSON: "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            (json!([]), "Expected a filter object, found []"),
+            (json!({}), "Filter objects cannot be empty"),
+            (
+                json!({"password": "x"}),
+                "Cannot filter by unknown column: password",
+            ),
+            (json!({"age": {}}), "No operators given for age"),
+            (json!({"age": {"_gte": 1.5}}), "Invalid integer value: 1.5"),
+            (
+                json!({"age": {"_in": [1, "x"]}}),
+                "Invalid integer value: x",
+            ),
+            (
+                json!({"age": {"_in": []}}),
+                "_in expects a non-empty array for age",
+            ),
+            (
+                json!({"age": {"_between": 1}}),
+                "Unknown operator for age: _between",
+            ),
+            (
+                json!({"age": {"_eq": [1]}}),
+                "Expected a number, boolean or string for age, found [1]",
+            ),
+            (
+                json!({"name": {"_like": 1}}),
+                "_like expects a string for name",
+            ),
+            (
+                json!({"name": {"_is_null": "yes"}}),
+                "_is_null expects a boolean for name",
+            ),
+            (
+                json!({"name": {"_contains": ["a"]}}),
+                "_contains is only supported on array columns: name",
+            ),
+            (
+                json!({"tags": {"_contains": ["a,b"]}}),
+                "_contains values cannot contain commas for tags",
+            ),
+            (
+                json!({"_or": {}}),
+                "_or expects a non-empty array of filter objects",
+            ),
+            (json!({"_not": []}), "Expected a filter object, found []"),
+        ];
+
+        for (filter, message) in cases {
+            let error = parse_filter(&filter, &setup_test_columns()).err().unwrap();
+            assert_eq!(error.to_string(), message, "{}", filter);
+        }
+    }
+}
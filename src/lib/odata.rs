@@ -0,0 +1,678 @@
+//! OData module
+//!
+//! This module parses the OData query options `$filter`, `$orderby`, `$top` and `$skip` into
+//! `FilterExpression`s, `SortedColumn`s and `PaginationOptions`, validated against
+//! `column_defs`:
+//!
+//! ```text
+//! $filter=age ge 21 and (contains(name,'jo') or city in ('London','Paris'))&$orderby=age desc,name&$top=20&$skip=40
+//! ```
+//!
+//! The supported subset of `$filter` is:
+//!
+//! - comparisons: `eq`, `ne`, `gt`, `ge`, `lt`, `le`, with `eq null` and `ne null` for null checks
+//! - `column in (value, ...)`
+//! - `contains(column,'text')`, `startswith(column,'text')` and `endswith(column,'text')` on text
+//!   columns, matched literally
+//! - `and`, `or`, `not` and parentheses, nested at most 32 levels deep
+//!
+//! Strings are quoted with `'`, with `''` for a quote inside a string. Numbers, booleans, dates,
+//! timestamps and GUIDs are written unquoted, and every value is parsed as the column type.
+//! Matching is case-sensitive, as in OData, and `not` becomes SQL `NOT`.
+//!
+//! Paging is page-based, so `$skip` must be a multiple of `$top`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::odata::ODataQuery;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let query = ODataQuery::parse(
+//!     "$filter=age ge 21 and (contains(name,'jo') or not startswith(name,'x'))&$orderby=age desc,name&$top=20&$skip=40",
+//!     &columns,
+//! )
+//! .unwrap();
+//!
+//! let filters = query.to_pg_filters(columns, 100, 1000).unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE (age >= 21 AND (name LIKE '%jo%' OR NOT (name LIKE 'x%'))) ORDER BY age DESC, name ASC LIMIT 20 OFFSET 40"
+//! );
+//! ```
+
+use crate::filtering::{
+    escape_like, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use crate::query_string::decode;
+use crate::sorting::{SortOrder, SortedColumn};
+use crate::{ColumnDef, FilteringOptions, PaginationOptions, PgFilters};
+use eyre::Result;
+use std::collections::HashMap;
+
+/// The `$filter`, `$orderby`, `$top` and `$skip` options of an OData request
+#[derive(Debug, Clone, Default)]
+pub struct ODataQuery {
+    pub filter: Option<FilterExpression>,
+    pub order_by: Vec<SortedColumn>,
+    pub top: Option<i64>,
+    pub skip: Option<i64>,
+}
+
+impl ODataQuery {
+    /// Parses a query string, with or without a leading `?`
+    ///
+    /// Query options other than `$filter`, `$orderby`, `$top` and `$skip` are ignored.
+    pub fn parse(query: &str, column_defs: &HashMap<&'static str, ColumnDef>) -> Result<Self> {
+        let mut parsed = ODataQuery::default();
+
+        for pair in query.trim_start_matches('?').split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(value)?;
+
+            match decode(key)?.as_str() {
+                "$filter" => parsed.filter = Some(parse_filter(&value, column_defs)?),
+                "$orderby" => parsed.order_by = parse_order_by(&value, column_defs)?,
+                "$top" => parsed.top = Some(parse_count("$top", &value)?),
+                "$skip" => parsed.skip = Some(parse_count("$skip", &value)?),
+                _ => {}
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Pagination options for `$top` and `$skip`, or `None` if neither was given
+    ///
+    /// Fails if `$top` exceeds `max_top`, if `$skip` is given without `$top`, or if `$skip` is
+    /// not a multiple of `$top`.
+    pub fn pagination_options(
+        &self,
+        max_top: i64,
+        total_records: i64,
+    ) -> Result<Option<PaginationOptions>> {
+        let top = match (self.top, self.skip) {
+            (None, None) => return Ok(None),
+            (None, Some(_)) => return Err(eyre::eyre!("$skip requires $top")),
+            (Some(top), _) => top,
+        };
+        if top < 1 || top > max_top {
+            return Err(eyre::eyre!("$top must be between 1 and {}", max_top));
+        }
+        let skip = self.skip.unwrap_or(0);
+        if skip % top != 0 {
+            return Err(eyre::eyre!("$skip must be a multiple of $top"));
+        }
+
+        Ok(Some(PaginationOptions::new(
+            skip / top + 1,
+            top,
+            max_top,
+            total_records,
+        )))
+    }
+
+    /// Case-sensitive filtering options, or `None` without `$filter`
+    pub fn filtering_options(
+        &self,
+        column_defs: HashMap<&'static str, ColumnDef>,
+    ) -> Option<FilteringOptions> {
+        self.filter
+            .clone()
+            .map(|filter| FilteringOptions::case_sensitive(vec![filter], column_defs))
+    }
+
+    pub fn to_pg_filters(
+        &self,
+        column_defs: HashMap<&'static str, ColumnDef>,
+        max_top: i64,
+        total_records: i64,
+    ) -> Result<PgFilters> {
+        PgFilters::new(
+            self.pagination_options(max_top, total_records)?,
+            self.order_by.clone(),
+            self.filtering_options(column_defs.clone()),
+            column_defs,
+        )
+    }
+}
+
+/// Parses a `$filter` expression
+pub fn parse_filter(
+    filter: &str,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    let mut parser = Parser {
+        tokens: tokenize(filter)?,
+        position: 0,
+        depth: 0,
+        column_defs,
+    };
+
+    let expression = parser.or()?;
+    match parser.next() {
+        None => Ok(expression),
+        Some(token) => Err(eyre::eyre!("Unexpected {} in $filter", token)),
+    }
+}
+
+/// Parses an `$orderby` list, e.g. `age desc,name`
+pub fn parse_order_by(
+    order_by: &str,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<Vec<SortedColumn>> {
+    let mut columns = Vec::new();
+
+    for item in order_by.split(',') {
+        let mut parts = item.split_whitespace();
+        let column = match parts.next() {
+            Some(column) => column,
+            None => continue,
+        };
+        if !column_defs.contains_key(column) {
+            return Err(eyre::eyre!("Cannot sort by unknown column: {}", column));
+        }
+        let order = match parts.next() {
+            None => SortOrder::Asc,
+            Some(direction) if direction.eq_ignore_ascii_case("asc") => SortOrder::Asc,
+            Some(direction) if direction.eq_ignore_ascii_case("desc") => SortOrder::Desc,
+            Some(direction) => return Err(eyre::eyre!("Invalid sort direction: {}", direction)),
+        };
+        if let Some(extra) = parts.next() {
+            return Err(eyre::eyre!("Unexpected {} in $orderby", extra));
+        }
+
+        columns.push(SortedColumn {
+            column: column.to_string(),
+            order,
+        });
+    }
+
+    Ok(columns)
+}
+
+fn parse_count(name: &str, value: &str) -> Result<i64> {
+    value
+        .parse::<i64>()
+        .ok()
+        .filter(|count| *count >= 0)
+        .ok_or_else(|| eyre::eyre!("Invalid {} value: {}", name, value))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Comma,
+    /// A quoted string, unescaped
+    Text(String),
+    /// A keyword, column name or unquoted value
+    Word(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+            Token::Text(text) => write!(f, "string '{}'", text.replace('\'', "''")),
+            Token::Word(word) => write!(f, "'{}'", word),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            text.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(eyre::eyre!("Unterminated string in $filter")),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | ',' | '\'') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Deepest nesting of parentheses and `not` in a `$filter`, so that a deeply nested filter
+/// cannot overflow the stack
+const MAX_DEPTH: usize = 32;
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Parentheses and `not`s around the current token
+    depth: usize,
+    column_defs: &'a HashMap<&'static str, ColumnDef>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(eyre::eyre!(
+                "Expected {} in $filter, got {}",
+                expected,
+                token
+            )),
+            None => Err(eyre::eyre!("Expected {} at end of $filter", expected)),
+        }
+    }
+
+    /// Consumes the next token if it is the keyword `keyword`
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses a nested expression with `parse`, one level deeper
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<FilterExpression>,
+    ) -> Result<FilterExpression> {
+        if self.depth == MAX_DEPTH {
+            return Err(eyre::eyre!(
+                "$filter is nested more than {} levels deep",
+                MAX_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let expression = parse(self)?;
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn or(&mut self) -> Result<FilterExpression> {
+        let mut operands = vec![self.and()?];
+        while self.keyword("or") {
+            operands.push(self.and()?);
+        }
+        Ok(FilterExpression::combine(LogicalOperator::Or, operands))
+    }
+
+    fn and(&mut self) -> Result<FilterExpression> {
+        let mut operands = vec![self.unary()?];
+        while self.keyword("and") {
+            operands.push(self.unary()?);
+        }
+        Ok(FilterExpression::combine(LogicalOperator::And, operands))
+    }
+
+    fn unary(&mut self) -> Result<FilterExpression> {
+        if self.keyword("not") {
+            let expression = self.nested(Self::unary)?;
+            return Ok(FilterExpression::negate(expression));
+        }
+
+        match self.next() {
+            Some(Token::Open) => self.nested(|parser| {
+                let expression = parser.or()?;
+                parser.expect(Token::Close)?;
+                Ok(expression)
+            }),
+            Some(Token::Word(word)) if self.peek() == Some(&Token::Open) => self.function(&word),
+            Some(Token::Word(word)) => self.comparison(&word),
+            Some(token) => Err(eyre::eyre!("Unexpected {} in $filter", token)),
+            None => Err(eyre::eyre!("Unexpected end of $filter")),
+        }
+    }
+
+    /// `contains`, `startswith` or `endswith`
+    fn function(&mut self, name: &str) -> Result<FilterExpression> {
+        let name = name.to_lowercase();
+        if !matches!(name.as_str(), "contains" | "startswith" | "endswith") {
+            return Err(eyre::eyre!("Unsupported function in $filter: {}", name));
+        }
+
+        self.expect(Token::Open)?;
+        let column = match self.next() {
+            Some(Token::Word(column)) => column,
+            _ => return Err(eyre::eyre!("{} expects a column", name)),
+        };
+        let column_def = self.column_def(&column)?;
+        self.expect(Token::Comma)?;
+        let value = match self.next() {
+            Some(Token::Text(value)) => escape_like(&value),
+            _ => return Err(eyre::eyre!("{} expects a string", name)),
+        };
+        self.expect(Token::Close)?;
+
+        if !matches!(
+            column_def,
+            ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_)
+        ) {
+            return Err(eyre::eyre!(
+                "{} is only supported on text columns: {}",
+                name,
+                column
+            ));
+        }
+        let pattern = match name.as_str() {
+            "contains" => format!("%{}%", value),
+            "startswith" => format!("{}%", value),
+            _ => format!("%{}", value),
+        };
+
+        Ok(FilterExpression::Condition(
+            column_def.to_filter_condition("LIKE", &pattern)?,
+        ))
+    }
+
+    fn comparison(&mut self, column: &str) -> Result<FilterExpression> {
+        let column_def = self.column_def(column)?;
+        let operator = match self.next() {
+            Some(Token::Word(operator)) => operator.to_lowercase(),
+            Some(token) => return Err(eyre::eyre!("Expected an operator, got {}", token)),
+            None => return Err(eyre::eyre!("Expected an operator after {}", column)),
+        };
+
+        if operator == "in" {
+            self.expect(Token::Open)?;
+            let mut values = Vec::new();
+            loop {
+                let value = self
+                    .literal()?
+                    .ok_or_else(|| eyre::eyre!("null is not allowed in an in list"))?;
+                column_def.to_filter_condition("=", &value)?;
+                values.push(value);
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.position += 1;
+            }
+            self.expect(Token::Close)?;
+
+            return Ok(FilterExpression::Condition(FilterCondition::InValues {
+                column: column_def.get_column_name(),
+                operator: FilterOperator::In,
+                values,
+            }));
+        }
+
+        let sql_operator = match operator.as_str() {
+            "eq" => "=",
+            "ne" => "!=",
+            "gt" => ">",
+            "ge" => ">=",
+            "lt" => "<",
+            "le" => "<=",
+            _ => return Err(eyre::eyre!("Invalid operator: {}", operator)),
+        };
+        let condition = match (self.literal()?, sql_operator) {
+            (Some(value), _) => column_def.to_filter_condition(sql_operator, &value)?,
+            (None, "=") => column_def.to_filter_condition("IS NULL", "")?,
+            (None, "!=") => column_def.to_filter_condition("IS NOT NULL", "")?,
+            (None, _) => {
+                return Err(eyre::eyre!(
+                    "null can only be compared with eq or ne: {}",
+                    column
+                ))
+            }
+        };
+
+        Ok(FilterExpression::Condition(condition))
+    }
+
+    /// A value, or `None` for `null`
+    fn literal(&mut self) -> Result<Option<String>> {
+        match self.next() {
+            Some(Token::Text(text)) => Ok(Some(text)),
+            Some(Token::Word(word)) if word == "null" => Ok(None),
+            Some(Token::Word(word)) if is_unquoted_literal(&word) => Ok(Some(word)),
+            Some(token) => Err(eyre::eyre!("Expected a value, got {}", token)),
+            None => Err(eyre::eyre!("Expected a value at end of $filter")),
+        }
+    }
+
+    fn column_def(&self, column: &str) -> Result<&'a ColumnDef> {
+        self.column_defs
+            .get(column)
+            .ok_or_else(|| eyre::eyre!("Cannot filter by unknown column: {}", column))
+    }
+}
+
+/// Numbers, booleans, dates, timestamps and GUIDs may be written without quotes
+fn is_unquoted_literal(word: &str) -> bool {
+    let is_guid = word.len() == 36
+        && word.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+
+    word.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+        || word == "true"
+        || word == "false"
+        || is_guid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("city", ColumnDef::Text("city"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("id", ColumnDef::Uuid("id"));
+        columns.insert("tags", ColumnDef::TextArray("tags"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        columns
+    }
+
+    fn filter_sql(filter: &str) -> Result<String> {
+        parse_filter(filter, &setup_test_columns())?.to_sql(false)
+    }
+
+    #[test]
+    fn test_comparisons() -> Result<()> {
+        assert_eq!(filter_sql("age gt 21")?, "age > 21");
+        assert_eq!(filter_sql("age Le -3")?, "age <= -3");
+        assert_eq!(filter_sql("name eq 'O''Brien'")?, "name = 'O''Brien'");
+        assert_eq!(filter_sql("active eq true")?, "active = true");
+        assert_eq!(
+            filter_sql("id eq 30cbcd23-2660-44fa-a051-a13c4e2aa63a")?,
+            "id = '30cbcd23-2660-44fa-a051-a13c4e2aa63a'"
+        );
+        assert_eq!(
+            filter_sql("created_at ge 2024-01-01T00:00:00Z")?,
+            "created_at >= '2024-01-01T00:00:00Z'"
+        );
+        assert_eq!(filter_sql("city eq null")?, "city IS NULL");
+        assert_eq!(filter_sql("city ne null")?, "city IS NOT NULL");
+        Ok(())
+    }
+
+    #[test]
+    fn test_functions_and_in() -> Result<()> {
+        assert_eq!(
+            filter_sql("contains(name,'50%_off')")?,
+            "name LIKE '%50\\%\\_off%'"
+        );
+        assert_eq!(filter_sql("startswith(name,'jo')")?, "name LIKE 'jo%'");
+        assert_eq!(filter_sql("endswith(name,'son')")?, "name LIKE '%son'");
+        assert_eq!(
+            filter_sql("city in ('London','Paris, France')")?,
+            "city IN ('London', 'Paris, France')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_operators() -> Result<()> {
+        assert_eq!(
+            filter_sql("age ge 21 and age lt 65 or name eq 'x'")?,
+            "((age >= 21 AND age < 65) OR name = 'x')"
+        );
+        assert_eq!(
+            filter_sql("age ge 21 and (age lt 65 or name eq 'x')")?,
+            "(age >= 21 AND (age < 65 OR name = 'x'))"
+        );
+        assert_eq!(
+            filter_sql("not (age lt 21 or city in ('London'))")?,
+            "NOT (age < 21 OR city IN ('London'))"
+        );
+        assert_eq!(
+            filter_sql("not not contains(name,'jo')")?,
+            "NOT (NOT (name LIKE '%jo%'))"
+        );
+        assert_eq!(
+            filter_sql("not tags eq 'a'")?,
+            "NOT (tags @> ARRAY['a']::text[])"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let cases = [
+            (
+                "password eq 'x'",
+                "Cannot filter by unknown column: password",
+            ),
+            ("age gt 'old'", "Invalid integer value: old"),
+            (
+                "age gt other_column",
+                "Expected a value, got 'other_column'",
+            ),
+            ("age has 1", "Invalid operator: has"),
+            (
+                "age gt null",
+                "null can only be compared with eq or ne: age",
+            ),
+            ("name eq 'x", "Unterminated string in $filter"),
+            ("(age gt 1", "Expected ')' at end of $filter"),
+            ("age gt 1 age lt 2", "Unexpected 'age' in $filter"),
+            (
+                "contains(age,'1')",
+                "contains is only supported on text columns: age",
+            ),
+            (
+                "tolower(name) eq 'x'",
+                "Unsupported function in $filter: tolower",
+            ),
+            ("not", "Unexpected end of $filter"),
+        ];
+
+        for (filter, message) in cases {
+            let error = parse_filter(filter, &setup_test_columns()).err().unwrap();
+            assert_eq!(error.to_string(), message, "{}", filter);
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let columns = setup_test_columns();
+        let nested = |depth: usize| format!("{}age gt 1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_filter(&nested(MAX_DEPTH), &columns).is_ok());
+
+        for filter in [nested(MAX_DEPTH + 1), "not ".repeat(100_000) + "age gt 1"] {
+            let error = parse_filter(&filter, &columns).err().unwrap();
+            assert_eq!(
+                error.to_string(),
+                "$filter is nested more than 32 levels deep"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_query() -> Result<()> {
+        let columns = setup_test_columns();
+        let query = ODataQuery::parse(
+            "?%24filter=name%20eq%20%27Jo%20Smith%27&$orderby=age desc, name&$top=10&$skip=30&$count=true",
+            &columns,
+        )?;
+
+        assert_eq!(
+            query.to_pg_filters(columns, 50, 100)?.sql()?,
+            " WHERE name = 'Jo Smith' ORDER BY age DESC, name ASC LIMIT 10 OFFSET 30"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_paging_errors() {
+        let columns = setup_test_columns();
+        let cases = [
+            ("$skip=10", "$skip requires $top"),
+            ("$top=10&$skip=15", "$skip must be a multiple of $top"),
+            ("$top=500", "$top must be between 1 and 100"),
+            ("$top=0", "$top must be between 1 and 100"),
+        ];
+
+        for (query, message) in cases {
+            let query = ODataQuery::parse(query, &columns).unwrap();
+            let error = query.pagination_options(100, 1000).err().unwrap();
+            assert_eq!(error.to_string(), message);
+        }
+
+        let error = ODataQuery::parse("$top=-1", &columns).err().unwrap();
+        assert_eq!(error.to_string(), "Invalid $top value: -1");
+        let error = ODataQuery::parse("$orderby=age sideways", &columns)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Invalid sort direction: sideways");
+    }
+}
@@ -85,6 +85,17 @@ impl Pagination {
     }
 }
 
+/// Page struct
+///
+/// Page struct holds the rows of one page of a query together with its pagination metadata
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    /// Rows on the current page
+    pub rows: Vec<T>,
+    /// Pagination struct
+    pub pagination: Pagination,
+}
+
 /// Paginate struct
 #[derive(Debug, Clone)]
 pub struct Paginate {
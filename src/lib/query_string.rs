@@ -0,0 +1,1045 @@
+//! Query string module
+//!
+//! This module defines a canonical URL form for filters, sorting and paging, and converts it to
+//! and from `FilterExpression`s, `SortedColumn`s and `PaginationOptions`, e.g. to build "next
+//! page" links or shareable URLs:
+//!
+//! ```text
+//! filter[age][gte]=21&filter[name][ilike]=jo%25&sort=-created_at,name&page=2&per_page=50
+//! ```
+//!
+//! - `filter[column][op]=value` adds a condition; `filter[column]=value` is short for `[eq]`
+//! - `filter[column][not][op]=value` adds the negation of a condition
+//! - all conditions are combined with AND, in the order they appear
+//! - `case_insensitive=true` matches text case-insensitively, as `FilteringOptions::new` does
+//! - `sort` is a comma-separated list of columns, each prefixed with `-` for descending order
+//! - `page` is 1-based; `page` and `per_page` are both optional
+//!
+//! | op          | condition                              |
+//! |-------------|----------------------------------------|
+//! | `eq`        | `=`                                    |
+//! | `ne`        | `!=`                                   |
+//! | `distinct`  | `IS DISTINCT FROM`                     |
+//! | `ndistinct` | `IS NOT DISTINCT FROM`                 |
+//! | `gt`        | `>`                                    |
+//! | `gte`       | `>=`                                   |
+//! | `lt`        | `<`                                    |
+//! | `lte`       | `<=`                                   |
+//! | `like`      | `LIKE`                                 |
+//! | `nlike`     | `NOT LIKE`                             |
+//! | `ilike`     | `ILIKE`                                |
+//! | `in`        | `IN`, comma-separated values           |
+//! | `nin`       | `NOT IN`, comma-separated values       |
+//! | `null`      | `IS NULL` for `true`, `IS NOT NULL` for `false` |
+//! | `starts`    | starts with                            |
+//! | `ends`      | ends with                              |
+//! | `contains`  | array contains, comma-separated values |
+//! | `overlaps`  | array overlaps, comma-separated values |
+//! | `similar`   | trigram similarity, `%`                |
+//! | `wsimilar`  | trigram word similarity, `<%`          |
+//! | `date`      | whole day, `YYYY-MM-DD`                |
+//! | `between`   | date range, `start,end`                |
+//!
+//! Conditions are case-sensitive unless `case_insensitive=true` is given; `ilike` is always
+//! case-insensitive. Values are percent-encoded when serialized, including commas within a
+//! value, so the commas separating list values are the only literal commas. OR groups, negated
+//! groups, null-safe negations, relative dates, similarity thresholds, accent-insensitive
+//! conditions and conditions on related tables have no query string form, so serializing them
+//! is an error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::query_string::QueryString;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("age", ColumnDef::Integer("age"));
+//! columns.insert("name", ColumnDef::Text("name"));
+//!
+//! let query = QueryString::parse(
+//!     "filter[age][gte]=21&filter[name][ilike]=jo%25&sort=-age,name&page=2&per_page=50",
+//!     &columns,
+//! )
+//! .unwrap();
+//!
+//! let filters = query.to_pg_filters(columns, 100, 1000).unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE (age >= 21 AND name ILIKE 'jo%') ORDER BY age DESC, name ASC LIMIT 50 OFFSET 50"
+//! );
+//!
+//! let next = query.with_page(3).to_query_string().unwrap();
+//! assert_eq!(
+//!     next,
+//!     "filter[age][gte]=21&filter[name][ilike]=jo%25&sort=-age,name&page=3&per_page=50"
+//! );
+//! ```
+
+use crate::filtering::{
+    DateRangeType, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use crate::sorting::{format_sort, parse_sort, SortedColumn};
+use crate::{ColumnDef, FilteringOptions, PaginationOptions, PgFilters};
+use eyre::Result;
+use std::collections::HashMap;
+
+/// Per-page size used when `page` is given without `per_page`
+const DEFAULT_PER_PAGE: i64 = 10;
+
+/// Filters, sorting and paging decoded from or encoded to a query string
+#[derive(Debug, Clone, Default)]
+pub struct QueryString {
+    /// Conditions, combined with AND
+    pub expressions: Vec<FilterExpression>,
+    /// Whether text conditions ignore case
+    pub case_insensitive: bool,
+    pub sorting: Vec<SortedColumn>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl QueryString {
+    /// Parses `query`, with or without a leading `?`
+    ///
+    /// Parameters other than `filter[...]`, `case_insensitive`, `sort`, `page` and `per_page` are
+    /// ignored. Filter and sort columns must be keys of `column_defs`, and values must parse as
+    /// the column type.
+    pub fn parse(query: &str, column_defs: &HashMap<&'static str, ColumnDef>) -> Result<Self> {
+        let mut parsed = QueryString::default();
+
+        for pair in query.trim_start_matches('?').split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+            // Filter values are decoded by `parse_filter`, after list values are split at commas
+            let (key, raw_value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode(key)?;
+
+            match key.as_str() {
+                "case_insensitive" => {
+                    let value = decode(raw_value)?;
+                    parsed.case_insensitive = value
+                        .parse::<bool>()
+                        .map_err(|_| eyre::eyre!("Invalid case_insensitive value: {}", value))?;
+                }
+                "sort" => parsed.sorting = parse_sort(&decode(raw_value)?),
+                "page" => parsed.page = Some(parse_number("page", &decode(raw_value)?)?),
+                "per_page" => {
+                    parsed.per_page = Some(parse_number("per_page", &decode(raw_value)?)?)
+                }
+                _ => {
+                    if let Some(filter) = key.strip_prefix("filter[") {
+                        parsed
+                            .expressions
+                            .push(parse_filter(filter, raw_value, column_defs)?);
+                    }
+                }
+            }
+        }
+
+        if let Some(unknown) = parsed
+            .sorting
+            .iter()
+            .find(|sorted| !column_defs.contains_key(sorted.column.as_str()))
+        {
+            return Err(eyre::eyre!(
+                "Cannot sort by unknown column: {}",
+                unknown.column
+            ));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Takes the filters, case sensitivity, sorting and current page of existing `PgFilters`
+    ///
+    /// A similarity sort is not taken, as a query string only sorts by columns.
+    pub fn from_pg_filters(filters: &PgFilters) -> Self {
+        QueryString {
+            expressions: filters
+                .filters
+                .as_ref()
+                .and_then(|builder| builder.root.clone())
+                .into_iter()
+                .collect(),
+            case_insensitive: filters
+                .filters
+                .as_ref()
+                .is_some_and(|builder| builder.case_insensitive),
+            sorting: filters
+                .sorting
+                .as_ref()
+                .map(|sorting| sorting.columns.clone())
+                .unwrap_or_default(),
+            page: filters
+                .pagination
+                .as_ref()
+                .map(|paginate| paginate.pagination.current_page),
+            per_page: filters
+                .pagination
+                .as_ref()
+                .map(|paginate| paginate.pagination.per_page),
+        }
+    }
+
+    /// A copy pointing at `page`, e.g. for a "next page" link
+    pub fn with_page(&self, page: i64) -> Self {
+        QueryString {
+            page: Some(page),
+            ..self.clone()
+        }
+    }
+
+    /// Serializes to the canonical form: filters in order, then `case_insensitive` if set, `sort`,
+    /// `page` and `per_page`
+    pub fn to_query_string(&self) -> Result<String> {
+        let mut pairs = Vec::new();
+
+        let mut conditions = Vec::new();
+        for expression in &self.expressions {
+            flatten_and(expression, &mut conditions)?;
+        }
+        for (condition, negated) in conditions {
+            let (column, op, values) = encode_condition(condition)?;
+            pairs.push(format!(
+                "filter[{}]{}[{}]={}",
+                encode(&column),
+                if negated { "[not]" } else { "" },
+                op,
+                values
+                    .iter()
+                    .map(|value| encode(value).replace(',', "%2C"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+        if self.case_insensitive {
+            pairs.push("case_insensitive=true".to_string());
+        }
+
+        if !self.sorting.is_empty() {
+            pairs.push(format!("sort={}", encode(&format_sort(&self.sorting))));
+        }
+        if let Some(page) = self.page {
+            pairs.push(format!("page={}", page));
+        }
+        if let Some(per_page) = self.per_page {
+            pairs.push(format!("per_page={}", per_page));
+        }
+
+        Ok(pairs.join("&"))
+    }
+
+    /// Pagination options, or `None` if neither `page` nor `per_page` was given
+    pub fn pagination_options(
+        &self,
+        per_page_limit: i64,
+        total_records: i64,
+    ) -> Option<PaginationOptions> {
+        if self.page.is_none() && self.per_page.is_none() {
+            return None;
+        }
+        Some(PaginationOptions::new(
+            self.page.unwrap_or(1),
+            self.per_page.unwrap_or(DEFAULT_PER_PAGE),
+            per_page_limit,
+            total_records,
+        ))
+    }
+
+    /// Filtering options, or `None` if there are no conditions
+    pub fn filtering_options(
+        &self,
+        column_defs: HashMap<&'static str, ColumnDef>,
+    ) -> Option<FilteringOptions> {
+        if self.expressions.is_empty() {
+            return None;
+        }
+        if self.case_insensitive {
+            Some(FilteringOptions::new(self.expressions.clone(), column_defs))
+        } else {
+            Some(FilteringOptions::case_sensitive(
+                self.expressions.clone(),
+                column_defs,
+            ))
+        }
+    }
+
+    pub fn to_pg_filters(
+        &self,
+        column_defs: HashMap<&'static str, ColumnDef>,
+        per_page_limit: i64,
+        total_records: i64,
+    ) -> Result<PgFilters> {
+        PgFilters::new(
+            self.pagination_options(per_page_limit, total_records),
+            self.sorting.clone(),
+            self.filtering_options(column_defs.clone()),
+            column_defs,
+        )
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<i64> {
+    value
+        .parse::<i64>()
+        .map_err(|_| eyre::eyre!("Invalid {} value: {}", name, value))
+}
+
+/// Parses the part of a filter key after `filter[`, e.g. `age][gte]` or `age][not][gte]`, and
+/// its still percent-encoded value
+fn parse_filter(
+    key: &str,
+    value: &str,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    let (column, rest) = key
+        .split_once(']')
+        .ok_or_else(|| eyre::eyre!("Invalid filter parameter: filter[{}", key))?;
+    let (rest, negated) = match rest.strip_prefix("[not]") {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+    let op = match rest {
+        "" => "eq",
+        _ => rest
+            .strip_prefix('[')
+            .and_then(|op| op.strip_suffix(']'))
+            .ok_or_else(|| eyre::eyre!("Invalid filter parameter: filter[{}", key))?,
+    };
+
+    let column_def = column_defs
+        .get(column)
+        .ok_or_else(|| eyre::eyre!("Cannot filter by unknown column: {}", column))?;
+
+    let condition = FilterExpression::Condition(parse_condition(column_def, op, value)?);
+    if negated {
+        Ok(FilterExpression::negate(condition))
+    } else {
+        Ok(condition)
+    }
+}
+
+/// Splits a percent-encoded value at its literal commas and decodes each value
+fn decode_list(value: &str) -> Result<Vec<String>> {
+    value.split(',').map(|item| decode(item.trim())).collect()
+}
+
+fn parse_condition(column_def: &ColumnDef, op: &str, raw_value: &str) -> Result<FilterCondition> {
+    match (op, column_def) {
+        ("in", _) => {
+            return column_def.to_in_condition(FilterOperator::In, decode_list(raw_value)?)
+        }
+        ("nin", _) => {
+            return column_def.to_in_condition(FilterOperator::NotIn, decode_list(raw_value)?)
+        }
+        ("overlaps", ColumnDef::TextArray(name)) => {
+            return Ok(FilterCondition::ArrayOverlap {
+                column: name.to_string(),
+                operator: FilterOperator::Overlaps,
+                values: decode_list(raw_value)?,
+            })
+        }
+        ("between", _) => {
+            return match decode_list(raw_value)?.as_slice() {
+                [start, end] => column_def.to_date_condition(DateRangeType::Range {
+                    start: start.to_string(),
+                    end: end.to_string(),
+                }),
+                _ => Err(eyre::eyre!("Invalid between value: {}", raw_value)),
+            }
+        }
+        _ => {}
+    }
+
+    let value = decode(raw_value)?;
+    let value = value.as_str();
+    let operator = match op {
+        "eq" => "=",
+        "ne" => "!=",
+        "distinct" => "IS DISTINCT FROM",
+        "ndistinct" => "IS NOT DISTINCT FROM",
+        "gt" => ">",
+        "gte" => ">=",
+        "lt" => "<",
+        "lte" => "<=",
+        "like" => "LIKE",
+        "nlike" => "NOT LIKE",
+        "ilike" => "ILIKE",
+        "in" => "IN",
+        "nin" => "NOT IN",
+        "starts" => "STARTS WITH",
+        "ends" => "ENDS WITH",
+        "contains" => "CONTAINS",
+        "overlaps" => "OVERLAPS",
+        "similar" => "SIMILAR",
+        "wsimilar" => "WORD_SIMILAR",
+        "null" => match value {
+            "true" => "IS NULL",
+            "false" => "IS NOT NULL",
+            _ => return Err(eyre::eyre!("Invalid null value: {}", value)),
+        },
+        "date" => return column_def.to_date_condition(DateRangeType::DateOnly(value.to_string())),
+        _ => return Err(eyre::eyre!("Invalid operator: {}", op)),
+    };
+
+    column_def.to_filter_condition(operator, value)
+}
+
+/// Collects the conditions of an AND tree, each with whether it is negated
+fn flatten_and<'a>(
+    expression: &'a FilterExpression,
+    conditions: &mut Vec<(&'a FilterCondition, bool)>,
+) -> Result<()> {
+    match expression {
+        FilterExpression::Condition(condition) => conditions.push((condition, false)),
+        FilterExpression::Not {
+            expression,
+            null_safe: false,
+        } => match &**expression {
+            FilterExpression::Condition(condition) => conditions.push((condition, true)),
+            _ => {
+                return Err(eyre::eyre!(
+                    "Only single conditions can be negated in a query string"
+                ))
+            }
+        },
+        FilterExpression::Not {
+            null_safe: true, ..
+        } => {
+            return Err(eyre::eyre!(
+                "Null-safe negations cannot be encoded in a query string"
+            ))
+        }
+        FilterExpression::Group {
+            operator: LogicalOperator::And,
+            expressions,
+        } => {
+            for expression in expressions {
+                flatten_and(expression, conditions)?;
+            }
+        }
+        FilterExpression::Group {
+            operator: LogicalOperator::Or,
+            ..
+        } => return Err(eyre::eyre!("OR groups cannot be encoded in a query string")),
+    }
+    Ok(())
+}
+
+/// Returns the column, op and values of `condition`; only list ops have more than one value
+fn encode_condition(condition: &FilterCondition) -> Result<(String, &'static str, Vec<String>)> {
+    let (column, operator, value) = match condition {
+        FilterCondition::TextValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::VarcharValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::CharValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::CiTextValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::DateValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::TimeValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::TimeTzValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::TimestampValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::TimestampTzValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::IntervalValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::NumericValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::InetValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::CidrValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::MacAddrValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::MacAddr8Value {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::UuidValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::JsonValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::JsonbValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::ByteAValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::MoneyValue {
+            column,
+            operator,
+            value,
+        }
+        | FilterCondition::XmlValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.clone()),
+        FilterCondition::SmallIntValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::IntegerValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::BigIntValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::RealValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::DoublePrecisionValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::BooleanValue {
+            column,
+            operator,
+            value,
+        } => (column, operator, value.map(|v| v.to_string())),
+        FilterCondition::InValues {
+            column,
+            operator,
+            values,
+            ..
+        } => return Ok((column.clone(), encode_op(column, operator)?, values.clone())),
+        // Contained values are kept comma-separated, so their commas separate values
+        FilterCondition::ArrayContains { column, value, .. } => {
+            let values = value.split(',').map(|v| v.trim().to_string()).collect();
+            return Ok((column.clone(), "contains", values));
+        }
+        FilterCondition::ArrayOverlap { column, values, .. } => {
+            return Ok((column.clone(), "overlaps", values.clone()))
+        }
+        FilterCondition::Between { column, .. } => {
+            return Err(eyre::eyre!(
+                "BETWEEN conditions cannot be encoded in a query string: {}",
+                column
+            ))
+        }
+        FilterCondition::Similarity {
+            column,
+            operator,
+            value,
+            threshold: None,
+        } => (column, operator, Some(value.clone())),
+        FilterCondition::Similarity { column, .. } => {
+            return Err(eyre::eyre!(
+                "Similarity thresholds cannot be encoded in a query string: {}",
+                column
+            ))
+        }
+        FilterCondition::UnaccentText { column, .. } => {
+            return Err(eyre::eyre!(
+                "Accent-insensitive conditions cannot be encoded in a query string: {}",
+                column
+            ))
+        }
+        FilterCondition::Related { table, .. } => {
+            return Err(eyre::eyre!(
+                "Related-table conditions cannot be encoded in a query string: {}",
+                table
+            ))
+        }
+        FilterCondition::DateFunction {
+            column, function, ..
+        } => {
+            return Err(eyre::eyre!(
+                "{} conditions cannot be encoded in a query string: {}",
+                function,
+                column
+            ))
+        }
+        FilterCondition::DateRange {
+            column, range_type, ..
+        } => {
+            return match range_type {
+                DateRangeType::Exact(timestamp) => {
+                    Ok((column.clone(), "eq", vec![timestamp.clone()]))
+                }
+                DateRangeType::DateOnly(date) => Ok((column.clone(), "date", vec![date.clone()])),
+                DateRangeType::Range { start, end } => {
+                    Ok((column.clone(), "between", vec![start.clone(), end.clone()]))
+                }
+                DateRangeType::Relative(_) => Err(eyre::eyre!(
+                    "Relative dates cannot be encoded in a query string: {}",
+                    column
+                )),
+            };
+        }
+    };
+
+    let op = match operator {
+        FilterOperator::IsNull => return Ok((column.clone(), "null", vec!["true".to_string()])),
+        FilterOperator::IsNotNull => {
+            return Ok((column.clone(), "null", vec!["false".to_string()]))
+        }
+        _ => encode_op(column, operator)?,
+    };
+
+    let value = value.ok_or_else(|| eyre::eyre!("Missing value for filter on {}", column))?;
+    Ok((column.clone(), op, vec![value]))
+}
+
+/// The query string op for `operator`
+fn encode_op(column: &str, operator: &FilterOperator) -> Result<&'static str> {
+    let op = match operator {
+        FilterOperator::Equal | FilterOperator::DateEqual => "eq",
+        FilterOperator::NotEqual => "ne",
+        FilterOperator::IsDistinctFrom => "distinct",
+        FilterOperator::IsNotDistinctFrom => "ndistinct",
+        FilterOperator::GreaterThan => "gt",
+        FilterOperator::GreaterThanOrEqual => "gte",
+        FilterOperator::LessThan => "lt",
+        FilterOperator::LessThanOrEqual => "lte",
+        FilterOperator::Like => "like",
+        FilterOperator::NotLike => "nlike",
+        FilterOperator::ILike => "ilike",
+        FilterOperator::In => "in",
+        FilterOperator::NotIn => "nin",
+        FilterOperator::IsNull | FilterOperator::IsNotNull => "null",
+        FilterOperator::StartsWith => "starts",
+        FilterOperator::EndsWith => "ends",
+        FilterOperator::Contains => "contains",
+        FilterOperator::Overlaps => "overlaps",
+        FilterOperator::Similar => "similar",
+        FilterOperator::WordSimilar => "wsimilar",
+        FilterOperator::Between
+        | FilterOperator::NotBetween
+        | FilterOperator::BetweenSymmetric
+        | FilterOperator::NotBetweenSymmetric
+        | FilterOperator::DateRange
+        | FilterOperator::RelativeDate => {
+            return Err(eyre::eyre!(
+                "Operator {:?} cannot be encoded in a query string: {}",
+                operator,
+                column
+            ))
+        }
+    };
+    Ok(op)
+}
+
+/// Percent-encodes everything except unreserved characters and commas
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b',' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decodes `+` and `%XX` escapes; a `%` not followed by two hex digits is kept as is
+pub(crate) fn decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| eyre::eyre!("Invalid UTF-8 in query string: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relation::{RelatedRows, Relation};
+    use crate::sorting::SortOrder;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("status", ColumnDef::Text("status"));
+        columns.insert("tags", ColumnDef::TextArray("tags"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        columns
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let columns = setup_test_columns();
+        let query = QueryString::parse(
+            "?filter[age][gte]=21&filter[name][ilike]=jo%&filter[active]=true&sort=-created_at,name&page=2&per_page=50&utm_source=mail",
+            &columns,
+        )?;
+
+        assert_eq!(
+            query.expressions,
+            vec![
+                FilterExpression::Condition(FilterCondition::integer(
+                    "age",
+                    FilterOperator::GreaterThanOrEqual,
+                    Some(21)
+                )),
+                FilterExpression::Condition(FilterCondition::text(
+                    "name",
+                    FilterOperator::ILike,
+                    Some("jo%")
+                )),
+                FilterExpression::Condition(FilterCondition::boolean(
+                    "active",
+                    FilterOperator::Equal,
+                    Some(true)
+                )),
+            ]
+        );
+        assert_eq!(query.sorting.len(), 2);
+        assert_eq!(query.sorting[0].column, "created_at");
+        assert_eq!(query.sorting[0].order, SortOrder::Desc);
+        assert_eq!(query.page, Some(2));
+        assert_eq!(query.per_page, Some(50));
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let columns = setup_test_columns();
+        let canonical = "filter[status][in]=new,open&filter[name][starts]=O%27Brien%20%26%20Co&filter[tags][overlaps]=a,b&filter[created_at][between]=2024-01-01,2024-01-31&filter[created_at][date]=2024-02-01&filter[age][null]=false&sort=-created_at,name&page=3&per_page=25";
+
+        let query = QueryString::parse(canonical, &columns)?;
+        assert_eq!(query.to_query_string()?, canonical);
+
+        let filters = query.to_pg_filters(columns, 100, 1000)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (status IN ('new', 'open') AND name LIKE 'O''Brien & Co%' AND tags && ARRAY['a','b']::text[] AND created_at >= '2024-01-01' AND created_at < ('2024-01-31')::date + interval '1 day' AND created_at >= '2024-02-01 00:00:00' AND created_at < ('2024-02-01')::date + interval '1 day' AND age IS NOT NULL) ORDER BY created_at DESC, name ASC LIMIT 25 OFFSET 50"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_values_with_commas() -> Result<()> {
+        let columns = setup_test_columns();
+        let canonical = "filter[name][in]=Smith%2C%20J,Doe&filter[tags][overlaps]=a%2Cb,c&filter[status][eq]=x%2Cy";
+
+        let query = QueryString::parse(canonical, &columns)?;
+        assert_eq!(
+            query.expressions,
+            vec![
+                FilterExpression::Condition(ColumnDef::Text("name").to_in_condition(
+                    FilterOperator::In,
+                    vec!["Smith, J".to_string(), "Doe".to_string()],
+                )?),
+                FilterExpression::Condition(FilterCondition::ArrayOverlap {
+                    column: "tags".to_string(),
+                    operator: FilterOperator::Overlaps,
+                    values: vec!["a,b".to_string(), "c".to_string()],
+                }),
+                FilterExpression::Condition(FilterCondition::text(
+                    "status",
+                    FilterOperator::Equal,
+                    Some("x,y"),
+                )),
+            ]
+        );
+        assert_eq!(query.to_query_string()?, canonical);
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_insensitive_round_trip() -> Result<()> {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(
+            None,
+            vec![],
+            Some(FilteringOptions::new(
+                vec![FilterExpression::Condition(FilterCondition::text(
+                    "name",
+                    FilterOperator::Equal,
+                    Some("Jo"),
+                ))],
+                columns.clone(),
+            )),
+            columns.clone(),
+        )?;
+
+        let encoded = QueryString::from_pg_filters(&filters).to_query_string()?;
+        assert_eq!(encoded, "filter[name][eq]=Jo&case_insensitive=true");
+
+        let query = QueryString::parse(&encoded, &columns)?;
+        assert!(query.case_insensitive);
+        assert_eq!(query.to_pg_filters(columns, 100, 0)?.sql()?, filters.sql()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_negated_conditions() -> Result<()> {
+        let columns = setup_test_columns();
+        let canonical = "filter[status][not][in]=new,open&filter[age][not][eq]=3";
+
+        let query = QueryString::parse(canonical, &columns)?;
+        assert_eq!(query.to_query_string()?, canonical);
+        assert_eq!(
+            query.to_pg_filters(columns.clone(), 100, 0)?.sql()?,
+            " WHERE (NOT (status IN ('new', 'open')) AND NOT (age = 3))"
+        );
+
+        let query = QueryString::parse("filter[age][not]=3", &columns)?;
+        assert_eq!(query.to_query_string()?, "filter[age][not][eq]=3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_conditions() -> Result<()> {
+        let columns = setup_test_columns();
+        let canonical = "filter[status][distinct]=new&filter[age][ndistinct]=3";
+
+        let query = QueryString::parse(canonical, &columns)?;
+        assert_eq!(query.to_query_string()?, canonical);
+        assert_eq!(
+            query.to_pg_filters(columns.clone(), 100, 0)?.sql()?,
+            " WHERE (status IS DISTINCT FROM 'new' AND age IS NOT DISTINCT FROM 3)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_conditions() -> Result<()> {
+        let columns = setup_test_columns();
+        let canonical = "filter[name][similar]=jon&filter[status][wsimilar]=open";
+
+        let query = QueryString::parse(canonical, &columns)?;
+        assert_eq!(query.to_query_string()?, canonical);
+        assert_eq!(
+            query.to_pg_filters(columns.clone(), 100, 0)?.sql()?,
+            " WHERE (name % 'jon' AND 'open' <% status)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_pg_filters_next_page() -> Result<()> {
+        let columns = setup_test_columns();
+        let query =
+            QueryString::parse("filter[age][lt]=30&sort=name&page=1&per_page=10", &columns)?;
+        let filters = query.to_pg_filters(columns, 100, 35)?;
+
+        let current = QueryString::from_pg_filters(&filters);
+        let next = current.with_page(filters.pagination.as_ref().unwrap().pagination.next_page);
+        assert_eq!(
+            next.to_query_string()?,
+            "filter[age][lt]=30&sort=name&page=2&per_page=10"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let columns = setup_test_columns();
+        let cases = [
+            (
+                "filter[password]=x",
+                "Cannot filter by unknown column: password",
+            ),
+            ("filter[age][approx]=3", "Invalid operator: approx"),
+            ("filter[age][gt]=old", "Invalid integer value: old"),
+            ("filter[age][null]=maybe", "Invalid null value: maybe"),
+            (
+                "filter[name][date]=2024-01-01",
+                "Date filters are only supported on date and timestamp columns: name",
+            ),
+            ("filter[age", "Invalid filter parameter: filter[age"),
+            ("page=two", "Invalid page value: two"),
+            (
+                "case_insensitive=yes",
+                "Invalid case_insensitive value: yes",
+            ),
+            (
+                "filter[created_at][between]=2024-01-01",
+                "Invalid between value: 2024-01-01",
+            ),
+            ("sort=-password", "Cannot sort by unknown column: password"),
+        ];
+
+        for (query, message) in cases {
+            let error = QueryString::parse(query, &columns).err().unwrap();
+            assert_eq!(error.to_string(), message, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let query = QueryString {
+            expressions: vec![FilterExpression::or(vec![
+                FilterExpression::Condition(FilterCondition::integer(
+                    "age",
+                    FilterOperator::Equal,
+                    Some(1),
+                )),
+                FilterExpression::Condition(FilterCondition::integer(
+                    "age",
+                    FilterOperator::Equal,
+                    Some(2),
+                )),
+            ])],
+            ..Default::default()
+        };
+        assert!(query.to_query_string().is_err());
+
+        let age = FilterExpression::Condition(FilterCondition::integer(
+            "age",
+            FilterOperator::Equal,
+            Some(1),
+        ));
+        for expression in [
+            FilterExpression::negate(FilterExpression::and(vec![age.clone()])),
+            FilterExpression::negate_null_safe(age),
+        ] {
+            let query = QueryString {
+                expressions: vec![expression],
+                ..Default::default()
+            };
+            assert!(query.to_query_string().is_err());
+        }
+
+        let query = QueryString {
+            expressions: vec![FilterExpression::Condition(FilterCondition::relative_date(
+                "created_at",
+                "last 1 day".parse().unwrap(),
+            ))],
+            ..Default::default()
+        };
+        assert!(query.to_query_string().is_err());
+
+        let query = QueryString {
+            expressions: vec![FilterExpression::Condition(FilterCondition::similarity(
+                "name",
+                FilterOperator::Similar,
+                "jon",
+                Some(0.5),
+            ))],
+            ..Default::default()
+        };
+        assert_eq!(
+            query.to_query_string().err().unwrap().to_string(),
+            "Similarity thresholds cannot be encoded in a query string: name"
+        );
+
+        let query = QueryString {
+            expressions: vec![FilterExpression::Condition(
+                FilterCondition::text("name", FilterOperator::Equal, Some("Zoë"))
+                    .unaccent("unaccent"),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            query.to_query_string().err().unwrap().to_string(),
+            "Accent-insensitive conditions cannot be encoded in a query string: name"
+        );
+
+        let relation = Relation::new("order_items", HashMap::new()).key("order_id", "orders.id");
+        let query = QueryString {
+            expressions: vec![FilterExpression::Condition(
+                relation.condition(RelatedRows::Any, None),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            query.to_query_string().err().unwrap().to_string(),
+            "Related-table conditions cannot be encoded in a query string: order_items"
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() -> Result<()> {
+        assert_eq!(encode("jo%"), "jo%25");
+        assert_eq!(encode("a b,c"), "a%20b,c");
+        assert_eq!(encode("é"), "%C3%A9");
+        assert_eq!(decode("a+b%20c")?, "a b c");
+        assert_eq!(decode("jo%")?, "jo%");
+        assert_eq!(decode("%C3%A9")?, "é");
+        assert!(decode("%FF").is_err());
+        Ok(())
+    }
+}
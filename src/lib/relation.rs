@@ -0,0 +1,462 @@
+//! Relation module
+//!
+//! This module filters rows by the rows of a related table, e.g. orders by their line items or
+//! customers by their tickets. A `Relation` names the related table, the keys joining it to the
+//! filtered table and the columns it may be filtered by; its conditions compile into correlated
+//! subqueries:
+//!
+//! ```text
+//! items.sku = 'X'     =>  EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND sku = 'X')
+//! NONE items.sku = 'X' => NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND sku = 'X')
+//! ALL items.qty > 0   =>  NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND (qty > 0) IS NOT TRUE)
+//! items COUNT >= 2    =>  (SELECT COUNT(*) FROM order_items WHERE order_id = orders.id) >= 2
+//! ```
+//!
+//! Columns of the filtered table in the join keys should be qualified with its name or alias,
+//! e.g. `orders.id`, as unqualified names in the subquery refer to the related table first.
+//!
+//! In JSON filters, `n` is a relation name followed by one of its columns, e.g. `items.sku`,
+//! and `f` is an operator for that column, optionally prefixed with the quantifier `ANY`, the
+//! default, `ALL` or `NONE`. A bare relation name takes `EXISTS`, `NOT EXISTS` or `COUNT`
+//! followed by a comparison operator with a non-negative integer value.
+//!
+//! Each JSON filter is a subquery of its own: `items.sku = 'X'` and `items.qty > 5` may be met
+//! by different items, and a JSON `COUNT` counts every related row. Conditions on the same
+//! related row, or a count of matching rows, are built with `Relation::condition`, whose filter
+//! may be any expression on the related columns.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::{FilterBuilder, JsonFilter};
+//! use pg_filters::relation::Relation;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut item_columns = HashMap::new();
+//! item_columns.insert("sku", ColumnDef::Text("sku"));
+//! let mut relations = HashMap::new();
+//! relations.insert(
+//!     "items",
+//!     Relation::new("order_items", item_columns).key("order_id", "orders.id"),
+//! );
+//!
+//! let filters = vec![JsonFilter {
+//!     n: "items.sku".to_string(),
+//!     f: "=".to_string(),
+//!     v: "X-1".into(),
+//!     c: None,
+//! }];
+//! let builder =
+//!     FilterBuilder::from_json_filters_with_relations(&filters, false, &HashMap::new(), &relations)
+//!         .unwrap();
+//! assert_eq!(
+//!     builder.build().unwrap(),
+//!     " WHERE EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND sku = 'X-1')"
+//! );
+//! ```
+
+use crate::filtering::{
+    FilterBuilder, FilterCondition, FilterExpression, FilterOperator, JsonFilter,
+};
+use crate::ColumnDef;
+use eyre::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Which related rows a `FilterCondition::Related` requires
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelatedRows {
+    /// At least one related row matches
+    Any,
+    /// Every related row matches, also true without related rows
+    All,
+    /// No related row matches
+    None,
+    /// The number of matching related rows compares with the count by the operator
+    Count(FilterOperator, i64),
+}
+
+/// A table related to the filtered one, with the keys joining them and its filterable columns
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub table: &'static str,
+    /// Pairs of a column of the related table and the column of the filtered table it equals
+    pub keys: Vec<(&'static str, &'static str)>,
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+}
+
+impl Relation {
+    pub fn new(table: &'static str, column_defs: HashMap<&'static str, ColumnDef>) -> Self {
+        Self {
+            table,
+            keys: Vec::new(),
+            column_defs,
+        }
+    }
+
+    /// Joins `related_column` of the related table to `column` of the filtered table, e.g.
+    /// `key("order_id", "orders.id")`
+    pub fn key(mut self, related_column: &'static str, column: &'static str) -> Self {
+        self.keys.push((related_column, column));
+        self
+    }
+
+    /// The condition on the related rows matching `filter`, or on all related rows without one
+    pub fn condition(
+        &self,
+        rows: RelatedRows,
+        filter: Option<FilterExpression>,
+    ) -> FilterCondition {
+        FilterCondition::Related {
+            table: self.table.to_string(),
+            keys: self
+                .keys
+                .iter()
+                .map(|(related_column, column)| (related_column.to_string(), column.to_string()))
+                .collect(),
+            rows,
+            filter: filter.map(Box::new),
+            column_defs: self.column_defs.values().cloned().collect(),
+        }
+    }
+
+    /// The condition of a JSON filter on the relation, with `column` the part of its name after
+    /// the relation name
+    pub(crate) fn json_condition(
+        &self,
+        filter: &JsonFilter,
+        column: Option<&str>,
+    ) -> Result<FilterCondition> {
+        let column = match column {
+            Some(column) => column,
+            None => {
+                let rows = related_rows(filter)?;
+                return Ok(self.condition(rows, None));
+            }
+        };
+
+        if !self.column_defs.contains_key(column) {
+            return Err(eyre::eyre!("Unknown column of relation: {}", filter.n));
+        }
+        let (rows, operator) =
+            split_quantifier(&filter.f).unwrap_or((RelatedRows::Any, filter.f.trim()));
+        let related = JsonFilter {
+            n: column.to_string(),
+            f: operator.to_string(),
+            v: filter.v.clone(),
+            c: None,
+        };
+        let related = FilterBuilder::from_json_filters(&[related], false, &self.column_defs)?;
+        Ok(self.condition(rows, related.root))
+    }
+}
+
+/// The relation a JSON filter name refers to, and the column after the relation name in names
+/// such as `items.sku`
+pub(crate) fn find_relation<'a, 'n>(
+    name: &'n str,
+    relations: &'a HashMap<&str, Relation>,
+) -> Option<(&'a Relation, Option<&'n str>)> {
+    if let Some(relation) = relations.get(name) {
+        return Some((relation, None));
+    }
+    let (relation, column) = name.split_once('.')?;
+    relations
+        .get(relation)
+        .map(|relation| (relation, Some(column)))
+}
+
+/// The quantifier prefix of an operator on a related column, `ANY`, `ALL` or `NONE`, and the
+/// operator after it
+pub(crate) fn split_quantifier(operator: &str) -> Option<(RelatedRows, &str)> {
+    let (quantifier, operator) = operator.trim().split_once(' ')?;
+    let rows = match quantifier.to_uppercase().as_str() {
+        "ANY" => RelatedRows::Any,
+        "ALL" => RelatedRows::All,
+        "NONE" => RelatedRows::None,
+        _ => return None,
+    };
+    Some((rows, operator.trim_start()))
+}
+
+/// The rows required by a JSON filter on a bare relation name: `EXISTS`, `NOT EXISTS` or
+/// `COUNT <operator>` with a count
+pub(crate) fn related_rows(filter: &JsonFilter) -> Result<RelatedRows> {
+    let operator = filter.f.trim().to_uppercase();
+    match operator.as_str() {
+        "EXISTS" => return Ok(RelatedRows::Any),
+        "NOT EXISTS" => return Ok(RelatedRows::None),
+        _ => {}
+    }
+
+    let operator = match operator.strip_prefix("COUNT ") {
+        Some(operator) => count_operator(operator.trim())
+            .ok_or_else(|| eyre::eyre!("Unknown COUNT operator for {}: {}", filter.n, operator))?,
+        None => {
+            return Err(eyre::eyre!(
+                "Unknown operator for relation {}: {}",
+                filter.n,
+                filter.f
+            ))
+        }
+    };
+    let count = match &filter.v {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.trim().parse::<i64>().ok(),
+        _ => None,
+    };
+    match count {
+        Some(count) if count >= 0 => Ok(RelatedRows::Count(operator, count)),
+        _ => Err(eyre::eyre!(
+            "COUNT expects a non-negative integer for {}",
+            filter.n
+        )),
+    }
+}
+
+/// The comparison operators a count of related rows takes
+pub(crate) fn count_operator(operator: &str) -> Option<FilterOperator> {
+    match operator {
+        "=" => Some(FilterOperator::Equal),
+        "!=" => Some(FilterOperator::NotEqual),
+        ">" => Some(FilterOperator::GreaterThan),
+        ">=" => Some(FilterOperator::GreaterThanOrEqual),
+        "<" => Some(FilterOperator::LessThan),
+        "<=" => Some(FilterOperator::LessThanOrEqual),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::QueryParams;
+
+    fn setup_relations() -> HashMap<&'static str, Relation> {
+        let mut item_columns = HashMap::new();
+        item_columns.insert("sku", ColumnDef::Text("sku"));
+        item_columns.insert("quantity", ColumnDef::Integer("quantity"));
+        item_columns.insert("product_id", ColumnDef::Uuid("product_id"));
+
+        let mut ticket_columns = HashMap::new();
+        ticket_columns.insert("status", ColumnDef::Text("status"));
+
+        let mut relations = HashMap::new();
+        relations.insert(
+            "items",
+            Relation::new("order_items", item_columns).key("order_id", "orders.id"),
+        );
+        relations.insert(
+            "tickets",
+            Relation::new("tickets", ticket_columns)
+                .key("customer_id", "orders.customer_id")
+                .key("region", "orders.region"),
+        );
+        relations
+    }
+
+    fn setup_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("id", ColumnDef::Uuid("orders.id"));
+        columns.insert("status", ColumnDef::Text("status"));
+        columns
+    }
+
+    fn filter(n: &str, f: &str, v: impl Into<Value>) -> JsonFilter {
+        JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v: v.into(),
+            c: None,
+        }
+    }
+
+    fn json_sql(filters: &[JsonFilter], case_insensitive: bool) -> Result<String> {
+        FilterBuilder::from_json_filters_with_relations(
+            filters,
+            case_insensitive,
+            &setup_columns(),
+            &setup_relations(),
+        )?
+        .build()
+    }
+
+    #[test]
+    fn test_quantifiers() -> Result<()> {
+        assert_eq!(
+            json_sql(&[filter("items.sku", "=", "X-1")], false)?,
+            " WHERE EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND sku = 'X-1')"
+        );
+        assert_eq!(
+            json_sql(&[filter("items.sku", "any like", "X%")], true)?,
+            " WHERE EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND LOWER(sku) LIKE LOWER('X%'))"
+        );
+        assert_eq!(
+            json_sql(&[filter("items.quantity", "ALL >", 0)], false)?,
+            " WHERE NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND (quantity > 0) IS NOT TRUE)"
+        );
+        assert_eq!(
+            json_sql(&[filter("tickets.status", "NONE =", "open")], false)?,
+            " WHERE NOT EXISTS (SELECT 1 FROM tickets WHERE customer_id = orders.customer_id AND region = orders.region AND status = 'open')"
+        );
+        assert_eq!(
+            json_sql(&[filter("items.quantity", "NOT ANY >=", 10)], false)?,
+            " WHERE NOT (EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND quantity >= 10))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_and_count() -> Result<()> {
+        assert_eq!(
+            json_sql(&[filter("items", "EXISTS", Value::Null)], false)?,
+            " WHERE EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id)"
+        );
+        assert_eq!(
+            json_sql(&[filter("tickets", "not exists", Value::Null)], false)?,
+            " WHERE NOT EXISTS (SELECT 1 FROM tickets WHERE customer_id = orders.customer_id AND region = orders.region)"
+        );
+        assert_eq!(
+            json_sql(
+                &[
+                    filter("status", "=", "paid"),
+                    filter("items", "COUNT >=", "3"),
+                ],
+                false
+            )?,
+            " WHERE (status = 'paid' AND (SELECT COUNT(*) FROM order_items WHERE order_id = orders.id) >= 3)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditions_on_the_same_row() -> Result<()> {
+        let relations = setup_relations();
+        let same_row = FilterExpression::and(vec![
+            FilterExpression::Condition(FilterCondition::text(
+                "sku",
+                FilterOperator::Equal,
+                Some("X-1"),
+            )),
+            FilterExpression::Condition(FilterCondition::integer(
+                "quantity",
+                FilterOperator::GreaterThan,
+                Some(5),
+            )),
+        ]);
+        let condition = relations["items"].condition(
+            RelatedRows::Count(FilterOperator::GreaterThanOrEqual, 2),
+            Some(same_row),
+        );
+        assert_eq!(
+            FilterBuilder::new().add_condition(condition).build()?,
+            " WHERE (SELECT COUNT(*) FROM order_items WHERE order_id = orders.id AND (sku = 'X-1' AND quantity > 5)) >= 2"
+        );
+
+        // Separate JSON filters are separate subqueries
+        assert_eq!(
+            json_sql(
+                &[
+                    filter("items.sku", "=", "X-1"),
+                    filter("items.quantity", ">", 5),
+                ],
+                false
+            )?,
+            " WHERE (EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND sku = 'X-1') AND EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND quantity > 5))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bound_params() -> Result<()> {
+        let relations = setup_relations();
+        let items = &relations["items"];
+        let filter = FilterExpression::Condition(FilterCondition::InValues {
+            column: "product_id".to_string(),
+            operator: FilterOperator::In,
+            values: vec!["a".to_string(), "b".to_string()],
+        });
+        let builder = FilterBuilder::new()
+            .add_condition(items.condition(
+                RelatedRows::Count(FilterOperator::LessThan, 2),
+                Some(filter),
+            ))
+            .add_condition(FilterCondition::in_values(
+                "product_id",
+                FilterOperator::In,
+                vec!["c"],
+            ));
+
+        // The casts of the filtered table do not apply in the subquery and come back after it
+        let mut columns = HashMap::new();
+        columns.insert("product_id", ColumnDef::Text("product_id"));
+        columns.insert("id", ColumnDef::Uuid("id"));
+        let mut params = QueryParams::new().with_column_defs(&columns);
+        assert_eq!(
+            builder.build_with_params(&mut params)?,
+            " WHERE ((SELECT COUNT(*) FROM order_items WHERE order_id = orders.id AND product_id IN ($1::uuid, $2::uuid)) < $3 AND product_id IN ($4))"
+        );
+        assert_eq!(params.values.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms_reach_related_filters() -> Result<()> {
+        let builder = FilterBuilder::from_json_filters_with_relations(
+            &[filter("tickets.status", "!=", "closed")],
+            false,
+            &setup_columns(),
+            &setup_relations(),
+        )?
+        .null_safe_inequality(true);
+        assert_eq!(
+            builder.build()?,
+            " WHERE EXISTS (SELECT 1 FROM tickets WHERE customer_id = orders.customer_id AND region = orders.region AND status IS DISTINCT FROM 'closed')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |filter: JsonFilter| json_sql(&[filter], false).err().unwrap().to_string();
+        assert_eq!(
+            error(filter("items.price", "=", 1)),
+            "Unknown column of relation: items.price"
+        );
+        assert_eq!(
+            error(filter("items", "=", 1)),
+            "Unknown operator for relation items: ="
+        );
+        assert_eq!(
+            error(filter("items", "COUNT LIKE", 1)),
+            "Unknown COUNT operator for items: LIKE"
+        );
+        assert_eq!(
+            error(filter("items", "COUNT >", -1)),
+            "COUNT expects a non-negative integer for items"
+        );
+        assert_eq!(
+            error(filter("items.quantity", "ALL >", Value::Null)),
+            "> requires a value for quantity"
+        );
+
+        let relation = Relation::new("order_items", HashMap::new());
+        let condition = relation.condition(RelatedRows::All, None);
+        assert_eq!(
+            condition.to_sql(false).err().unwrap().to_string(),
+            "Relation order_items has no join keys"
+        );
+        let relation = relation.key("order_id", "orders.id");
+        let condition = relation.condition(RelatedRows::All, None);
+        assert_eq!(
+            condition.to_sql(false).err().unwrap().to_string(),
+            "ALL requires a filter on order_items"
+        );
+        let condition = relation.condition(RelatedRows::Count(FilterOperator::Like, 1), None);
+        assert_eq!(
+            condition.to_sql(false).err().unwrap().to_string(),
+            "Invalid count operator: LIKE"
+        );
+    }
+}
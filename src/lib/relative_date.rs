@@ -0,0 +1,371 @@
+//! Relative date module
+//!
+//! This module parses the values of `RELATIVE` date filters. Instead of SQL, a relative date is
+//! a short phrase that is compiled to bound interval arithmetic on the current time:
+//!
+//! | phrase                | matches                                              |
+//! |-----------------------|------------------------------------------------------|
+//! | `today`               | the current day                                      |
+//! | `yesterday`           | the day before the current day                       |
+//! | `this month`          | the current calendar month                           |
+//! | `previous quarter`    | the calendar quarter before the current one          |
+//! | `previous 2 weeks`    | the two calendar weeks before the current one        |
+//! | `last 7 days`         | from 7 days ago until now                            |
+//! | `next 2 weeks`        | from now until 2 weeks ahead                         |
+//! | `older than 90 days`  | before 90 days ago                                   |
+//!
+//! Units are `minute`, `hour`, `day`, `week`, `month`, `quarter` and `year`, in the singular or
+//! plural. The count defaults to 1 and phrases are case-insensitive. Calendar weeks start on
+//! Monday.
+//!
+//! Date and timestamp columns compare with `LOCALTIMESTAMP`, timestamptz columns with `now()`,
+//! or with the local time in the time zone given to `FilterBuilder::time_zone`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::FilterCondition;
+//! use pg_filters::relative_date::RelativeDate;
+//!
+//! let last_week: RelativeDate = "last 7 days".parse().unwrap();
+//! assert_eq!(
+//!     FilterCondition::relative_date("created_at", last_week).to_string(),
+//!     "created_at >= LOCALTIMESTAMP - ('7 days')::interval AND created_at < LOCALTIMESTAMP"
+//! );
+//!
+//! assert!("now() - interval '1 day'".parse::<RelativeDate>().is_err());
+//! ```
+
+use crate::filtering::SqlValues;
+use eyre::Result;
+use std::fmt;
+use std::str::FromStr;
+
+/// The largest count of a relative date, which keeps intervals well within PostgreSQL's range
+const MAX_COUNT: u32 = 10_000;
+
+/// A calendar unit of a relative date or `date_trunc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl DateUnit {
+    pub(crate) const ALL: [DateUnit; 7] = [
+        DateUnit::Minute,
+        DateUnit::Hour,
+        DateUnit::Day,
+        DateUnit::Week,
+        DateUnit::Month,
+        DateUnit::Quarter,
+        DateUnit::Year,
+    ];
+
+    /// The `date_trunc` field of the unit
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateUnit::Minute => "minute",
+            DateUnit::Hour => "hour",
+            DateUnit::Day => "day",
+            DateUnit::Week => "week",
+            DateUnit::Month => "month",
+            DateUnit::Quarter => "quarter",
+            DateUnit::Year => "year",
+        }
+    }
+
+    pub(crate) fn parse(unit: &str) -> Option<Self> {
+        let unit = unit.strip_suffix('s').unwrap_or(unit);
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == unit)
+    }
+
+    /// `count` units as PostgreSQL interval input, e.g. `7 days`
+    fn interval(&self, count: u32) -> String {
+        match self {
+            // Interval input has no quarters
+            DateUnit::Quarter => plural(count * 3, "month"),
+            _ => plural(count, self.as_str()),
+        }
+    }
+}
+
+fn plural(count: u32, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// A time span relative to the current time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeDate {
+    /// The current calendar unit, e.g. `this month`; `today` is `This(Day)`
+    This(DateUnit),
+    /// The `count` calendar units before the current one, e.g. `previous quarter`;
+    /// `yesterday` is `Previous(1, Day)`
+    Previous(u32, DateUnit),
+    /// From `count` units ago until now, e.g. `last 7 days`
+    Last(u32, DateUnit),
+    /// From now until `count` units ahead, e.g. `next 2 weeks`
+    Next(u32, DateUnit),
+    /// Before `count` units ago, e.g. `older than 90 days`
+    OlderThan(u32, DateUnit),
+}
+
+impl FromStr for RelativeDate {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let lowercase = value.to_lowercase();
+        let words = lowercase.split_whitespace().collect::<Vec<_>>();
+        let invalid = || eyre::eyre!("Invalid relative date: {}", value);
+
+        type Constructor = fn(u32, DateUnit) -> RelativeDate;
+        let (constructor, rest): (Constructor, &[&str]) = match words.as_slice() {
+            ["today"] => return Ok(RelativeDate::This(DateUnit::Day)),
+            ["yesterday"] => return Ok(RelativeDate::Previous(1, DateUnit::Day)),
+            ["this", unit] => {
+                return DateUnit::parse(unit)
+                    .map(RelativeDate::This)
+                    .ok_or_else(invalid)
+            }
+            ["previous", rest @ ..] => (RelativeDate::Previous, rest),
+            ["last", rest @ ..] => (RelativeDate::Last, rest),
+            ["next", rest @ ..] => (RelativeDate::Next, rest),
+            ["older", "than", rest @ ..] => (RelativeDate::OlderThan, rest),
+            _ => return Err(invalid()),
+        };
+
+        let (count, unit) = match rest {
+            [unit] => (Some(1), unit),
+            [count, unit] => (count.parse::<u32>().ok(), unit),
+            _ => return Err(invalid()),
+        };
+        match (count, DateUnit::parse(unit)) {
+            (Some(count @ 1..=MAX_COUNT), Some(unit)) => Ok(constructor(count, unit)),
+            (Some(_), Some(_)) => Err(eyre::eyre!(
+                "Relative date count must be between 1 and {}: {}",
+                MAX_COUNT,
+                value
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for RelativeDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RelativeDate::This(DateUnit::Day) => write!(f, "today"),
+            RelativeDate::This(unit) => write!(f, "this {}", unit.as_str()),
+            RelativeDate::Previous(1, DateUnit::Day) => write!(f, "yesterday"),
+            RelativeDate::Previous(count, unit) => {
+                write!(f, "previous {}", plural(count, unit.as_str()))
+            }
+            RelativeDate::Last(count, unit) => write!(f, "last {}", plural(count, unit.as_str())),
+            RelativeDate::Next(count, unit) => write!(f, "next {}", plural(count, unit.as_str())),
+            RelativeDate::OlderThan(count, unit) => {
+                write!(f, "older than {}", plural(count, unit.as_str()))
+            }
+        }
+    }
+}
+
+/// The current time a relative date is measured from
+pub(crate) struct Clock<'a> {
+    /// `now()`, `LOCALTIMESTAMP`, or `CURRENT_DATE` so that date columns compare whole days
+    pub(crate) now: &'static str,
+    /// Time zone the current time is taken in, for `timestamptz` columns
+    pub(crate) time_zone: Option<&'a str>,
+}
+
+impl Clock<'_> {
+    fn now(&self, values: &mut SqlValues) -> String {
+        match self.time_zone {
+            Some(zone) => format!("(now() AT TIME ZONE {})", values.text(zone, Some("text"))),
+            None => self.now.to_string(),
+        }
+    }
+
+    fn start_of(&self, unit: DateUnit, values: &mut SqlValues) -> String {
+        let field = values.text(unit.as_str(), Some("text"));
+        format!("date_trunc({}, {})", field, self.now(values))
+    }
+
+    /// Converts a local time back to the column type
+    fn instant(&self, local: String, values: &mut SqlValues) -> String {
+        match self.time_zone {
+            Some(zone) => format!(
+                "({}) AT TIME ZONE {}",
+                local,
+                values.text(zone, Some("text"))
+            ),
+            None => local,
+        }
+    }
+}
+
+fn shift(base: String, sign: char, count: u32, unit: DateUnit, values: &mut SqlValues) -> String {
+    format!(
+        "{} {} {}",
+        base,
+        sign,
+        values.cast(&unit.interval(count), "interval")
+    )
+}
+
+impl RelativeDate {
+    /// Renders `column >= start AND column < end`, or only the end for `OlderThan`
+    pub(crate) fn to_sql(self, column: &str, clock: &Clock, values: &mut SqlValues) -> String {
+        let (start, end) = match self {
+            RelativeDate::This(unit) => {
+                let start = clock.start_of(unit, values);
+                let end = shift(clock.start_of(unit, values), '+', 1, unit, values);
+                (Some(start), end)
+            }
+            RelativeDate::Previous(count, unit) => {
+                let start = shift(clock.start_of(unit, values), '-', count, unit, values);
+                (Some(start), clock.start_of(unit, values))
+            }
+            RelativeDate::Last(count, unit) => {
+                let start = shift(clock.now(values), '-', count, unit, values);
+                (Some(start), clock.now(values))
+            }
+            RelativeDate::Next(count, unit) => {
+                let start = clock.now(values);
+                (
+                    Some(start),
+                    shift(clock.now(values), '+', count, unit, values),
+                )
+            }
+            RelativeDate::OlderThan(count, unit) => {
+                (None, shift(clock.now(values), '-', count, unit, values))
+            }
+        };
+
+        let start = start.map(|start| clock.instant(start, values));
+        let end = clock.instant(end, values);
+        match start {
+            Some(start) => format!("{} >= {} AND {} < {}", column, start, column, end),
+            None => format!("{} < {}", column, end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let cases = [
+            ("today", RelativeDate::This(DateUnit::Day)),
+            ("Yesterday", RelativeDate::Previous(1, DateUnit::Day)),
+            ("this month", RelativeDate::This(DateUnit::Month)),
+            (
+                "previous quarter",
+                RelativeDate::Previous(1, DateUnit::Quarter),
+            ),
+            ("last 7 days", RelativeDate::Last(7, DateUnit::Day)),
+            ("last hour", RelativeDate::Last(1, DateUnit::Hour)),
+            ("next  2 weeks", RelativeDate::Next(2, DateUnit::Week)),
+            (
+                "older than 90 days",
+                RelativeDate::OlderThan(90, DateUnit::Day),
+            ),
+            (
+                "OLDER THAN 1 YEAR",
+                RelativeDate::OlderThan(1, DateUnit::Year),
+            ),
+        ];
+        for (phrase, expected) in cases {
+            assert_eq!(phrase.parse::<RelativeDate>()?, expected, "{}", phrase);
+            assert_eq!(expected.to_string().parse::<RelativeDate>()?, expected);
+        }
+
+        for invalid in [
+            "",
+            "now() - interval '1 day'",
+            "last 0 days",
+            "last -1 days",
+            "last 10001 days",
+            "last 7 fortnights",
+            "this 2 months",
+            "older 90 days",
+            "last 7 days; DROP TABLE users",
+        ] {
+            assert!(invalid.parse::<RelativeDate>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let clock = Clock {
+            now: "now()",
+            time_zone: None,
+        };
+        let sql = |relative: &str| {
+            relative.parse::<RelativeDate>().unwrap().to_sql(
+                "created_at",
+                &clock,
+                &mut SqlValues::inline(),
+            )
+        };
+
+        assert_eq!(
+            sql("this month"),
+            "created_at >= date_trunc('month', now()) AND created_at < date_trunc('month', now()) + ('1 month')::interval"
+        );
+        assert_eq!(
+            sql("previous 2 quarters"),
+            "created_at >= date_trunc('quarter', now()) - ('6 months')::interval AND created_at < date_trunc('quarter', now())"
+        );
+        assert_eq!(
+            sql("next 2 weeks"),
+            "created_at >= now() AND created_at < now() + ('2 weeks')::interval"
+        );
+        assert_eq!(
+            sql("older than 90 days"),
+            "created_at < now() - ('90 days')::interval"
+        );
+
+        let clock = Clock {
+            now: "CURRENT_DATE",
+            time_zone: None,
+        };
+        let sql = |relative: &str| {
+            relative.parse::<RelativeDate>().unwrap().to_sql(
+                "birthday",
+                &clock,
+                &mut SqlValues::inline(),
+            )
+        };
+
+        assert_eq!(
+            sql("today"),
+            "birthday >= date_trunc('day', CURRENT_DATE) AND birthday < date_trunc('day', CURRENT_DATE) + ('1 day')::interval"
+        );
+        assert_eq!(
+            sql("last 7 days"),
+            "birthday >= CURRENT_DATE - ('7 days')::interval AND birthday < CURRENT_DATE"
+        );
+        assert_eq!(
+            sql("next 2 days"),
+            "birthday >= CURRENT_DATE AND birthday < CURRENT_DATE + ('2 days')::interval"
+        );
+        assert_eq!(
+            sql("older than 1 month"),
+            "birthday < CURRENT_DATE - ('1 month')::interval"
+        );
+    }
+}
@@ -0,0 +1,1630 @@
+//! Request module
+//!
+//! This module turns the paging, sorting and filter parameters of an HTTP request into
+//! validated `PgFilters` inputs, independently of any web framework. The framework extractors
+//! are thin wrappers around `FilterRequestConfig::validate`.
+//!
+//! # Request convention
+//!
+//! As a query string:
+//!
+//! ```text
+//! ?page=2&per_page=20&sort=name,-age&filters=[{"n":"name","f":"LIKE","v":"%jo%"}]
+//! ```
+//!
+//! or as a JSON body:
+//!
+//! ```json
+//! {"page": 2, "per_page": 20, "sort": "name,-age", "filters": [{"n": "name", "f": "LIKE", "v": "%jo%"}]}
+//! ```
+//!
+//! - `page` is 1-based and defaults to 1
+//! - `per_page` defaults to `default_per_page` and may not exceed `max_per_page`
+//! - `sort` is a comma-separated list of columns, each prefixed with `-` for descending order.
+//!   The first entry may instead be a text column prefixed with `~`, e.g. `~name,-age`, which
+//!   orders by the trigram similarity of the column to `q`, most similar first
+//! - `filters` is a list of `JsonFilter`s; in a query string it is URL-encoded JSON
+//! - `time_zone` is an optional IANA time zone, e.g. `Australia/Sydney`, in which date filters on
+//!   `TimestampTz` columns start and end their days. Only the form of the name is validated; a
+//!   well-formed but unknown zone such as `Mars/Olympus` passes validation and fails when the
+//!   query runs
+//! - `q` is an optional free-text search across the `search_columns`, combined with the filters
+//!   with AND; see the `search` module
+//!
+//! Every filter and sort column must be a key of `column_defs`, except that filters may also
+//! refer to a configured relation or one of its columns, e.g. `items.sku`; see the `relation`
+//! module. `RELATIVE` date filters, whose values are parsed by `RelativeDate`, are rejected
+//! unless `allow_relative_dates` is set.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::request::{FilterErrorCode, FilterRequest, FilterRequestConfig};
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//! let config = FilterRequestConfig::new(columns).max_per_page(50);
+//!
+//! let request: FilterRequest = serde_json::from_str(
+//!     r#"{"page": 2, "sort": "-age", "filters": [{"n": "age", "f": ">", "v": "18"}]}"#,
+//! )
+//! .unwrap();
+//! let filters = config.validate(&request).unwrap().to_pg_filters(0).unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE age > 18 ORDER BY age DESC LIMIT 20 OFFSET 20"
+//! );
+//!
+//! let request: FilterRequest = serde_json::from_str(r#"{"sort": "password"}"#).unwrap();
+//! let error = config.validate(&request).err().unwrap();
+//! assert_eq!(error.code, FilterErrorCode::UnknownColumn);
+//! ```
+
+use crate::dates;
+use crate::filtering::{
+    between_condition, date_function_condition, date_function_operator, scalar_text,
+    similarity_condition, split_date_function, DateFunction, FilterBuilder, FilterExpression,
+    FilterOperator, JsonFilter,
+};
+use crate::relation::{count_operator, find_relation, related_rows, split_quantifier, Relation};
+use crate::relative_date::RelativeDate;
+use crate::search::Search;
+use crate::sorting::{parse_sort, SimilaritySort, SortedColumn};
+use crate::{ColumnDef, FilteringOptions, PaginationOptions, PgFilters};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+const OPERATORS: [&str; 27] = [
+    "=",
+    "!=",
+    "IS DISTINCT FROM",
+    "IS NOT DISTINCT FROM",
+    ">",
+    ">=",
+    "<",
+    "<=",
+    "LIKE",
+    "ILIKE",
+    "IN",
+    "NOT IN",
+    "BETWEEN",
+    "NOT BETWEEN",
+    "BETWEEN SYMMETRIC",
+    "NOT BETWEEN SYMMETRIC",
+    "IS NULL",
+    "IS NOT NULL",
+    "STARTS WITH",
+    "ENDS WITH",
+    "CONTAINS",
+    "OVERLAPS",
+    "SIMILAR",
+    "WORD_SIMILAR",
+    "DATE_ONLY",
+    "DATE_RANGE",
+    "RELATIVE",
+];
+
+const COMPARISON_OPERATORS: [&str; 8] = [
+    "=",
+    "!=",
+    "IS DISTINCT FROM",
+    "IS NOT DISTINCT FROM",
+    ">",
+    ">=",
+    "<",
+    "<=",
+];
+
+/// Paging, sorting and filter parameters as sent by a client
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FilterRequest {
+    /// 1-based page number
+    pub page: Option<i64>,
+    /// Rows per page
+    pub per_page: Option<i64>,
+    /// Comma-separated sort columns, each prefixed with `-` for descending order; the first may
+    /// be a text column prefixed with `~` to order by similarity to `q`
+    pub sort: Option<String>,
+    /// Filters, combined as described for `FilterBuilder::from_json_filters`
+    #[serde(default)]
+    pub filters: Vec<JsonFilter>,
+    /// IANA time zone, e.g. `Australia/Sydney`, in which the days of date filters on
+    /// `TimestampTz` columns start and end; only its form is validated, see the module docs
+    pub time_zone: Option<String>,
+    /// Free-text search across the configured search columns
+    pub q: Option<String>,
+}
+
+/// The query string form of a `FilterRequest`, where `filters` is URL-encoded JSON
+#[derive(Debug, Default, Deserialize)]
+pub struct FilterQueryParams {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub sort: Option<String>,
+    pub filters: Option<String>,
+    pub time_zone: Option<String>,
+    pub q: Option<String>,
+}
+
+impl FilterQueryParams {
+    /// Parses the `filters` JSON into a `FilterRequest`
+    pub fn into_request(self) -> Result<FilterRequest, FilterRequestError> {
+        let filters = match self.filters.as_deref().map(str::trim) {
+            None | Some("") => Vec::new(),
+            Some(json) => serde_json::from_str(json).map_err(|e| {
+                FilterRequestError::new(
+                    FilterErrorCode::InvalidFilters,
+                    format!("Invalid filters JSON: {}", e),
+                )
+                .field("filters")
+            })?,
+        };
+
+        Ok(FilterRequest {
+            page: self.page,
+            per_page: self.per_page,
+            sort: self.sort,
+            filters,
+            time_zone: self.time_zone,
+            q: self.q,
+        })
+    }
+}
+
+/// Machine-readable reason a request was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterErrorCode {
+    /// The query string could not be parsed
+    InvalidQuery,
+    /// The request body could not be parsed
+    InvalidBody,
+    /// The filters could not be parsed or combined
+    InvalidFilters,
+    TooManyFilters,
+    /// A filter or sort column is not in `column_defs`
+    UnknownColumn,
+    /// The operator is unknown, not valid for the column type or not allowed
+    InvalidOperator,
+    /// The value does not match the column type
+    InvalidValue,
+    TooManySortColumns,
+    InvalidPage,
+    InvalidPerPage,
+    /// No `FilterRequestConfig` was registered for the route
+    MissingConfig,
+    /// The query could not be built or run; the message does not say why
+    Internal,
+}
+
+impl FilterErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterErrorCode::InvalidQuery => "invalid_query",
+            FilterErrorCode::InvalidBody => "invalid_body",
+            FilterErrorCode::InvalidFilters => "invalid_filters",
+            FilterErrorCode::TooManyFilters => "too_many_filters",
+            FilterErrorCode::UnknownColumn => "unknown_column",
+            FilterErrorCode::InvalidOperator => "invalid_operator",
+            FilterErrorCode::InvalidValue => "invalid_value",
+            FilterErrorCode::TooManySortColumns => "too_many_sort_columns",
+            FilterErrorCode::InvalidPage => "invalid_page",
+            FilterErrorCode::InvalidPerPage => "invalid_per_page",
+            FilterErrorCode::MissingConfig => "missing_config",
+            FilterErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for FilterErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A rejected request, serialized as `{"code": ..., "message": ..., "field": ...}`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterRequestError {
+    pub code: FilterErrorCode,
+    pub message: String,
+    /// The request parameter at fault, e.g. `sort` or `filters[1].v`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl FilterRequestError {
+    pub fn new(code: FilterErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field: None,
+        }
+    }
+
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// An error from building or running the query, e.g. `fetch_page`, reported without its
+    /// details
+    pub fn internal() -> Self {
+        Self::new(FilterErrorCode::Internal, "Internal server error")
+    }
+
+    /// The HTTP status for this error: 500 for `MissingConfig` and `Internal`, otherwise 400
+    pub fn http_status(&self) -> u16 {
+        match self.code {
+            FilterErrorCode::MissingConfig | FilterErrorCode::Internal => 500,
+            _ => 400,
+        }
+    }
+}
+
+impl fmt::Display for FilterRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for FilterRequestError {}
+
+/// The JSON body of an error response: `{"error": {...}}`
+#[derive(Serialize)]
+pub(crate) struct ErrorBody<'a> {
+    pub(crate) error: &'a FilterRequestError,
+}
+
+/// The column definitions and limits a request is validated against
+#[derive(Debug, Clone)]
+pub struct FilterRequestConfig {
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+    pub default_per_page: i64,
+    pub max_per_page: i64,
+    pub max_filters: usize,
+    pub max_sort_columns: usize,
+    pub case_insensitive: bool,
+    pub null_safe_inequality: bool,
+    pub allow_relative_dates: bool,
+    /// Columns searched by `q`, checked against `column_defs` by `search_columns`
+    search: Option<Search>,
+    /// Columns whose text filters and searches ignore accents
+    pub unaccent_columns: Vec<&'static str>,
+    /// Function stripping accents, `unaccent` by default
+    pub unaccent_function: String,
+    /// Related tables filters may refer to, e.g. `items.sku`
+    pub relations: HashMap<&'static str, Relation>,
+}
+
+impl FilterRequestConfig {
+    /// A config with 20 rows per page by default, at most 100 rows per page, 20 filters and
+    /// 5 sort columns, and case-insensitive text matching
+    pub fn new(column_defs: HashMap<&'static str, ColumnDef>) -> Self {
+        Self {
+            column_defs,
+            default_per_page: 20,
+            max_per_page: 100,
+            max_filters: 20,
+            max_sort_columns: 5,
+            case_insensitive: true,
+            null_safe_inequality: false,
+            allow_relative_dates: false,
+            search: None,
+            unaccent_columns: Vec::new(),
+            unaccent_function: "unaccent".to_string(),
+            relations: HashMap::new(),
+        }
+    }
+
+    pub fn default_per_page(mut self, value: i64) -> Self {
+        self.default_per_page = value;
+        self
+    }
+
+    pub fn max_per_page(mut self, value: i64) -> Self {
+        self.max_per_page = value;
+        self
+    }
+
+    pub fn max_filters(mut self, value: usize) -> Self {
+        self.max_filters = value;
+        self
+    }
+
+    pub fn max_sort_columns(mut self, value: usize) -> Self {
+        self.max_sort_columns = value;
+        self
+    }
+
+    pub fn case_insensitive(mut self, value: bool) -> Self {
+        self.case_insensitive = value;
+        self
+    }
+
+    /// Makes `!=` and `NOT IN` filters also match rows where the column is NULL
+    pub fn null_safe_inequality(mut self, value: bool) -> Self {
+        self.null_safe_inequality = value;
+        self
+    }
+
+    /// Allows `RELATIVE` filters such as `last 7 days`
+    pub fn allow_relative_dates(mut self, value: bool) -> Self {
+        self.allow_relative_dates = value;
+        self
+    }
+
+    /// Enables `q` searches across `columns`, which must be keys of `column_defs` and include a
+    /// text column
+    ///
+    /// Fails on a column that cannot be searched, as that is a configuration error rather than
+    /// an error of any request. Set `column_defs` first.
+    pub fn search_columns(mut self, columns: &[&'static str]) -> eyre::Result<Self> {
+        self.search = Some(Search::new(columns, &self.column_defs)?);
+        Ok(self)
+    }
+
+    /// Makes text filters and searches on `columns` ignore accents
+    pub fn unaccent_columns(mut self, columns: &[&'static str]) -> Self {
+        self.unaccent_columns = columns.to_vec();
+        self
+    }
+
+    /// Strips accents with `function` instead of `unaccent`
+    pub fn unaccent_function(mut self, function: impl Into<String>) -> Self {
+        self.unaccent_function = function.into();
+        self
+    }
+
+    /// Allows filters on the rows of `relation` under `name`
+    pub fn relation(mut self, name: &'static str, relation: Relation) -> Self {
+        self.relations.insert(name, relation);
+        self
+    }
+
+    /// Checks `request` against the column definitions and limits
+    pub fn validate(
+        &self,
+        request: &FilterRequest,
+    ) -> Result<ValidatedFilters, FilterRequestError> {
+        let pagination = self.validate_pagination(request)?;
+        let (similarity, sorting) =
+            self.validate_sort(request.sort.as_deref(), request.q.as_deref())?;
+        let time_zone = self.validate_time_zone(request.time_zone.as_deref())?;
+        let search = self.validate_search(request.q.as_deref())?;
+        let filtering = self.validate_filters(&request.filters, search, time_zone)?;
+
+        Ok(ValidatedFilters {
+            pagination,
+            sorting,
+            similarity,
+            filtering,
+            column_defs: self.column_defs.clone(),
+        })
+    }
+
+    fn validate_pagination(
+        &self,
+        request: &FilterRequest,
+    ) -> Result<PaginationOptions, FilterRequestError> {
+        let page = request.page.unwrap_or(1);
+        if page < 1 {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::InvalidPage,
+                "page must be at least 1",
+            )
+            .field("page"));
+        }
+
+        let per_page = request.per_page.unwrap_or(self.default_per_page);
+        if per_page < 1 || per_page > self.max_per_page {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::InvalidPerPage,
+                format!("per_page must be between 1 and {}", self.max_per_page),
+            )
+            .field("per_page"));
+        }
+
+        Ok(PaginationOptions::new(page, per_page, self.max_per_page, 0))
+    }
+
+    fn validate_sort(
+        &self,
+        sort: Option<&str>,
+        q: Option<&str>,
+    ) -> Result<(Option<SimilaritySort>, Vec<SortedColumn>), FilterRequestError> {
+        let sort = sort.unwrap_or_default().trim_start();
+        let (similarity, sort) = match sort.strip_prefix('~') {
+            Some(rest) => {
+                let (column, rest) = rest.split_once(',').unwrap_or((rest, ""));
+                (Some(self.validate_similarity_sort(column.trim(), q)?), rest)
+            }
+            None => (None, sort),
+        };
+
+        let columns = parse_sort(sort);
+        if let Some(misplaced) = columns.iter().find(|sorted| sorted.column.starts_with('~')) {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::InvalidValue,
+                format!(
+                    "Similarity sort must be the first sort entry: {}",
+                    misplaced.column
+                ),
+            )
+            .field("sort"));
+        }
+
+        if let Some(unknown) = columns
+            .iter()
+            .find(|sorted| !self.column_defs.contains_key(sorted.column.as_str()))
+        {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::UnknownColumn,
+                format!("Cannot sort by unknown column: {}", unknown.column),
+            )
+            .field("sort"));
+        }
+
+        if columns.len() + usize::from(similarity.is_some()) > self.max_sort_columns {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::TooManySortColumns,
+                format!("At most {} sort columns are allowed", self.max_sort_columns),
+            )
+            .field("sort"));
+        }
+
+        Ok((similarity, columns))
+    }
+
+    fn validate_similarity_sort(
+        &self,
+        column: &str,
+        q: Option<&str>,
+    ) -> Result<SimilaritySort, FilterRequestError> {
+        let column_def = self.column_defs.get(column).ok_or_else(|| {
+            FilterRequestError::new(
+                FilterErrorCode::UnknownColumn,
+                format!("Cannot sort by unknown column: {}", column),
+            )
+            .field("sort")
+        })?;
+        if !matches!(
+            column_def,
+            ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_)
+        ) {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::InvalidValue,
+                format!(
+                    "Similarity sort is only supported on text columns: {}",
+                    column
+                ),
+            )
+            .field("sort"));
+        }
+
+        match q.map(str::trim).filter(|q| !q.is_empty()) {
+            Some(q) => Ok(SimilaritySort::new(&column_def.get_column_name(), q)),
+            None => Err(FilterRequestError::new(
+                FilterErrorCode::InvalidValue,
+                format!("Similarity sort by {} needs a search query q", column),
+            )
+            .field("sort")),
+        }
+    }
+
+    fn validate_time_zone<'a>(
+        &self,
+        time_zone: Option<&'a str>,
+    ) -> Result<Option<&'a str>, FilterRequestError> {
+        match time_zone {
+            Some(time_zone) if !dates::is_time_zone(time_zone) => Err(FilterRequestError::new(
+                FilterErrorCode::InvalidValue,
+                format!("Invalid time zone: {}", time_zone),
+            )
+            .field("time_zone")),
+            _ => Ok(time_zone),
+        }
+    }
+
+    fn validate_search(
+        &self,
+        query: Option<&str>,
+    ) -> Result<Option<FilterExpression>, FilterRequestError> {
+        let query = match query {
+            Some(query) => query,
+            None => return Ok(None),
+        };
+        let search = self.search.as_ref().ok_or_else(|| {
+            FilterRequestError::new(FilterErrorCode::InvalidValue, "Search is not supported")
+                .field("q")
+        })?;
+        search.expression(query).map_err(|e| {
+            FilterRequestError::new(FilterErrorCode::InvalidValue, e.to_string()).field("q")
+        })
+    }
+
+    fn validate_filters(
+        &self,
+        filters: &[JsonFilter],
+        search: Option<FilterExpression>,
+        time_zone: Option<&str>,
+    ) -> Result<Option<FilteringOptions>, FilterRequestError> {
+        if filters.len() > self.max_filters {
+            return Err(FilterRequestError::new(
+                FilterErrorCode::TooManyFilters,
+                format!("At most {} filters are allowed", self.max_filters),
+            )
+            .field("filters"));
+        }
+
+        for (index, filter) in filters.iter().enumerate() {
+            self.validate_filter(filter)
+                .map_err(|(key, error)| error.field(format!("filters[{}].{}", index, key)))?;
+        }
+
+        let builder = FilterBuilder::from_json_filters_with_relations(
+            filters,
+            self.case_insensitive,
+            &self.column_defs,
+            &self.relations,
+        )
+        .map_err(|e| {
+            FilterRequestError::new(FilterErrorCode::InvalidFilters, e.to_string()).field("filters")
+        })?;
+
+        let expressions = builder.root.into_iter().chain(search).collect::<Vec<_>>();
+        if expressions.is_empty() {
+            return Ok(None);
+        }
+
+        // An unknown unaccent column is a configuration error
+        let unaccent_columns = self
+            .unaccent_columns
+            .iter()
+            .map(|column| match self.column_defs.get(column) {
+                Some(column_def) => Ok(column_def.get_column_name()),
+                None => Err(FilterRequestError::internal()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(FilteringOptions {
+            expressions,
+            case_insensitive: self.case_insensitive,
+            null_safe_inequality: self.null_safe_inequality,
+            time_zone: time_zone.map(ToString::to_string),
+            unaccent_columns,
+            unaccent_function: self.unaccent_function.clone(),
+            column_defs: self.column_defs.clone(),
+        }))
+    }
+
+    fn validate_filter(
+        &self,
+        filter: &JsonFilter,
+    ) -> Result<(), (&'static str, FilterRequestError)> {
+        let unknown_column = || {
+            (
+                "n",
+                FilterRequestError::new(
+                    FilterErrorCode::UnknownColumn,
+                    format!("Cannot filter by unknown column: {}", filter.n),
+                ),
+            )
+        };
+        let column_def = match self.column_defs.get(filter.n.as_str()) {
+            Some(column_def) => column_def,
+            None => {
+                let (relation, column) =
+                    find_relation(&filter.n, &self.relations).ok_or_else(unknown_column)?;
+                if column.is_some_and(|column| !relation.column_defs.contains_key(column)) {
+                    return Err(unknown_column());
+                }
+                validate_connector(filter)?;
+                return self.validate_related_filter(filter, relation, column);
+            }
+        };
+        validate_connector(filter)?;
+
+        let invalid_operator = |message: String| {
+            (
+                "f",
+                FilterRequestError::new(FilterErrorCode::InvalidOperator, message),
+            )
+        };
+        // "NOT <date function>" is validated as the date function it negates
+        let unnegated = match filter.f.get(..4) {
+            Some(not) if not.eq_ignore_ascii_case("NOT ") => filter.f[4..].trim_start(),
+            _ => filter.f.as_str(),
+        };
+        if let Some((function, operator)) = split_date_function(unnegated) {
+            DateFunction::parse(function).map_err(|error| invalid_operator(error.to_string()))?;
+            if column_def.date_column_type().is_none() {
+                return Err(invalid_operator(format!(
+                    "{} is only supported on date and timestamp columns",
+                    function
+                )));
+            }
+            if date_function_operator(operator).is_none() {
+                return Err(invalid_operator(format!(
+                    "Unknown operator for {}: {}",
+                    function, operator
+                )));
+            }
+            let unnegated = JsonFilter {
+                n: filter.n.clone(),
+                f: unnegated.to_string(),
+                v: filter.v.clone(),
+                c: None,
+            };
+            return date_function_condition(&unnegated, column_def)
+                .map(|_| ())
+                .map_err(|error| {
+                    (
+                        "v",
+                        FilterRequestError::new(FilterErrorCode::InvalidValue, error.to_string()),
+                    )
+                });
+        }
+
+        // "NOT <operator>" is validated as the operator it negates
+        let operator = filter.f.to_uppercase();
+        let operator = match operator.strip_prefix("NOT ") {
+            Some(negated) if negated != "IN" && !negated.starts_with("BETWEEN") => {
+                negated.to_string()
+            }
+            _ => operator,
+        };
+        if !OPERATORS.contains(&operator.as_str()) {
+            return Err(invalid_operator(format!("Unknown operator: {}", filter.f)));
+        }
+        if matches!(operator.as_str(), "DATE_ONLY" | "DATE_RANGE" | "RELATIVE")
+            && !matches!(
+                column_def,
+                ColumnDef::Date(_) | ColumnDef::Timestamp(_) | ColumnDef::TimestampTz(_)
+            )
+        {
+            return Err(invalid_operator(format!(
+                "{} is only supported on date and timestamp columns",
+                operator
+            )));
+        }
+        if matches!(operator.as_str(), "SIMILAR" | "WORD_SIMILAR")
+            && !matches!(
+                column_def,
+                ColumnDef::Text(_)
+                    | ColumnDef::Varchar(_)
+                    | ColumnDef::Char(_)
+                    | ColumnDef::CiText(_)
+            )
+        {
+            return Err(invalid_operator(format!(
+                "{} is only supported on text columns",
+                operator
+            )));
+        }
+        if operator == "RELATIVE" && !self.allow_relative_dates {
+            return Err(invalid_operator(
+                "RELATIVE filters are not allowed".to_string(),
+            ));
+        }
+
+        let text = filter.text();
+        let shown = text.clone().unwrap_or_else(|| filter.v.to_string());
+        let invalid_value = |expected: &str| {
+            (
+                "v",
+                FilterRequestError::new(
+                    FilterErrorCode::InvalidValue,
+                    format!("Expected {} for {}, got: {}", expected, filter.n, shown),
+                ),
+            )
+        };
+        if operator == "DATE_ONLY" && !text.as_deref().is_some_and(dates::is_date) {
+            return Err(invalid_value("a YYYY-MM-DD date"));
+        }
+        if operator == "RELATIVE"
+            && text
+                .as_deref()
+                .is_none_or(|text| text.parse::<RelativeDate>().is_err())
+        {
+            return Err(invalid_value("a relative date such as last 7 days"));
+        }
+        if operator == "DATE_RANGE" {
+            let bounds = match &filter.v {
+                Value::Array(items) => items.iter().map(scalar_text).collect(),
+                Value::Object(range) => vec![
+                    range.get("start").and_then(scalar_text),
+                    range.get("end").and_then(scalar_text),
+                ],
+                _ => match &text {
+                    Some(text) => text
+                        .split(',')
+                        .map(|s| Some(s.trim().to_string()))
+                        .collect(),
+                    None => Vec::new(),
+                },
+            };
+            let valid = bounds.len() == 2
+                && bounds.iter().all(|bound| {
+                    bound
+                        .as_deref()
+                        .is_some_and(|bound| dates::is_valid_for(column_def, bound))
+                });
+            if !valid {
+                return Err(invalid_value("start,end"));
+            }
+        }
+        if operator.contains("BETWEEN") {
+            let between = match operator.as_str() {
+                "BETWEEN" => FilterOperator::Between,
+                "NOT BETWEEN" => FilterOperator::NotBetween,
+                "BETWEEN SYMMETRIC" => FilterOperator::BetweenSymmetric,
+                _ => FilterOperator::NotBetweenSymmetric,
+            };
+            if let Err(error) = between_condition(filter, between, column_def) {
+                return Err((
+                    "v",
+                    FilterRequestError::new(FilterErrorCode::InvalidValue, error.to_string()),
+                ));
+            }
+        }
+        if matches!(operator.as_str(), "SIMILAR" | "WORD_SIMILAR") {
+            let similar = if operator == "SIMILAR" {
+                FilterOperator::Similar
+            } else {
+                FilterOperator::WordSimilar
+            };
+            if let Err(error) = similarity_condition(filter, similar, column_def) {
+                return Err((
+                    "v",
+                    FilterRequestError::new(FilterErrorCode::InvalidValue, error.to_string()),
+                ));
+            }
+        }
+        if COMPARISON_OPERATORS.contains(&operator.as_str()) {
+            let valid = match &text {
+                Some(text) => match column_def {
+                    ColumnDef::SmallInt(_) => text.parse::<i16>().is_ok(),
+                    ColumnDef::Integer(_) => text.parse::<i32>().is_ok(),
+                    ColumnDef::BigInt(_) => text.parse::<i64>().is_ok(),
+                    ColumnDef::Real(_) => text.parse::<f32>().is_ok(),
+                    ColumnDef::DoublePrecision(_) => text.parse::<f64>().is_ok(),
+                    ColumnDef::Boolean(_) => text.parse::<bool>().is_ok(),
+                    _ => dates::is_valid_for(column_def, text),
+                },
+                // null means IS NULL or IS NOT NULL
+                None => {
+                    filter.v.is_null()
+                        && matches!(
+                            operator.as_str(),
+                            "=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM"
+                        )
+                }
+            };
+            if !valid {
+                return Err(invalid_value("a value of the column type"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates a filter on `relation`, by the filter on `column` it applies to the related
+    /// rows or, without a column, as `EXISTS`, `NOT EXISTS` or `COUNT <operator>`
+    fn validate_related_filter(
+        &self,
+        filter: &JsonFilter,
+        relation: &Relation,
+        column: Option<&str>,
+    ) -> Result<(), (&'static str, FilterRequestError)> {
+        let operator = filter.f.trim();
+        let column = match column {
+            Some(column) => column,
+            None => {
+                let operator = operator.to_uppercase();
+                let operator = match operator.strip_prefix("NOT ") {
+                    Some(negated) if negated != "EXISTS" => negated.to_string(),
+                    _ => operator,
+                };
+                if operator == "EXISTS" || operator == "NOT EXISTS" {
+                    return Ok(());
+                }
+                if operator
+                    .strip_prefix("COUNT ")
+                    .is_none_or(|operator| count_operator(operator.trim()).is_none())
+                {
+                    return Err((
+                        "f",
+                        FilterRequestError::new(
+                            FilterErrorCode::InvalidOperator,
+                            format!("Unknown operator for relation {}: {}", filter.n, filter.f),
+                        ),
+                    ));
+                }
+                let count = JsonFilter {
+                    n: filter.n.clone(),
+                    f: operator,
+                    v: filter.v.clone(),
+                    c: None,
+                };
+                return related_rows(&count).map(|_| ()).map_err(|error| {
+                    (
+                        "v",
+                        FilterRequestError::new(FilterErrorCode::InvalidValue, error.to_string()),
+                    )
+                });
+            }
+        };
+
+        // "NOT <quantifier> <operator>" is validated as the operator it quantifies
+        let quantified = operator
+            .get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("NOT "))
+            .and_then(|_| operator.get(4..))
+            .and_then(split_quantifier)
+            .or_else(|| split_quantifier(operator));
+        let operator = quantified.map_or(operator, |(_, operator)| operator);
+        let related = JsonFilter {
+            n: column.to_string(),
+            f: operator.to_string(),
+            v: filter.v.clone(),
+            c: None,
+        };
+        let config = FilterRequestConfig {
+            column_defs: relation.column_defs.clone(),
+            relations: HashMap::new(),
+            ..self.clone()
+        };
+        config.validate_filter(&related)
+    }
+}
+
+fn validate_connector(filter: &JsonFilter) -> Result<(), (&'static str, FilterRequestError)> {
+    match &filter.c {
+        Some(connector) if connector != "AND" && connector != "OR" => Err((
+            "c",
+            FilterRequestError::new(
+                FilterErrorCode::InvalidFilters,
+                format!("Connector must be AND or OR, got: {}", connector),
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A request that passed validation, ready to build `PgFilters`
+#[derive(Clone)]
+pub struct ValidatedFilters {
+    pub pagination: PaginationOptions,
+    pub sorting: Vec<SortedColumn>,
+    /// Similarity to `q`, sorted before `sorting`
+    pub similarity: Option<SimilaritySort>,
+    pub filtering: Option<FilteringOptions>,
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+}
+
+impl ValidatedFilters {
+    /// Builds `PgFilters` for a result set of `total_records` rows
+    ///
+    /// Pass 0 when using `fetch_page`, which replaces it with the filtered count.
+    pub fn to_pg_filters(&self, total_records: i64) -> eyre::Result<PgFilters> {
+        let mut pagination = self.pagination.clone();
+        pagination.total_records = total_records;
+        let mut filters = PgFilters::new(
+            Some(pagination),
+            self.sorting.clone(),
+            self.filtering.clone(),
+            self.column_defs.clone(),
+        )?;
+        if let Some(similarity) = &self.similarity {
+            filters.sorting = filters
+                .sorting
+                .map(|sorting| sorting.with_similarity(similarity.clone()));
+        }
+        Ok(filters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::QueryParams;
+
+    fn setup_config() -> FilterRequestConfig {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("registration", ColumnDef::Timestamp("registration"));
+        FilterRequestConfig::new(columns)
+    }
+
+    fn filter(n: &str, f: &str, v: impl Into<Value>, c: Option<&str>) -> JsonFilter {
+        JsonFilter {
+            n: n.to_string(),
+            f: f.to_string(),
+            v: v.into(),
+            c: c.map(str::to_string),
+        }
+    }
+
+    fn error_for(request: FilterRequest) -> FilterRequestError {
+        setup_config().validate(&request).err().unwrap()
+    }
+
+    #[test]
+    fn test_validate_builds_pg_filters() -> eyre::Result<()> {
+        let request = FilterRequest {
+            page: Some(3),
+            per_page: Some(10),
+            sort: Some("-age, name".to_string()),
+            filters: vec![
+                filter("name", "LIKE", "%jo%", None),
+                filter("age", ">=", "21", Some("OR")),
+            ],
+            time_zone: None,
+            q: None,
+        };
+
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (LOWER(name) LIKE LOWER('%jo%') OR age >= 21) ORDER BY age DESC, name ASC LIMIT 10 OFFSET 20"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_typed_values() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("age", ">=", 21, None),
+                filter("active", "=", true, None),
+                filter("name", "IN", vec!["Smith, J", "Jones"], None),
+                filter("registration", "!=", Value::Null, None),
+            ],
+            ..Default::default()
+        };
+
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (age >= 21 AND active = true AND LOWER(name) IN (LOWER('Smith, J'), LOWER('Jones')) AND registration IS NOT NULL) LIMIT 20 OFFSET 0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_negated_operators() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("name", "NOT LIKE", "%jo%", None),
+                filter(
+                    "registration",
+                    "not date_range",
+                    "2024-01-01,2024-01-31",
+                    None,
+                ),
+                filter("age", "NOT IN", vec![1, 2], None),
+            ],
+            ..Default::default()
+        };
+
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (NOT (LOWER(name) LIKE LOWER('%jo%')) AND NOT (registration >= '2024-01-01' AND registration < ('2024-01-31')::date + interval '1 day') AND age NOT IN ('1', '2')) LIMIT 20 OFFSET 0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_null_safe_inequality() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("name", "!=", "John", None),
+                filter("age", "NOT IN", vec![1, 2], None),
+                filter("active", "IS DISTINCT FROM", Value::Null, None),
+            ],
+            ..Default::default()
+        };
+
+        let filters = setup_config()
+            .null_safe_inequality(true)
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (LOWER(name) IS DISTINCT FROM LOWER('John') AND (age NOT IN ('1', '2') OR age IS NULL) AND active IS NOT NULL) LIMIT 20 OFFSET 0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_between() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("age", "BETWEEN", vec![18, 30], None),
+                filter("registration", "NOT BETWEEN", "2024-01-01,2024-01-31", None),
+            ],
+            ..Default::default()
+        };
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (age BETWEEN 18 AND 30 AND registration NOT BETWEEN '2024-01-01' AND '2024-01-31') LIMIT 20 OFFSET 0"
+        );
+
+        let error = error_for(FilterRequest {
+            filters: vec![filter("age", "BETWEEN", vec![30, 18], None)],
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("filters[0].v"));
+        assert_eq!(error.message, "Start 30 is greater than end 18 for age");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_date_functions() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("registration", "EXTRACT(ISODOW) IN", vec![6, 7], None),
+                filter("registration", "DATE_TRUNC(MONTH) =", "2024-03-01", None),
+            ],
+            ..Default::default()
+        };
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (EXTRACT(ISODOW FROM registration) IN (6, 7) AND date_trunc('month', registration) = '2024-03-01') LIMIT 20 OFFSET 0"
+        );
+
+        let error = error_for(FilterRequest {
+            filters: vec![filter("registration", "EXTRACT(HOUR) BETWEEN", "9", None)],
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("filters[0].v"));
+        assert_eq!(
+            error.message,
+            "Invalid number of values for EXTRACT(HOUR) BETWEEN: registration"
+        );
+
+        // Negated date functions are accepted as from_json_filters accepts them
+        let request = FilterRequest {
+            filters: vec![filter(
+                "registration",
+                "not EXTRACT(ISODOW) IN",
+                vec![6, 7],
+                None,
+            )],
+            ..Default::default()
+        };
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE NOT (EXTRACT(ISODOW FROM registration) IN (6, 7)) LIMIT 20 OFFSET 0"
+        );
+
+        let error = error_for(FilterRequest {
+            filters: vec![filter("registration", "NOT EXTRACT(MONTH) =", 13, None)],
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_unaccent() -> eyre::Result<()> {
+        let config = setup_config()
+            .search_columns(&["name"])?
+            .unaccent_columns(&["name"]);
+        let request = FilterRequest {
+            filters: vec![filter("name", "=", "Zoe", None)],
+            q: Some("muller".to_string()),
+            ..Default::default()
+        };
+        let filters = config.validate(&request).unwrap().to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (LOWER(unaccent(name)) = LOWER(unaccent('Zoe')) AND LOWER(unaccent(name)) LIKE LOWER(unaccent('%muller%'))) LIMIT 20 OFFSET 0"
+        );
+
+        let error = config
+            .unaccent_columns(&["nickname"])
+            .validate(&request)
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::Internal);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_similarity() -> eyre::Result<()> {
+        let request = FilterRequest {
+            filters: vec![
+                filter("name", "SIMILAR", "jon", None),
+                filter(
+                    "name",
+                    "WORD_SIMILAR",
+                    serde_json::json!({"value": "smith", "threshold": 0.5}),
+                    Some("AND"),
+                ),
+            ],
+            ..Default::default()
+        };
+        let filters = setup_config()
+            .validate(&request)
+            .unwrap()
+            .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (name % 'jon' AND word_similarity('smith', name) >= 0.5) LIMIT 20 OFFSET 0"
+        );
+
+        let error = error_for(FilterRequest {
+            filters: vec![filter("age", "SIMILAR", "42", None)],
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidOperator);
+        assert_eq!(error.field.as_deref(), Some("filters[0].f"));
+
+        let error = error_for(FilterRequest {
+            filters: vec![filter(
+                "name",
+                "SIMILAR",
+                serde_json::json!({"value": "jon", "threshold": 2}),
+                None,
+            )],
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(
+            error.message,
+            "Similarity threshold must be between 0 and 1: 2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_relations() -> eyre::Result<()> {
+        let mut item_columns = HashMap::new();
+        item_columns.insert("sku", ColumnDef::Text("sku"));
+        item_columns.insert("quantity", ColumnDef::Integer("quantity"));
+        let config = setup_config().relation(
+            "items",
+            Relation::new("order_items", item_columns).key("order_id", "orders.id"),
+        );
+        let validate = |filters: Vec<JsonFilter>| {
+            config.validate(&FilterRequest {
+                filters,
+                ..Default::default()
+            })
+        };
+
+        let filters = validate(vec![
+            filter("items.sku", "=", "X-1", None),
+            filter("items.quantity", "NOT ALL >", 1, None),
+            filter("items", "COUNT <", 5, None),
+            filter("items", "NOT EXISTS", Value::Null, Some("OR")),
+        ])
+        .unwrap()
+        .to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND LOWER(sku) = LOWER('X-1')) AND NOT (NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id AND (quantity > 1) IS NOT TRUE)) AND ((SELECT COUNT(*) FROM order_items WHERE order_id = orders.id) < 5 OR NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = orders.id))) LIMIT 20 OFFSET 0"
+        );
+
+        let error = validate(vec![filter("items.price", "=", 1, None)])
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::UnknownColumn);
+        assert_eq!(error.field.as_deref(), Some("filters[0].n"));
+        assert_eq!(
+            error.message,
+            "Cannot filter by unknown column: items.price"
+        );
+
+        let error = validate(vec![filter("items.quantity", "ANY >", "many", None)])
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("filters[0].v"));
+
+        let error = validate(vec![filter("items.sku", "NONE MATCHES", "x", None)])
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidOperator);
+
+        let error = validate(vec![filter("items", "=", 1, None)]).err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidOperator);
+        assert_eq!(error.message, "Unknown operator for relation items: =");
+
+        let error = validate(vec![filter("items", "COUNT >=", "two", None)])
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(
+            error.message,
+            "COUNT expects a non-negative integer for items"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_search() -> eyre::Result<()> {
+        let config = setup_config().search_columns(&["name", "age"])?;
+        let request = FilterRequest {
+            filters: vec![filter("active", "=", true, None)],
+            q: Some("jo 42".to_string()),
+            ..Default::default()
+        };
+        let filters = config.validate(&request).unwrap().to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE (active = true AND (LOWER(name) LIKE LOWER('%jo%') AND (LOWER(name) LIKE LOWER('%42%') OR age = 42))) LIMIT 20 OFFSET 0"
+        );
+
+        let request = FilterRequest {
+            q: Some("smith".to_string()),
+            ..Default::default()
+        };
+        let filters = config.validate(&request).unwrap().to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE LOWER(name) LIKE LOWER('%smith%') LIMIT 20 OFFSET 0"
+        );
+
+        let error = error_for(request);
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("q"));
+
+        // Columns that cannot be searched are rejected with the config, not per request
+        assert!(setup_config().search_columns(&["password"]).is_err());
+        assert!(setup_config().search_columns(&["age"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_similarity_sort() -> eyre::Result<()> {
+        let config = setup_config().search_columns(&["name"])?;
+        let request = FilterRequest {
+            sort: Some("~name,-age".to_string()),
+            q: Some("o'brien".to_string()),
+            ..Default::default()
+        };
+        let filters = config.validate(&request).unwrap().to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE LOWER(name) LIKE LOWER('%o''brien%') ORDER BY similarity(name, 'o''brien') DESC, age DESC LIMIT 20 OFFSET 0"
+        );
+
+        let mut params = QueryParams::new();
+        assert_eq!(
+            filters.sql_with_params(&mut params)?,
+            " WHERE LOWER(name) LIKE LOWER($1) ORDER BY similarity(name, $2) DESC, age DESC LIMIT 20 OFFSET 0"
+        );
+        assert_eq!(params.values.len(), 2);
+
+        let cases = vec![
+            ("~name", None, FilterErrorCode::InvalidValue),
+            ("~age", Some("jo"), FilterErrorCode::InvalidValue),
+            ("~password", Some("jo"), FilterErrorCode::UnknownColumn),
+            ("age,~name", Some("jo"), FilterErrorCode::InvalidValue),
+            ("~name,-~name", Some("jo"), FilterErrorCode::InvalidValue),
+        ];
+        for (sort, q, code) in cases {
+            let error = config
+                .validate(&FilterRequest {
+                    sort: Some(sort.to_string()),
+                    q: q.map(str::to_string),
+                    ..Default::default()
+                })
+                .err()
+                .unwrap();
+            assert_eq!(error.code, code, "{}", sort);
+            assert_eq!(error.field.as_deref(), Some("sort"));
+        }
+
+        let error = setup_config()
+            .search_columns(&["name"])?
+            .max_sort_columns(1)
+            .validate(&request)
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::TooManySortColumns);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_defaults() -> eyre::Result<()> {
+        let validated = setup_config().validate(&FilterRequest::default()).unwrap();
+        assert_eq!(validated.pagination.current_page, 1);
+        assert_eq!(validated.pagination.per_page, 20);
+        assert!(validated.sorting.is_empty());
+        assert!(validated.filtering.is_none());
+        assert_eq!(validated.to_pg_filters(0)?.sql()?, " LIMIT 20 OFFSET 0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pagination_errors() {
+        let error = error_for(FilterRequest {
+            page: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidPage);
+        assert_eq!(error.field.as_deref(), Some("page"));
+
+        let error = error_for(FilterRequest {
+            per_page: Some(101),
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::InvalidPerPage);
+    }
+
+    #[test]
+    fn test_validate_sort_errors() {
+        let error = error_for(FilterRequest {
+            sort: Some("name,-password".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(error.code, FilterErrorCode::UnknownColumn);
+        assert_eq!(error.field.as_deref(), Some("sort"));
+
+        let config = setup_config().max_sort_columns(1);
+        let error = config
+            .validate(&FilterRequest {
+                sort: Some("name,age".to_string()),
+                ..Default::default()
+            })
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::TooManySortColumns);
+    }
+
+    #[test]
+    fn test_validate_filter_errors() {
+        let cases = vec![
+            (
+                filter("password", "=", "x", None),
+                FilterErrorCode::UnknownColumn,
+                "filters[0].n",
+            ),
+            (
+                filter("name", "NOT SIMILAR TO", "x", None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("name", "DATE_ONLY", "2024-01-01", None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("registration", "RELATIVE", "now() - interval '1 day'", None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("age", ">", "twenty", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("active", "=", "yes", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("registration", "DATE_RANGE", "2024-01-01", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("age", "=", 1.5, None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("age", ">", Value::Null, None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("registration", "DATE_RANGE", vec!["2024-01-01"], None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("registration", ">", "last tuesday", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("registration", "DATE_ONLY", "2024-01-01 10:00:00", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("registration", "DATE_RANGE", "2024-01-01,2024-02-30", None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("age", "EXTRACT(MONTH) =", 3, None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("registration", "EXTRACT(EPOCH) =", 0, None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("registration", "EXTRACT(MONTH) LIKE", "3", None),
+                FilterErrorCode::InvalidOperator,
+                "filters[0].f",
+            ),
+            (
+                filter("registration", "EXTRACT(MONTH) =", 13, None),
+                FilterErrorCode::InvalidValue,
+                "filters[0].v",
+            ),
+            (
+                filter("age", "=", "1", Some("or")),
+                FilterErrorCode::InvalidFilters,
+                "filters[0].c",
+            ),
+        ];
+
+        for (filter, code, field) in cases {
+            let error = error_for(FilterRequest {
+                filters: vec![filter],
+                ..Default::default()
+            });
+            assert_eq!(error.code, code, "{}", error);
+            assert_eq!(error.field.as_deref(), Some(field));
+        }
+    }
+
+    #[test]
+    fn test_validate_too_many_filters() {
+        let config = setup_config().max_filters(1);
+        let error = config
+            .validate(&FilterRequest {
+                filters: vec![filter("age", ">", "1", None), filter("age", "<", "9", None)],
+                ..Default::default()
+            })
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::TooManyFilters);
+    }
+
+    #[test]
+    fn test_allow_relative_dates() {
+        let config = setup_config().allow_relative_dates(true);
+        let request = FilterRequest {
+            filters: vec![filter("registration", "RELATIVE", "previous month", None)],
+            ..Default::default()
+        };
+        assert!(config.validate(&request).is_ok());
+
+        let request = FilterRequest {
+            filters: vec![filter(
+                "registration",
+                "RELATIVE",
+                "now() - interval '1 day'",
+                None,
+            )],
+            ..Default::default()
+        };
+        let error = config.validate(&request).err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("filters[0].v"));
+    }
+
+    #[test]
+    fn test_validate_time_zone() -> eyre::Result<()> {
+        let mut columns = HashMap::new();
+        columns.insert("logged_at", ColumnDef::TimestampTz("logged_at"));
+        let config = FilterRequestConfig::new(columns);
+
+        let request = FilterRequest {
+            filters: vec![filter("logged_at", "DATE_ONLY", "2024-12-29", None)],
+            time_zone: Some("America/Los_Angeles".to_string()),
+            ..Default::default()
+        };
+        let filters = config.validate(&request).unwrap().to_pg_filters(100)?;
+        assert_eq!(
+            filters.sql()?,
+            " WHERE logged_at >= ('2024-12-29')::timestamp AT TIME ZONE 'America/Los_Angeles' AND logged_at < (('2024-12-29')::timestamp + interval '1 day') AT TIME ZONE 'America/Los_Angeles' LIMIT 20 OFFSET 0"
+        );
+
+        let error = config
+            .validate(&FilterRequest {
+                time_zone: Some("PST8PDT'".to_string()),
+                ..Default::default()
+            })
+            .err()
+            .unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("time_zone"));
+
+        // Unknown zones are only rejected by PostgreSQL when the query runs
+        assert!(config
+            .validate(&FilterRequest {
+                time_zone: Some("Mars/Olympus".to_string()),
+                ..Default::default()
+            })
+            .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_params_into_request() {
+        let params = FilterQueryParams {
+            filters: Some(r#"[{"n":"age","f":">","v":"18"}]"#.to_string()),
+            q: Some("jo".to_string()),
+            ..Default::default()
+        };
+        let request = params.into_request().unwrap();
+        assert_eq!(request.filters.len(), 1);
+        assert_eq!(request.filters[0].n, "age");
+        assert_eq!(request.q.as_deref(), Some("jo"));
+
+        let params = FilterQueryParams {
+            filters: Some("[{".to_string()),
+            ..Default::default()
+        };
+        let error = params.into_request().err().unwrap();
+        assert_eq!(error.code, FilterErrorCode::InvalidFilters);
+        assert_eq!(error.field.as_deref(), Some("filters"));
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let error =
+            FilterRequestError::new(FilterErrorCode::UnknownColumn, "Unknown column").field("sort");
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"code":"unknown_column","message":"Unknown column","field":"sort"}"#
+        );
+        assert_eq!(error.http_status(), 400);
+        assert_eq!(
+            FilterRequestError::new(FilterErrorCode::MissingConfig, "").http_status(),
+            500
+        );
+
+        let error = FilterRequestError::internal();
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"code":"internal","message":"Internal server error"}"#
+        );
+        assert_eq!(error.http_status(), 500);
+    }
+}
@@ -0,0 +1,502 @@
+//! RSQL module
+//!
+//! This module parses RSQL/FIQL filters, as used by many Java services, into a
+//! `FilterExpression` validated against `column_defs`:
+//!
+//! ```text
+//! name==jo*;age=gt=21,status=in=(active,pending)
+//! ```
+//!
+//! - `;` is AND and `,` is OR, with AND binding tighter; parentheses group, nested at most 32
+//!   levels deep
+//! - `!` before a comparison or parenthesised group negates it, e.g. `!(a==1,b==2)`; this is
+//!   an extension to RSQL
+//! - selectors are `column_defs` keys, and values are parsed as the column type
+//! - values containing reserved characters (`"'();,=!~<>` or whitespace) are quoted with `"` or
+//!   `'`, with `\` escaping the next character
+//!
+//! | operator        | condition                              |
+//! |-----------------|----------------------------------------|
+//! | `==`            | `=`, or `LIKE` if the value contains `*` |
+//! | `!=`            | `!=`, or `NOT LIKE` if the value contains `*` |
+//! | `=gt=` or `>`   | `>`                                    |
+//! | `=ge=` or `>=`  | `>=`                                   |
+//! | `=lt=` or `<`   | `<`                                    |
+//! | `=le=` or `<=`  | `<=`                                   |
+//! | `=in=`          | `IN`                                   |
+//! | `=out=`         | `NOT IN`                               |
+//!
+//! `*` is a wildcard on text columns; `\*` in a quoted value matches a literal `*`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::FilterBuilder;
+//! use pg_filters::rsql;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//! columns.insert("status", ColumnDef::Text("status"));
+//!
+//! let expression = rsql::parse_filter("name==jo*;age=gt=21,status=in=(a,b)", &columns).unwrap();
+//! let sql = FilterBuilder::new().add_expression(expression).build().unwrap();
+//! assert_eq!(
+//!     sql,
+//!     " WHERE ((name LIKE 'jo%' AND age > 21) OR status IN ('a', 'b'))"
+//! );
+//! ```
+
+use crate::filtering::{
+    escape_like, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use crate::ColumnDef;
+use eyre::Result;
+use std::collections::HashMap;
+
+/// Parses an RSQL filter
+pub fn parse_filter(
+    rsql: &str,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<FilterExpression> {
+    let mut parser = Parser {
+        chars: rsql.chars().collect(),
+        position: 0,
+        depth: 0,
+        column_defs,
+    };
+
+    let expression = parser.or()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(expression),
+        Some(c) => Err(parser.unexpected(c)),
+    }
+}
+
+/// A value, and the `LIKE` pattern for it if it contains wildcards
+struct Value {
+    text: String,
+    pattern: Option<String>,
+}
+
+fn is_reserved(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '"' | '\'' | '(' | ')' | ';' | ',' | '=' | '!' | '~' | '<' | '>'
+        )
+}
+
+/// Deepest nesting of parentheses and `!` in a filter, so that a deeply nested filter cannot
+/// overflow the stack
+const MAX_DEPTH: usize = 32;
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    /// Parentheses and `!`s around the current position
+    depth: usize,
+    column_defs: &'a HashMap<&'static str, ColumnDef>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes `c`, after any whitespace, if it is next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(found) => Err(self.unexpected(found)),
+            None => Err(eyre::eyre!("Expected '{}' at end of RSQL filter", c)),
+        }
+    }
+
+    fn unexpected(&self, c: char) -> eyre::Report {
+        eyre::eyre!(
+            "Unexpected '{}' at position {} in RSQL filter",
+            c,
+            self.position
+        )
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// Parses a nested expression with `parse`, one level deeper
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<FilterExpression>,
+    ) -> Result<FilterExpression> {
+        if self.depth == MAX_DEPTH {
+            return Err(eyre::eyre!(
+                "RSQL filter is nested more than {} levels deep",
+                MAX_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let expression = parse(self)?;
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn or(&mut self) -> Result<FilterExpression> {
+        let mut operands = vec![self.and()?];
+        while self.eat(',') {
+            operands.push(self.and()?);
+        }
+        Ok(FilterExpression::combine(LogicalOperator::Or, operands))
+    }
+
+    fn and(&mut self) -> Result<FilterExpression> {
+        let mut operands = vec![self.constraint()?];
+        while self.eat(';') {
+            operands.push(self.constraint()?);
+        }
+        Ok(FilterExpression::combine(LogicalOperator::And, operands))
+    }
+
+    fn constraint(&mut self) -> Result<FilterExpression> {
+        if self.eat('!') {
+            let expression = self.nested(Self::constraint)?;
+            return Ok(FilterExpression::negate(expression));
+        }
+        if self.eat('(') {
+            return self.nested(|parser| {
+                let expression = parser.or()?;
+                parser.expect(')')?;
+                Ok(expression)
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<FilterExpression> {
+        self.skip_whitespace();
+        let selector = self.take_while(|c| !is_reserved(c));
+        if selector.is_empty() {
+            return match self.peek() {
+                Some(c) => Err(self.unexpected(c)),
+                None => Err(eyre::eyre!("Expected a selector at end of RSQL filter")),
+            };
+        }
+        let column_def = self
+            .column_defs
+            .get(selector.as_str())
+            .ok_or_else(|| eyre::eyre!("Cannot filter by unknown column: {}", selector))?;
+
+        self.skip_whitespace();
+        let comparator = self.comparator()?;
+
+        self.skip_whitespace();
+        let values = if self.eat('(') {
+            let mut values = vec![self.value()?];
+            while self.eat(',') {
+                values.push(self.value()?);
+            }
+            self.expect(')')?;
+            values
+        } else {
+            vec![self.value()?]
+        };
+
+        let condition = match comparator.as_str() {
+            "=in=" | "=out=" => {
+                let mut texts = Vec::new();
+                for value in values {
+                    column_def.to_filter_condition("=", &value.text)?;
+                    texts.push(value.text);
+                }
+                FilterCondition::InValues {
+                    column: column_def.get_column_name(),
+                    operator: if comparator == "=in=" {
+                        FilterOperator::In
+                    } else {
+                        FilterOperator::NotIn
+                    },
+                    values: texts,
+                }
+            }
+            _ => {
+                let operator = match comparator.as_str() {
+                    "==" => "=",
+                    "!=" => "!=",
+                    "=gt=" | ">" => ">",
+                    "=ge=" | ">=" => ">=",
+                    "=lt=" | "<" => "<",
+                    "=le=" | "<=" => "<=",
+                    _ => return Err(eyre::eyre!("Unknown comparison operator: {}", comparator)),
+                };
+                let value = match <[Value; 1]>::try_from(values) {
+                    Ok([value]) => value,
+                    Err(_) => {
+                        return Err(eyre::eyre!(
+                            "{} takes a single value, use =in= or =out= for lists",
+                            comparator
+                        ))
+                    }
+                };
+                self.condition(&selector, column_def, operator, value)?
+            }
+        };
+
+        Ok(FilterExpression::Condition(condition))
+    }
+
+    fn comparator(&mut self) -> Result<String> {
+        let rest = &self.chars[self.position..];
+        let comparator = match rest {
+            ['=', '=', ..] => "==".to_string(),
+            ['!', '=', ..] => "!=".to_string(),
+            ['<', '=', ..] => "<=".to_string(),
+            ['>', '=', ..] => ">=".to_string(),
+            ['<', ..] => "<".to_string(),
+            ['>', ..] => ">".to_string(),
+            ['=', ..] => {
+                let name = rest[1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .collect::<String>();
+                if name.is_empty() || rest.get(name.len() + 1) != Some(&'=') {
+                    return Err(self.unexpected('='));
+                }
+                format!("={}=", name.to_lowercase())
+            }
+            [c, ..] => return Err(self.unexpected(*c)),
+            [] => {
+                return Err(eyre::eyre!(
+                    "Expected a comparison operator at end of RSQL filter"
+                ))
+            }
+        };
+        self.position += comparator.chars().count();
+        Ok(comparator)
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        let mut text = String::new();
+        let mut pattern = String::new();
+        let mut wildcard = false;
+
+        let mut push = |c: char, is_wildcard: bool| {
+            if is_wildcard {
+                wildcard = true;
+                pattern.push('%');
+            } else {
+                pattern.push_str(&escape_like(&c.to_string()));
+            }
+            text.push(c);
+        };
+
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.position += 1;
+                loop {
+                    match self.peek() {
+                        Some('\\') => {
+                            self.position += 1;
+                            match self.peek() {
+                                Some(c) => push(c, false),
+                                None => break,
+                            }
+                        }
+                        Some(c) if c == quote => break,
+                        Some(c) => push(c, c == '*'),
+                        None => break,
+                    }
+                    self.position += 1;
+                }
+                if self.peek() != Some(quote) {
+                    return Err(eyre::eyre!("Unterminated string in RSQL filter"));
+                }
+                self.position += 1;
+            }
+            _ => {
+                let unquoted = self.take_while(|c| !is_reserved(c));
+                if unquoted.is_empty() {
+                    return match self.peek() {
+                        Some(c) => Err(self.unexpected(c)),
+                        None => Err(eyre::eyre!("Expected a value at end of RSQL filter")),
+                    };
+                }
+                for c in unquoted.chars() {
+                    push(c, c == '*');
+                }
+            }
+        }
+
+        Ok(Value {
+            text,
+            pattern: wildcard.then_some(pattern),
+        })
+    }
+
+    fn condition(
+        &self,
+        selector: &str,
+        column_def: &ColumnDef,
+        operator: &str,
+        value: Value,
+    ) -> Result<FilterCondition> {
+        let pattern = match value.pattern {
+            Some(pattern) => pattern,
+            None => return column_def.to_filter_condition(operator, &value.text),
+        };
+
+        if !matches!(
+            column_def,
+            ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_)
+        ) {
+            return Err(eyre::eyre!(
+                "Wildcards are only supported on text columns: {}",
+                selector
+            ));
+        }
+        match operator {
+            "=" => column_def.to_filter_condition("LIKE", &pattern),
+            "!=" => column_def.to_filter_condition("NOT LIKE", &pattern),
+            _ => column_def.to_filter_condition(operator, &value.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("status", ColumnDef::Text("status"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        columns
+    }
+
+    fn filter_sql(rsql: &str) -> Result<String> {
+        parse_filter(rsql, &setup_test_columns())?.to_sql(false)
+    }
+
+    #[test]
+    fn test_comparisons() -> Result<()> {
+        assert_eq!(filter_sql("age==21")?, "age = 21");
+        assert_eq!(filter_sql("age!=21")?, "age != 21");
+        assert_eq!(filter_sql("age=gt=21")?, "age > 21");
+        assert_eq!(filter_sql("age=GE=21")?, "age >= 21");
+        assert_eq!(filter_sql("age<21")?, "age < 21");
+        assert_eq!(filter_sql("age<=21")?, "age <= 21");
+        assert_eq!(filter_sql("active==true")?, "active = true");
+        assert_eq!(
+            filter_sql("created_at=ge='2024-01-01 00:00:00'")?,
+            "created_at >= '2024-01-01 00:00:00'"
+        );
+        assert_eq!(
+            filter_sql("status=out=(archived,\"on hold\")")?,
+            "status NOT IN ('archived', 'on hold')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcards() -> Result<()> {
+        assert_eq!(filter_sql("name==*smith")?, "name LIKE '%smith'");
+        assert_eq!(filter_sql("name!=jo*")?, "name NOT LIKE 'jo%'");
+        assert_eq!(filter_sql("name=='50%*'")?, "name LIKE '50\\%%'");
+        assert_eq!(filter_sql("name=='a\\*b'")?, "name = 'a*b'");
+        assert_eq!(filter_sql("name==\"O'Brien\"")?, "name = 'O''Brien'");
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_operators() -> Result<()> {
+        assert_eq!(
+            filter_sql("name==a;age=gt=1,status==b")?,
+            "((name = 'a' AND age > 1) OR status = 'b')"
+        );
+        assert_eq!(
+            filter_sql("name==a;(age=gt=1,status==b)")?,
+            "(name = 'a' AND (age > 1 OR status = 'b'))"
+        );
+        assert_eq!(
+            filter_sql(" name == a ; ( age =gt= 1 ) ")?,
+            "(name = 'a' AND age > 1)"
+        );
+        assert_eq!(
+            filter_sql("!(age=gt=1,status==b);!name==a")?,
+            "(NOT (age > 1 OR status = 'b') AND NOT (name = 'a'))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("password==x", "Cannot filter by unknown column: password"),
+            ("age=gt=old", "Invalid integer value: old"),
+            ("age=in=(1,x)", "Invalid integer value: x"),
+            (
+                "age==2*",
+                "Wildcards are only supported on text columns: age",
+            ),
+            ("age=like=1", "Unknown comparison operator: =like="),
+            (
+                "age==(1,2)",
+                "== takes a single value, use =in= or =out= for lists",
+            ),
+            ("age~1", "Unexpected '~' at position 3 in RSQL filter"),
+            ("age==1;", "Expected a selector at end of RSQL filter"),
+            ("(age==1", "Expected ')' at end of RSQL filter"),
+            ("name=='x", "Unterminated string in RSQL filter"),
+            ("age==1)", "Unexpected ')' at position 6 in RSQL filter"),
+        ];
+
+        for (rsql, message) in cases {
+            let error = parse_filter(rsql, &setup_test_columns()).err().unwrap();
+            assert_eq!(error.to_string(), message, "{}", rsql);
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let columns = setup_test_columns();
+        let nested = |depth: usize| format!("{}age==1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_filter(&nested(MAX_DEPTH), &columns).is_ok());
+
+        for rsql in [nested(MAX_DEPTH + 1), "!".repeat(100_000) + "age==1"] {
+            let error = parse_filter(&rsql, &columns).err().unwrap();
+            assert_eq!(
+                error.to_string(),
+                "RSQL filter is nested more than 32 levels deep"
+            );
+        }
+    }
+}
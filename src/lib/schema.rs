@@ -0,0 +1,836 @@
+//! Schema module
+//!
+//! This module describes the filter request format for a column map as JSON Schema, so API docs
+//! can list the filterable and sortable fields and clients can validate requests before sending
+//! them. The schemas use the JSON Schema keywords shared with OpenAPI 3.1.
+//!
+//! Each filter is described per column: `n` is one of the `column_defs` keys, `f` is one of the
+//! operators of `ColumnDef::json_operators` for that column, and `v` is a value in the format
+//! the column type and operator expect, e.g. an integer, a `YYYY-MM-DD` date or an array for
+//! `IN`. Numbers and booleans may also be sent as strings, lists as comma-separated strings,
+//! `BETWEEN` ranges as `{"start", "end", "bounds"}` objects and date ranges as
+//! `{"start", "end"}` objects. `v` may be left out for `IS NULL` and
+//! `IS NOT NULL`. Operators are listed in upper case.
+//!
+//! The date functions of date and timestamp columns, such as `EXTRACT(MONTH) IN` or
+//! `DATE_TRUNC(DAY) BETWEEN`, are described by a `pattern` for `f` rather than an `enum`, with
+//! one alternative per function and value format.
+//!
+//! Filters on the `relations` are described per related column as well, with `n` such as
+//! `items.sku` and the operators of the column optionally prefixed with `ANY`, `ALL` or `NONE`,
+//! and per relation for `EXISTS`, `NOT EXISTS` and `COUNT <operator>`; see the `relation` module.
+//!
+//! With the `utoipa` feature, `FilterSchema::to_openapi` returns the request schema as a utoipa
+//! component.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::schema::FilterSchema;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let schema = FilterSchema::new(columns).max_per_page(100);
+//! assert_eq!(
+//!     schema.sort()["pattern"],
+//!     "^(-?(age|name)|~(name))(,-?(age|name))*$"
+//! );
+//!
+//! let request = schema.request();
+//! assert_eq!(request["properties"]["per_page"]["maximum"], 100);
+//! assert_eq!(request["properties"]["filters"]["items"]["oneOf"].as_array().unwrap().len(), 7);
+//! ```
+
+use crate::relation::Relation;
+use crate::{ColumnDef, ValueFormat, DATE_FUNCTION_OPERATOR_GROUPS};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// The column map and limits a filter request schema is generated from
+#[derive(Debug, Clone)]
+pub struct FilterSchema {
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+    pub max_per_page: Option<i64>,
+    pub max_filters: Option<usize>,
+    pub max_sort_columns: Option<usize>,
+    /// Whether `RELATIVE` is listed for timestamp columns
+    pub allow_relative_dates: bool,
+    /// Related tables that filters may refer to, by name
+    pub relations: HashMap<&'static str, Relation>,
+}
+
+impl FilterSchema {
+    /// A schema without limits, which leaves out `RELATIVE` date filters
+    pub fn new(column_defs: HashMap<&'static str, ColumnDef>) -> Self {
+        Self {
+            column_defs,
+            max_per_page: None,
+            max_filters: None,
+            max_sort_columns: None,
+            allow_relative_dates: false,
+            relations: HashMap::new(),
+        }
+    }
+
+    pub fn max_per_page(mut self, value: i64) -> Self {
+        self.max_per_page = Some(value);
+        self
+    }
+
+    pub fn max_filters(mut self, value: usize) -> Self {
+        self.max_filters = Some(value);
+        self
+    }
+
+    pub fn max_sort_columns(mut self, value: usize) -> Self {
+        self.max_sort_columns = Some(value);
+        self
+    }
+
+    pub fn allow_relative_dates(mut self, value: bool) -> Self {
+        self.allow_relative_dates = value;
+        self
+    }
+
+    /// Describes filters on the rows of `relation` under `name`
+    pub fn relation(mut self, name: &'static str, relation: Relation) -> Self {
+        self.relations.insert(name, relation);
+        self
+    }
+
+    /// The schema of a single `JsonFilter`, with one alternative per column and value format
+    pub fn json_filter(&self) -> Value {
+        let mut alternatives = Vec::new();
+
+        for (name, column_def) in self.sorted_columns() {
+            for (operators, format, value) in self.operator_groups(column_def) {
+                alternatives.push(filter_schema(json!(name), operators, format, value));
+            }
+        }
+
+        let mut relations = self.relations.iter().collect::<Vec<_>>();
+        relations.sort_by_key(|(name, _)| **name);
+        for (name, relation) in relations {
+            alternatives.push(filter_schema(
+                json!(name),
+                json!({"type": "string", "enum": ["EXISTS", "NOT EXISTS"]}),
+                ValueFormat::Ignored,
+                format_schema(&json!({}), ValueFormat::Ignored),
+            ));
+            alternatives.push(filter_schema(
+                json!(name),
+                json!({
+                    "type": "string",
+                    "enum": ["COUNT =", "COUNT !=", "COUNT >", "COUNT >=", "COUNT <", "COUNT <="],
+                }),
+                ValueFormat::Single,
+                json!({
+                    "description": "a number of related rows",
+                    "oneOf": [
+                        {"type": "integer", "minimum": 0},
+                        {"type": "string", "pattern": "^[0-9]+$"},
+                    ],
+                }),
+            ));
+
+            let mut columns = relation.column_defs.iter().collect::<Vec<_>>();
+            columns.sort_by_key(|(column, _)| **column);
+            for (column, column_def) in columns {
+                let n = json!(format!("{}.{}", name, column));
+                for (operators, format, value) in self.operator_groups(column_def) {
+                    alternatives.push(filter_schema(
+                        n.clone(),
+                        quantified(operators),
+                        format,
+                        value,
+                    ));
+                }
+            }
+        }
+
+        json!({ "oneOf": alternatives })
+    }
+
+    /// The schema of the `sort` parameter, which only matches the `column_defs` keys
+    ///
+    /// The first entry may be a text column prefixed with `~`, ordering by similarity to `q`.
+    pub fn sort(&self) -> Value {
+        let columns = self.sorted_columns();
+        let names = columns
+            .iter()
+            .map(|(name, _)| escape_regex(name))
+            .collect::<Vec<_>>()
+            .join("|");
+        let text_names = columns
+            .iter()
+            .filter(|(_, column_def)| {
+                matches!(
+                    column_def,
+                    ColumnDef::Text(_)
+                        | ColumnDef::Varchar(_)
+                        | ColumnDef::Char(_)
+                        | ColumnDef::CiText(_)
+                )
+            })
+            .map(|(name, _)| escape_regex(name))
+            .collect::<Vec<_>>()
+            .join("|");
+        let repeat = match self.max_sort_columns {
+            Some(max) => format!("{{0,{}}}", max.saturating_sub(1)),
+            None => "*".to_string(),
+        };
+        let first = if text_names.is_empty() {
+            format!("-?({names})")
+        } else {
+            format!("(-?({names})|~({text_names}))")
+        };
+
+        json!({
+            "type": "string",
+            "description": "Comma-separated columns, each prefixed with - for descending order; the first may be a text column prefixed with ~ to order by similarity to q",
+            "pattern": format!("^{first}(,-?({names})){repeat}$"),
+        })
+    }
+
+    /// The schema of a `FilterRequest` body
+    pub fn request(&self) -> Value {
+        let mut per_page = json!({"type": "integer", "minimum": 1});
+        if let Some(max) = self.max_per_page {
+            per_page["maximum"] = json!(max);
+        }
+
+        let mut filters = json!({"type": "array", "items": self.json_filter()});
+        if let Some(max) = self.max_filters {
+            filters["maxItems"] = json!(max);
+        }
+
+        json!({
+            "type": "object",
+            "properties": {
+                "page": {"type": "integer", "minimum": 1},
+                "per_page": per_page,
+                "sort": self.sort(),
+                "filters": filters,
+                "time_zone": {
+                    "type": "string",
+                    "description": "IANA time zone of the days of date filters on timestamptz columns, e.g. Australia/Sydney",
+                },
+                "q": {
+                    "type": "string",
+                    "description": "Free-text search across the searchable columns",
+                },
+            },
+        })
+    }
+
+    /// The request schema as a standalone JSON Schema document
+    pub fn json_schema(&self) -> Value {
+        let mut schema = self.request();
+        schema["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+        schema
+    }
+
+    /// The request schema as a utoipa component
+    #[cfg(feature = "utoipa")]
+    pub fn to_openapi(&self) -> utoipa::openapi::RefOr<utoipa::openapi::Schema> {
+        openapi::to_schema(&self.request())
+    }
+
+    fn sorted_columns(&self) -> Vec<(&'static str, &ColumnDef)> {
+        let mut columns = self
+            .column_defs
+            .iter()
+            .map(|(name, column_def)| (*name, column_def))
+            .collect::<Vec<_>>();
+        columns.sort_by_key(|(name, _)| *name);
+        columns
+    }
+
+    /// The operators of a column, as the schema of `f`, with the format and schema of the value
+    /// they expect
+    fn operator_groups(&self, column_def: &ColumnDef) -> Vec<(Value, ValueFormat, Value)> {
+        let value = value_schema(column_def);
+
+        let mut groups = column_def
+            .json_operator_groups(self.allow_relative_dates)
+            .into_iter()
+            .map(|(operators, format)| {
+                (
+                    json!({"type": "string", "enum": operators}),
+                    format,
+                    format_schema(&value, format),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (function, arguments) in column_def.json_date_functions() {
+            // EXTRACT compares integers, DATE_TRUNC values of the column type
+            let value = match function {
+                "EXTRACT" => value_schema(&ColumnDef::Integer("")),
+                _ => value.clone(),
+            };
+            for (operators, format) in DATE_FUNCTION_OPERATOR_GROUPS {
+                let pattern = format!(
+                    "^{}\\(({})\\) ({})$",
+                    function,
+                    arguments.join("|"),
+                    operators.join("|"),
+                );
+                groups.push((
+                    json!({
+                        "type": "string",
+                        "description": format!("{}(<field>) <operator>", function),
+                        "pattern": pattern,
+                    }),
+                    format,
+                    format_schema(&value, format),
+                ));
+            }
+        }
+
+        groups
+    }
+}
+
+#[cfg(any(feature = "axum", feature = "actix"))]
+impl From<&crate::request::FilterRequestConfig> for FilterSchema {
+    fn from(config: &crate::request::FilterRequestConfig) -> Self {
+        let mut schema = FilterSchema::new(config.column_defs.clone())
+            .max_per_page(config.max_per_page)
+            .max_filters(config.max_filters)
+            .max_sort_columns(config.max_sort_columns)
+            .allow_relative_dates(config.allow_relative_dates);
+        schema.relations = config.relations.clone();
+        schema
+    }
+}
+
+/// The schema of a `JsonFilter` on the column or relation `n` with one of the `operators`
+fn filter_schema(n: Value, operators: Value, format: ValueFormat, value: Value) -> Value {
+    let required = match format {
+        ValueFormat::Ignored => json!(["n", "f"]),
+        _ => json!(["n", "f", "v"]),
+    };
+    json!({
+        "type": "object",
+        "properties": {
+            "n": {"type": "string", "enum": [n]},
+            "f": operators,
+            "v": value,
+            "c": {"type": "string", "enum": ["AND", "OR"]},
+        },
+        "required": required,
+    })
+}
+
+/// The schema of `f` for a related column, whose operators may be prefixed with a quantifier
+fn quantified(mut operators: Value) -> Value {
+    if let Some(list) = operators["enum"].as_array() {
+        let mut all = list.clone();
+        for quantifier in ["ANY", "ALL", "NONE"] {
+            all.extend(
+                list.iter()
+                    .filter_map(Value::as_str)
+                    .map(|operator| json!(format!("{} {}", quantifier, operator))),
+            );
+        }
+        operators["enum"] = json!(all);
+    }
+    if let Some(pattern) = operators["pattern"].as_str() {
+        let pattern = pattern.replacen('^', "^((ANY|ALL|NONE) )?", 1);
+        operators["pattern"] = json!(pattern);
+    }
+    operators
+}
+
+/// The schema of a value in the given format, where `value` is the schema of a single value
+fn format_schema(value: &Value, format: ValueFormat) -> Value {
+    match format {
+        ValueFormat::Single => value.clone(),
+        ValueFormat::List => {
+            let description = match value.get("description").and_then(Value::as_str) {
+                Some(element) => format!("Comma-separated list, each {}", element),
+                None => "Comma-separated list".to_string(),
+            };
+            json!({
+                "oneOf": [
+                    {"type": "array", "items": value, "minItems": 1},
+                    {"type": "string", "description": description},
+                ],
+            })
+        }
+        ValueFormat::Range | ValueFormat::Pair => {
+            let description = match value.get("description").and_then(Value::as_str) {
+                Some(element) => format!("Comma-separated start and end, each {}", element),
+                None => "Comma-separated start and end".to_string(),
+            };
+            let mut forms = vec![
+                json!({
+                    "type": "string",
+                    "description": description,
+                    "pattern": "^[^,]+,[^,]+$",
+                }),
+                json!({"type": "array", "items": value, "minItems": 2, "maxItems": 2}),
+            ];
+            if format == ValueFormat::Range {
+                forms.push(json!({
+                    "type": "object",
+                    "properties": {
+                        "start": value,
+                        "end": value,
+                        "bounds": {
+                            "type": "string",
+                            "description": "Whether the start and end are included, [] by default",
+                            "enum": ["[]", "[)", "(]", "()"],
+                        },
+                    },
+                    "required": ["start", "end"],
+                }));
+            }
+            json!({ "oneOf": forms })
+        }
+        ValueFormat::Ignored => json!({
+            "oneOf": [
+                {"type": "null"},
+                {"type": "string", "description": "Ignored, may be empty"},
+            ],
+        }),
+        ValueFormat::Date => json!({"type": "string", "format": "date"}),
+        ValueFormat::DateRange => json!({
+            "oneOf": [
+                {
+                    "type": "string",
+                    "description": "Start and end timestamps, e.g. 2024-01-01,2024-01-31",
+                    "pattern": "^[^,]+,[^,]+$",
+                },
+                {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "start": {"type": "string"},
+                        "end": {"type": "string"},
+                    },
+                    "required": ["start", "end"],
+                },
+            ],
+        }),
+        ValueFormat::RelativeDate => json!({
+            "type": "string",
+            "description": "Relative date, e.g. today, this month, last 7 days or older than 90 days",
+        }),
+    }
+}
+
+/// The schema of a single value for a column type, where numbers and booleans may be strings
+fn value_schema(column_def: &ColumnDef) -> Value {
+    let described = |description: &str| json!({"type": "string", "description": description});
+
+    match column_def {
+        ColumnDef::SmallInt(_) | ColumnDef::Integer(_) | ColumnDef::BigInt(_) => json!({
+            "description": "an integer",
+            "oneOf": [
+                {"type": "integer"},
+                {"type": "string", "pattern": "^-?[0-9]+$"},
+            ],
+        }),
+        ColumnDef::Real(_) | ColumnDef::DoublePrecision(_) | ColumnDef::Numeric(_) => json!({
+            "description": "a number",
+            "oneOf": [
+                {"type": "number"},
+                {"type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?([eE][-+]?[0-9]+)?$"},
+            ],
+        }),
+        ColumnDef::Boolean(_) => json!({
+            "oneOf": [
+                {"type": "boolean"},
+                {"type": "string", "enum": ["true", "false"]},
+            ],
+        }),
+        ColumnDef::Uuid(_) => json!({"type": "string", "format": "uuid"}),
+        ColumnDef::Date(_) => json!({"type": "string", "format": "date"}),
+        ColumnDef::Time(_) | ColumnDef::TimeTz(_) => described("a time, e.g. 14:30:00"),
+        ColumnDef::Timestamp(_) | ColumnDef::TimestampTz(_) => {
+            described("a timestamp, e.g. 2024-01-31 14:30:00")
+        }
+        ColumnDef::Interval(_) => described("an interval, e.g. 1 day"),
+        ColumnDef::Money(_) => described("an amount"),
+        ColumnDef::Inet(_) | ColumnDef::Cidr(_) => described("an IP address or network"),
+        ColumnDef::MacAddr(_) | ColumnDef::MacAddr8(_) => described("a MAC address"),
+        ColumnDef::Json(_) | ColumnDef::Jsonb(_) => described("a JSON document"),
+        ColumnDef::ByteA(_) => described("binary data"),
+        ColumnDef::Xml(_) => described("an XML document"),
+        ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_) => {
+            json!({"type": "string"})
+        }
+        ColumnDef::TextArray(_) => described("an array element"),
+    }
+}
+
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if !c.is_alphanumeric() && c != '_' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Conversion of the keywords used above into utoipa schemas
+#[cfg(feature = "utoipa")]
+mod openapi {
+    use super::*;
+    use serde_json::Map;
+    use utoipa::openapi::schema::{
+        ArrayBuilder, ObjectBuilder, OneOfBuilder, Schema, SchemaFormat, Type,
+    };
+    use utoipa::openapi::RefOr;
+
+    pub(super) fn to_schema(value: &Value) -> RefOr<Schema> {
+        if let Some(alternatives) = value.get("oneOf").and_then(Value::as_array) {
+            return alternatives
+                .iter()
+                .fold(
+                    OneOfBuilder::new()
+                        .description(value.get("description").and_then(Value::as_str)),
+                    |builder, alternative| builder.item(to_schema(alternative)),
+                )
+                .into();
+        }
+
+        let text = |key: &str| value.get(key).and_then(Value::as_str);
+
+        if text("type") == Some("array") {
+            let items = value.get("items").map(to_schema).unwrap_or_default();
+            return ArrayBuilder::new()
+                .items(items)
+                .min_items(
+                    value
+                        .get("minItems")
+                        .and_then(Value::as_u64)
+                        .map(|min| min as usize),
+                )
+                .max_items(
+                    value
+                        .get("maxItems")
+                        .and_then(Value::as_u64)
+                        .map(|max| max as usize),
+                )
+                .into();
+        }
+
+        let schema_type = match text("type") {
+            Some("string") => Type::String,
+            Some("integer") => Type::Integer,
+            Some("number") => Type::Number,
+            Some("boolean") => Type::Boolean,
+            Some("null") => Type::Null,
+            _ => Type::Object,
+        };
+        let mut builder = ObjectBuilder::new()
+            .schema_type(schema_type)
+            .description(text("description"))
+            .pattern(text("pattern"))
+            .format(text("format").map(|format| SchemaFormat::Custom(format.to_string())))
+            .minimum(value.get("minimum").and_then(Value::as_i64))
+            .maximum(value.get("maximum").and_then(Value::as_i64));
+
+        if let Some(values) = value.get("enum").and_then(Value::as_array) {
+            builder = builder.enum_values(Some(values.clone()));
+        }
+        for (name, property) in value
+            .get("properties")
+            .and_then(Value::as_object)
+            .unwrap_or(&Map::new())
+        {
+            builder = builder.property(name, to_schema(property));
+        }
+        for name in value
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            builder = builder.required(name);
+        }
+
+        builder.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        columns.insert("tags", ColumnDef::TextArray("tags"));
+        columns
+    }
+
+    fn alternatives(schema: &FilterSchema, column: &str) -> Vec<Value> {
+        schema.json_filter()["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|alternative| alternative["properties"]["n"]["enum"][0] == column)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_json_filter_per_column() {
+        let schema = FilterSchema::new(setup_test_columns());
+
+        let age = alternatives(&schema, "age");
+        assert_eq!(age.len(), 4);
+        assert_eq!(
+            age[0]["properties"]["f"]["enum"],
+            json!([
+                "=",
+                "!=",
+                "IS DISTINCT FROM",
+                "IS NOT DISTINCT FROM",
+                ">",
+                ">=",
+                "<",
+                "<="
+            ])
+        );
+        assert_eq!(
+            age[0]["properties"]["v"]["oneOf"][0],
+            json!({"type": "integer"})
+        );
+        assert_eq!(
+            age[0]["properties"]["v"]["oneOf"][1]["pattern"],
+            "^-?[0-9]+$"
+        );
+        assert_eq!(age[1]["properties"]["f"]["enum"], json!(["IN", "NOT IN"]));
+        assert_eq!(age[1]["properties"]["v"]["oneOf"][0]["type"], "array");
+        assert_eq!(
+            age[1]["properties"]["v"]["oneOf"][0]["items"]["oneOf"][0],
+            json!({"type": "integer"})
+        );
+        assert_eq!(
+            age[1]["properties"]["v"]["oneOf"][1]["description"],
+            "Comma-separated list, each an integer"
+        );
+        assert_eq!(
+            age[2]["properties"]["f"]["enum"],
+            json!([
+                "BETWEEN",
+                "NOT BETWEEN",
+                "BETWEEN SYMMETRIC",
+                "NOT BETWEEN SYMMETRIC"
+            ])
+        );
+        let range = &age[2]["properties"]["v"]["oneOf"];
+        assert_eq!(
+            range[0]["description"],
+            "Comma-separated start and end, each an integer"
+        );
+        assert_eq!(range[1]["maxItems"], 2);
+        assert_eq!(
+            range[2]["properties"]["start"]["oneOf"][0]["type"],
+            "integer"
+        );
+        assert_eq!(
+            range[2]["properties"]["bounds"]["enum"],
+            json!(["[]", "[)", "(]", "()"])
+        );
+        assert_eq!(
+            age[3]["properties"]["f"]["enum"],
+            json!(["IS NULL", "IS NOT NULL"])
+        );
+        assert_eq!(age[0]["required"], json!(["n", "f", "v"]));
+        assert_eq!(age[3]["required"], json!(["n", "f"]));
+        assert_eq!(age[3]["properties"]["v"]["oneOf"][0]["type"], "null");
+
+        let active = alternatives(&schema, "active");
+        assert_eq!(
+            active[0]["properties"]["v"]["oneOf"][0],
+            json!({"type": "boolean"})
+        );
+        assert_eq!(
+            active[0]["properties"]["v"]["oneOf"][1]["enum"],
+            json!(["true", "false"])
+        );
+
+        let created_at = alternatives(&schema, "created_at");
+        let date_range = created_at
+            .iter()
+            .find(|alternative| alternative["properties"]["f"]["enum"] == json!(["DATE_RANGE"]))
+            .unwrap();
+        let forms = &date_range["properties"]["v"]["oneOf"];
+        assert_eq!(forms[0]["type"], "string");
+        assert_eq!(forms[1]["maxItems"], 2);
+        assert_eq!(forms[2]["required"], json!(["start", "end"]));
+
+        let tags = alternatives(&schema, "tags");
+        assert_eq!(
+            tags[0]["properties"]["f"]["enum"],
+            json!(["CONTAINS", "OVERLAPS"])
+        );
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        let operators = |schema: &FilterSchema| {
+            alternatives(schema, "created_at")
+                .iter()
+                .filter_map(|alternative| alternative["properties"]["f"]["enum"].as_array())
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let schema = FilterSchema::new(setup_test_columns());
+        assert!(operators(&schema).contains(&json!("DATE_RANGE")));
+        assert!(!operators(&schema).contains(&json!("RELATIVE")));
+
+        let schema = schema.allow_relative_dates(true);
+        assert!(operators(&schema).contains(&json!("RELATIVE")));
+    }
+
+    #[test]
+    fn test_relations() {
+        let mut item_columns = HashMap::new();
+        item_columns.insert("sku", ColumnDef::Text("sku"));
+        item_columns.insert("shipped_at", ColumnDef::Timestamp("shipped_at"));
+        let schema = FilterSchema::new(setup_test_columns()).relation(
+            "items",
+            Relation::new("order_items", item_columns).key("order_id", "orders.id"),
+        );
+
+        let items = alternatives(&schema, "items");
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0]["properties"]["f"]["enum"],
+            json!(["EXISTS", "NOT EXISTS"])
+        );
+        assert_eq!(items[0]["required"], json!(["n", "f"]));
+        assert_eq!(items[1]["properties"]["f"]["enum"][3], "COUNT >=");
+        assert_eq!(
+            items[1]["properties"]["v"]["oneOf"][0],
+            json!({"type": "integer", "minimum": 0})
+        );
+
+        let sku = alternatives(&schema, "items.sku");
+        let operators = sku[0]["properties"]["f"]["enum"].as_array().unwrap();
+        assert!(operators.contains(&json!("=")));
+        assert!(operators.contains(&json!("ANY =")));
+        assert!(operators.contains(&json!("NONE LIKE")));
+        assert!(operators.contains(&json!("ALL !=")));
+
+        let shipped_at = alternatives(&schema, "items.shipped_at");
+        let function = shipped_at
+            .iter()
+            .find_map(|alternative| alternative["properties"]["f"]["pattern"].as_str())
+            .unwrap();
+        assert!(function.starts_with("^((ANY|ALL|NONE) )?EXTRACT\\("));
+    }
+
+    #[test]
+    fn test_date_functions() {
+        let mut columns = setup_test_columns();
+        columns.insert("birthday", ColumnDef::Date("birthday"));
+        let schema = FilterSchema::new(columns);
+
+        let functions = |column: &str| {
+            alternatives(&schema, column)
+                .into_iter()
+                .filter(|alternative| alternative["properties"]["f"]["pattern"].is_string())
+                .collect::<Vec<_>>()
+        };
+
+        let created_at = functions("created_at");
+        assert_eq!(created_at.len(), 6);
+        assert_eq!(
+            created_at[0]["properties"]["f"]["pattern"],
+            "^EXTRACT\\((YEAR|QUARTER|MONTH|WEEK|DAY|DOW|ISODOW|DOY|HOUR|MINUTE|SECOND)\\) (=|!=|IS DISTINCT FROM|IS NOT DISTINCT FROM|>|>=|<|<=)$"
+        );
+        assert_eq!(
+            created_at[0]["properties"]["v"]["oneOf"][0],
+            json!({"type": "integer"})
+        );
+        assert_eq!(
+            created_at[2]["properties"]["v"]["oneOf"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            created_at[4]["properties"]["f"]["pattern"],
+            "^DATE_TRUNC\\((MINUTE|HOUR|DAY|WEEK|MONTH|QUARTER|YEAR)\\) (IN|NOT IN)$"
+        );
+
+        // Dates have no time of day
+        let birthday = functions("birthday");
+        assert_eq!(
+            birthday[0]["properties"]["f"]["pattern"],
+            "^EXTRACT\\((YEAR|QUARTER|MONTH|WEEK|DAY|DOW|ISODOW|DOY)\\) (=|!=|IS DISTINCT FROM|IS NOT DISTINCT FROM|>|>=|<|<=)$"
+        );
+        assert_eq!(
+            birthday[3]["properties"]["v"],
+            json!({"type": "string", "format": "date"})
+        );
+        assert!(functions("age").is_empty());
+    }
+
+    #[test]
+    fn test_request() {
+        let schema = FilterSchema::new(setup_test_columns())
+            .max_per_page(50)
+            .max_filters(10)
+            .max_sort_columns(2);
+        let request = schema.json_schema();
+
+        assert_eq!(
+            request["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(request["properties"]["per_page"]["maximum"], 50);
+        assert_eq!(request["properties"]["filters"]["maxItems"], 10);
+        assert_eq!(request["properties"]["time_zone"]["type"], "string");
+        assert_eq!(request["properties"]["q"]["type"], "string");
+        assert_eq!(
+            request["properties"]["sort"]["pattern"],
+            "^(-?(active|age|created_at|name|tags)|~(name))(,-?(active|age|created_at|name|tags)){0,1}$"
+        );
+    }
+
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("user.name"), "user\\.name");
+        assert_eq!(escape_regex("created_at"), "created_at");
+    }
+
+    #[cfg(feature = "utoipa")]
+    #[test]
+    fn test_to_openapi() {
+        let schema = FilterSchema::new(setup_test_columns()).max_per_page(50);
+        let openapi = serde_json::to_value(schema.to_openapi()).unwrap();
+
+        assert_eq!(openapi["properties"]["per_page"]["maximum"], 50);
+        assert_eq!(
+            openapi["properties"]["filters"]["items"]["oneOf"],
+            schema.json_filter()["oneOf"]
+        );
+    }
+}
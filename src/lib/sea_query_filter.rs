@@ -0,0 +1,327 @@
+//! SeaQuery filter module
+//!
+//! This module converts a `FilterExpression` tree into a `sea_query::Condition` and a `Sorting`
+//! into a list of columns and orders, so the same filters can be used with SeaQuery or SeaORM.
+//!
+//! Groups become `Condition::all()` or `Condition::any()`. Each condition becomes a custom
+//! expression whose values are `sea_query::Value`s, so they are bound by whichever driver
+//! builds the final query. Columns are written as given, unquoted, in both the conditions and
+//! the sort order, so qualified columns such as `orders.id` work in either.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use pg_filters::sea_query_filter::{filter_expression, order_by};
+//! use pg_filters::sorting::{SortedColumn, Sorting};
+//! use sea_query::{Alias, PostgresQueryBuilder, Query};
+//! use std::collections::HashMap;
+//!
+//! let expression = FilterExpression::Condition(FilterCondition::integer(
+//!     "age",
+//!     FilterOperator::GreaterThan,
+//!     Some(18),
+//! ));
+//!
+//! let mut query = Query::select();
+//! query
+//!     .column(Alias::new("name"))
+//!     .from(Alias::new("person"))
+//!     .cond_where(filter_expression(&expression, false, &HashMap::new()).unwrap());
+//! for (column, order) in order_by(&Sorting::new(vec![SortedColumn::new("name", "asc")])) {
+//!     query.order_by_expr(column, order);
+//! }
+//!
+//! let (sql, values) = query.build(PostgresQueryBuilder);
+//! assert_eq!(
+//!     sql,
+//!     r#"SELECT "name" FROM "person" WHERE age > $1 ORDER BY name ASC"#
+//! );
+//! assert_eq!(values.0, vec![18.into()]);
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterExpression, LogicalOperator, SqlParam, SqlPart};
+use crate::sorting::{SortOrder, Sorting};
+use crate::ColumnDef;
+use eyre::Result;
+use sea_query::{Condition, Expr, Order, SimpleExpr, Value};
+use std::collections::HashMap;
+
+/// Converts `expression` into a `sea_query::Condition`
+///
+/// An empty group, or its negation, becomes an empty `Condition::all()`, which SeaQuery renders
+/// as `TRUE`. IN list values are cast to the type of their column in `column_defs`.
+pub fn filter_expression(
+    expression: &FilterExpression,
+    case_insensitive: bool,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<Condition> {
+    match expression {
+        FilterExpression::Condition(_) | FilterExpression::Not { .. } => {
+            let parts = expression.to_sql_parts(case_insensitive, column_defs)?;
+            if parts.is_empty() {
+                return Ok(Condition::all());
+            }
+            // Every part is an expression of its own, so SeaQuery never reads the rendered SQL
+            // for placeholders. The placeholders are separated by spaces, which end up around
+            // each value, so the SQL is trimmed to keep a single space between parts.
+            let parts = parts
+                .into_iter()
+                .filter_map(|part| match part {
+                    SqlPart::Sql(sql) => {
+                        let sql = sql.trim();
+                        if sql.is_empty() {
+                            None
+                        } else {
+                            Some(SimpleExpr::Custom(sql.to_string()))
+                        }
+                    }
+                    SqlPart::Param(param) => Some(SimpleExpr::Value(to_value(param))),
+                })
+                .collect::<Vec<_>>();
+            let template = (1..=parts.len())
+                .map(|index| format!("${}", index))
+                .collect::<Vec<_>>()
+                .join(" ");
+            Ok(Condition::all().add(Expr::cust_with_exprs(template, parts)))
+        }
+        FilterExpression::Group {
+            operator,
+            expressions,
+        } => {
+            let mut condition = match operator {
+                LogicalOperator::And => Condition::all(),
+                LogicalOperator::Or => Condition::any(),
+            };
+            for expression in expressions {
+                let child = filter_expression(expression, case_insensitive, column_defs)?;
+                if !child.is_empty() {
+                    condition = condition.add(child);
+                }
+            }
+            if condition.is_empty() {
+                return Ok(Condition::all());
+            }
+            Ok(condition)
+        }
+    }
+}
+
+/// Converts the filters of `filter_builder` into a `sea_query::Condition`
+pub fn filter_builder(
+    filter_builder: &FilterBuilder,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<Condition> {
+    match filter_builder.expression()? {
+        Some(expression) => {
+            filter_expression(&expression, filter_builder.case_insensitive, column_defs)
+        }
+        None => Ok(Condition::all()),
+    }
+}
+
+/// Converts `sorting` into `(column, order)` pairs, in the same order as `sorting.sql`
+///
+/// Columns are unquoted expressions, as in `filter_expression`. Use them with `order_by_expr`.
+pub fn order_by(sorting: &Sorting) -> Vec<(SimpleExpr, Order)> {
+    sorting
+        .columns
+        .iter()
+        .map(|column| {
+            let order = match column.order {
+                SortOrder::Asc => Order::Asc,
+                SortOrder::Desc => Order::Desc,
+            };
+            (Expr::cust(&column.column), order)
+        })
+        .collect()
+}
+
+/// Converts the similarity sort of `sorting` into an expression ordered before `order_by`
+///
+/// The query is a bound value. Use it with `order_by_expr`.
+pub fn order_by_similarity(sorting: &Sorting) -> Option<(SimpleExpr, Order)> {
+    sorting.similarity().map(|similarity| {
+        let expression = similarity.expression("$1");
+        (
+            Expr::cust_with_values(expression, [similarity.query.clone()]),
+            Order::Desc,
+        )
+    })
+}
+
+fn to_value(param: SqlParam) -> Value {
+    match param {
+        SqlParam::Text(value) => value.into(),
+        SqlParam::SmallInt(value) => value.into(),
+        SqlParam::Integer(value) => value.into(),
+        SqlParam::BigInt(value) => value.into(),
+        SqlParam::Real(value) => value.into(),
+        SqlParam::DoublePrecision(value) => value.into(),
+        SqlParam::Boolean(value) => value.into(),
+        SqlParam::TextArray(values) => values.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::{FilterCondition, FilterOperator};
+    use crate::sorting::{SimilaritySort, SortedColumn};
+    use sea_query::{Alias, PostgresQueryBuilder, Query};
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("id", ColumnDef::Uuid("id"));
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("services", ColumnDef::TextArray("services"));
+        columns
+    }
+
+    fn build(condition: Condition) -> (String, Vec<Value>) {
+        let (sql, values) = Query::select()
+            .column(Alias::new("id"))
+            .from(Alias::new("person"))
+            .cond_where(condition)
+            .build(PostgresQueryBuilder);
+        (sql, values.0)
+    }
+
+    #[test]
+    fn test_filter_expression_groups() -> Result<()> {
+        let expression = FilterExpression::and(vec![
+            FilterExpression::Condition(FilterCondition::in_values(
+                "id",
+                FilterOperator::In,
+                vec![
+                    "123e4567-e89b-12d3-a456-426614174000",
+                    "123e4567-e89b-12d3-a456-426614174001",
+                ],
+            )),
+            FilterExpression::or(vec![
+                FilterExpression::Condition(FilterCondition::text(
+                    "name",
+                    FilterOperator::Like,
+                    Some("%$1%"),
+                )),
+                FilterExpression::Condition(FilterCondition::ArrayOverlap {
+                    column: "services".to_string(),
+                    operator: FilterOperator::Overlaps,
+                    values: vec!["EPC".to_string(), "Search".to_string()],
+                }),
+            ]),
+        ]);
+
+        let (sql, values) = build(filter_expression(&expression, true, &setup_test_columns())?);
+        assert_eq!(
+            sql,
+            r#"SELECT "id" FROM "person" WHERE (id IN ( $1 ::uuid, $2 ::uuid)) AND ((LOWER(name) LIKE LOWER( $3 )) OR (services && $4 ::text[]))"#
+        );
+        assert_eq!(
+            values,
+            vec![
+                "123e4567-e89b-12d3-a456-426614174000".into(),
+                "123e4567-e89b-12d3-a456-426614174001".into(),
+                "%$1%".into(),
+                vec!["EPC".to_string(), "Search".to_string()].into(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_builder_empty() -> Result<()> {
+        let columns = setup_test_columns();
+        let (sql, _) = build(filter_builder(&FilterBuilder::new(), &columns)?);
+        assert_eq!(sql, r#"SELECT "id" FROM "person" WHERE TRUE"#);
+
+        let empty_group = FilterBuilder::new().group(LogicalOperator::Or, vec![]);
+        let (sql, _) = build(filter_builder(&empty_group, &columns)?);
+        assert_eq!(sql, r#"SELECT "id" FROM "person" WHERE TRUE"#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_expression_with_dollars() -> Result<()> {
+        let expression = FilterExpression::and(vec![
+            FilterExpression::Condition(FilterCondition::text(
+                "\"price$1\"",
+                FilterOperator::Equal,
+                Some("$2"),
+            )),
+            FilterExpression::Condition(FilterCondition::text(
+                "a$$b",
+                FilterOperator::IsNull,
+                None,
+            )),
+        ]);
+
+        let (sql, values) = build(filter_expression(&expression, false, &HashMap::new())?);
+        assert_eq!(
+            sql,
+            r#"SELECT "id" FROM "person" WHERE ("price$1" = $1) AND (a$$b IS NULL)"#
+        );
+        assert_eq!(values, vec!["$2".into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by() {
+        let sorting = Sorting::new(vec![
+            SortedColumn::new("name", "asc"),
+            SortedColumn::new("age", "desc"),
+        ]);
+        let mut query = Query::select();
+        query.column(Alias::new("id")).from(Alias::new("person"));
+        for (column, order) in order_by(&sorting) {
+            query.order_by_expr(column, order);
+        }
+        assert_eq!(
+            query.to_string(PostgresQueryBuilder),
+            r#"SELECT "id" FROM "person" ORDER BY age DESC, name ASC"#
+        );
+        assert!(order_by_similarity(&sorting).is_none());
+
+        let sorting = sorting.with_similarity(SimilaritySort::new("name", "jon"));
+        let mut query = Query::select();
+        query.column(Alias::new("id")).from(Alias::new("person"));
+        if let Some((expression, order)) = order_by_similarity(&sorting) {
+            query.order_by_expr(expression, order);
+        }
+        for (column, order) in order_by(&sorting) {
+            query.order_by_expr(column, order);
+        }
+        let (sql, values) = query.build(PostgresQueryBuilder);
+        assert_eq!(
+            sql,
+            r#"SELECT "id" FROM "person" ORDER BY similarity(name, $1) DESC, age DESC, name ASC"#
+        );
+        assert_eq!(values.0, vec!["jon".into()]);
+    }
+
+    #[test]
+    fn test_qualified_column() -> Result<()> {
+        let expression = FilterExpression::Condition(FilterCondition::integer(
+            "orders.id",
+            FilterOperator::GreaterThan,
+            Some(10),
+        ));
+        let mut query = Query::select();
+        query
+            .column(Alias::new("id"))
+            .from(Alias::new("orders"))
+            .cond_where(filter_expression(&expression, false, &HashMap::new())?);
+        for (column, order) in order_by(&Sorting::new(vec![SortedColumn::new("orders.id", "desc")]))
+        {
+            query.order_by_expr(column, order);
+        }
+        let (sql, values) = query.build(PostgresQueryBuilder);
+        assert_eq!(
+            sql,
+            r#"SELECT "id" FROM "orders" WHERE orders.id > $1 ORDER BY orders.id DESC"#
+        );
+        assert_eq!(values.0, vec![10.into()]);
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+//! Search module
+//!
+//! This module builds the filter of a single search box that matches across several columns,
+//! e.g. name, email and reference number. The query is split into terms at whitespace; every
+//! term must match at least one of the searchable columns:
+//!
+//! ```text
+//! jo smith  =>  (name LIKE '%jo%' OR email LIKE '%jo%') AND (name LIKE '%smith%' OR email LIKE '%smith%')
+//! ```
+//!
+//! Text columns match terms as substrings, with `%`, `_` and `\` matched literally. Numeric,
+//! date, time and UUID columns match terms equal to a value of their type, so `42` also finds
+//! the row with reference number 42; other terms skip them. A `YYYY-MM-DD` term matches the
+//! whole day on timestamp columns. Other column types are never
+//! searched. Text matching follows the case sensitivity of the `FilterBuilder`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::search::Search;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("reference", ColumnDef::Integer("reference"));
+//!
+//! let search = Search::new(&["name", "reference"], &columns).unwrap();
+//! let expression = search.expression("jo 42").unwrap().unwrap();
+//! assert_eq!(
+//!     expression.to_sql(false).unwrap(),
+//!     "(name LIKE '%jo%' AND (name LIKE '%42%' OR reference = 42))"
+//! );
+//! ```
+
+use crate::filtering::{escape_like, DateRangeType, FilterExpression, LogicalOperator};
+use crate::{dates, ColumnDef};
+use eyre::Result;
+use std::collections::HashMap;
+
+/// The most terms a search query may have
+const MAX_TERMS: usize = 10;
+
+/// The searchable columns of a list
+#[derive(Debug, Clone)]
+pub struct Search {
+    columns: Vec<ColumnDef>,
+}
+
+impl Search {
+    /// Searches `columns`, which must be keys of `column_defs` and include a text column
+    pub fn new(columns: &[&str], column_defs: &HashMap<&'static str, ColumnDef>) -> Result<Self> {
+        let columns = columns
+            .iter()
+            .map(|column| {
+                column_defs
+                    .get(column)
+                    .cloned()
+                    .ok_or_else(|| eyre::eyre!("Cannot search unknown column: {}", column))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !columns.iter().any(is_text) {
+            return Err(eyre::eyre!("Search requires at least one text column"));
+        }
+        Ok(Self { columns })
+    }
+
+    /// The filter matching every term of `query`, or `None` when it has no terms
+    pub fn expression(&self, query: &str) -> Result<Option<FilterExpression>> {
+        let terms = query.split_whitespace().collect::<Vec<_>>();
+        if terms.len() > MAX_TERMS {
+            return Err(eyre::eyre!(
+                "Search queries are limited to {} terms",
+                MAX_TERMS
+            ));
+        }
+
+        let mut matches = Vec::new();
+        for term in terms {
+            let mut columns = Vec::new();
+            for column_def in &self.columns {
+                if let Some(condition) = condition(column_def, term)? {
+                    columns.push(condition);
+                }
+            }
+            matches.push(FilterExpression::combine(LogicalOperator::Or, columns));
+        }
+
+        if matches.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(FilterExpression::combine(
+            LogicalOperator::And,
+            matches,
+        )))
+    }
+}
+
+/// The condition of `column_def` matching `term`, or `None` when `term` is not of its type
+fn condition(column_def: &ColumnDef, term: &str) -> Result<Option<FilterExpression>> {
+    let matches = match column_def {
+        ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_) => {
+            let pattern = format!("%{}%", escape_like(term));
+            return column_def
+                .to_filter_condition("LIKE", &pattern)
+                .map(|condition| Some(FilterExpression::Condition(condition)));
+        }
+        ColumnDef::SmallInt(_) | ColumnDef::Integer(_) | ColumnDef::BigInt(_) => {
+            is_number(term) && !term.contains('.')
+        }
+        ColumnDef::Real(_) | ColumnDef::DoublePrecision(_) | ColumnDef::Numeric(_) => {
+            is_number(term)
+        }
+        // A date matches the whole day of a timestamp
+        ColumnDef::Timestamp(_) | ColumnDef::TimestampTz(_) if dates::is_date(term) => {
+            return column_def
+                .to_date_condition(DateRangeType::DateOnly(term.to_string()))
+                .map(|condition| Some(FilterExpression::Condition(condition)));
+        }
+        ColumnDef::Date(_)
+        | ColumnDef::Time(_)
+        | ColumnDef::TimeTz(_)
+        | ColumnDef::Timestamp(_)
+        | ColumnDef::TimestampTz(_) => dates::is_valid_for(column_def, term),
+        ColumnDef::Uuid(_) => is_uuid(term),
+        _ => false,
+    };
+
+    if !matches {
+        return Ok(None);
+    }
+    // Integers out of the range of the column do not match it
+    Ok(column_def
+        .to_filter_condition("=", term)
+        .ok()
+        .map(FilterExpression::Condition))
+}
+
+fn is_text(column_def: &ColumnDef) -> bool {
+    matches!(
+        column_def,
+        ColumnDef::Text(_) | ColumnDef::Varchar(_) | ColumnDef::Char(_) | ColumnDef::CiText(_)
+    )
+}
+
+/// Whether `term` is a decimal number such as `42`, `-7` or `3.5`
+fn is_number(term: &str) -> bool {
+    let digits = term.strip_prefix('-').unwrap_or(term);
+    match digits.split_once('.') {
+        Some((whole, fraction)) => all_digits(whole) && all_digits(fraction),
+        None => all_digits(digits),
+    }
+}
+
+fn all_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether `term` is a UUID in the hyphenated form
+fn is_uuid(term: &str) -> bool {
+    term.len() == 36
+        && term.char_indices().all(|(index, c)| match index {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::QueryParams;
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("email", ColumnDef::Varchar("email"));
+        columns.insert("reference", ColumnDef::SmallInt("reference"));
+        columns.insert("amount", ColumnDef::Numeric("amount"));
+        columns.insert("created_on", ColumnDef::Date("created_on"));
+        columns.insert("updated_at", ColumnDef::Timestamp("updated_at"));
+        columns.insert("id", ColumnDef::Uuid("id"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns
+    }
+
+    fn search_sql(columns: &[&str], query: &str) -> Result<Option<String>> {
+        Search::new(columns, &setup_test_columns())?
+            .expression(query)?
+            .map(|expression| expression.to_sql(true))
+            .transpose()
+    }
+
+    #[test]
+    fn test_terms() -> Result<()> {
+        assert_eq!(
+            search_sql(&["name", "email"], "  jo   smith ")?.unwrap(),
+            "((LOWER(name) LIKE LOWER('%jo%') OR LOWER(email) LIKE LOWER('%jo%')) AND (LOWER(name) LIKE LOWER('%smith%') OR LOWER(email) LIKE LOWER('%smith%')))"
+        );
+        assert_eq!(
+            search_sql(&["name"], "50%_off")?.unwrap(),
+            "LOWER(name) LIKE LOWER('%50\\%\\_off%')"
+        );
+        assert_eq!(
+            search_sql(&["name"], "o'brien")?.unwrap(),
+            "LOWER(name) LIKE LOWER('%o''brien%')"
+        );
+        assert_eq!(search_sql(&["name", "email"], " \t ")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_columns() -> Result<()> {
+        let columns = ["name", "reference", "amount", "created_on", "id", "active"];
+        assert_eq!(
+            search_sql(&columns, "smith")?.unwrap(),
+            "LOWER(name) LIKE LOWER('%smith%')"
+        );
+        assert_eq!(
+            search_sql(&columns, "42")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%42%') OR reference = 42 OR amount = '42')"
+        );
+        assert_eq!(
+            search_sql(&columns, "-1.5")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%-1.5%') OR amount = '-1.5')"
+        );
+        // 70000 is out of the range of a smallint
+        assert_eq!(
+            search_sql(&columns, "70000")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%70000%') OR amount = '70000')"
+        );
+        assert_eq!(
+            search_sql(&columns, "2024-03-01")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%2024-03-01%') OR created_on = '2024-03-01')"
+        );
+        assert_eq!(
+            search_sql(&["name", "updated_at"], "2024-03-01")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%2024-03-01%') OR updated_at >= '2024-03-01 00:00:00' AND updated_at < ('2024-03-01')::date + interval '1 day')"
+        );
+        assert_eq!(
+            search_sql(&["name", "updated_at"], "2024-03-01T10:00:00")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%2024-03-01T10:00:00%') OR updated_at = '2024-03-01T10:00:00')"
+        );
+        assert_eq!(
+            search_sql(&columns, "123e4567-e89b-12d3-a456-426614174000")?.unwrap(),
+            "(LOWER(name) LIKE LOWER('%123e4567-e89b-12d3-a456-426614174000%') OR id = '123e4567-e89b-12d3-a456-426614174000')"
+        );
+        assert_eq!(
+            search_sql(&columns, "true")?.unwrap(),
+            "LOWER(name) LIKE LOWER('%true%')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bound_params() -> Result<()> {
+        let expression = Search::new(&["name", "reference"], &setup_test_columns())?
+            .expression("7")?
+            .unwrap();
+        let mut params = QueryParams::new();
+        assert_eq!(
+            expression.to_sql_with_params(true, &mut params)?,
+            "(LOWER(name) LIKE LOWER($1) OR reference = $2)"
+        );
+        assert_eq!(params.values.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let columns = setup_test_columns();
+        assert_eq!(
+            Search::new(&["password"], &columns)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Cannot search unknown column: password"
+        );
+        assert_eq!(
+            Search::new(&["reference", "id"], &columns)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Search requires at least one text column"
+        );
+
+        let search = Search::new(&["name"], &columns).unwrap();
+        let query = ["a"; MAX_TERMS + 1].join(" ");
+        assert_eq!(
+            search.expression(&query).err().unwrap().to_string(),
+            "Search queries are limited to 10 terms"
+        );
+    }
+}
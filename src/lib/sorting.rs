@@ -19,6 +19,14 @@
 //! ```
 //!
 
+#[cfg(feature = "sqlx")]
+use crate::filtering::{render_parts, SqlPart};
+use crate::filtering::{QueryParams, SqlValues};
+#[cfg(feature = "sqlx")]
+use eyre::Result;
+#[cfg(feature = "sqlx")]
+use std::collections::HashMap;
+
 /// SortOrder enum
 ///
 /// This enum is used to specify the sorting order of a column
@@ -79,6 +87,59 @@ impl SortedColumn {
     }
 }
 
+/// SimilaritySort struct
+///
+/// This struct orders rows by the trigram similarity of a text column to a query, best matches
+/// first. It needs the `pg_trgm` extension.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_filters::sorting::SimilaritySort;
+///
+/// let similarity = SimilaritySort::word("name", "jon");
+///
+/// assert_eq!(similarity.expression("$1"), "word_similarity($1, name)");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimilaritySort {
+    /// Column name
+    pub column: String,
+    /// Text the column is compared with
+    pub query: String,
+    /// Whether to use `word_similarity`, which compares the query with the closest part of the
+    /// column rather than all of it
+    pub word: bool,
+}
+
+impl SimilaritySort {
+    /// Orders by `similarity(column, query)`
+    pub fn new(column: &str, query: &str) -> SimilaritySort {
+        SimilaritySort {
+            column: column.to_string(),
+            query: query.to_string(),
+            word: false,
+        }
+    }
+
+    /// Orders by `word_similarity(query, column)`
+    pub fn word(column: &str, query: &str) -> SimilaritySort {
+        SimilaritySort {
+            word: true,
+            ..SimilaritySort::new(column, query)
+        }
+    }
+
+    /// The similarity function applied to the column, with `value` in place of the query
+    pub fn expression(&self, value: &str) -> String {
+        if self.word {
+            format!("word_similarity({}, {})", value, self.column)
+        } else {
+            format!("similarity({}, {})", self.column, value)
+        }
+    }
+}
+
 /// Sorting struct
 ///
 /// This struct is used to generate the ORDER BY clause in SQL queries
@@ -101,7 +162,9 @@ impl SortedColumn {
 pub struct Sorting {
     /// Vector of SortedColumn structs
     pub columns: Vec<SortedColumn>,
-    /// SQL ORDER BY clause
+    /// Similarity to a query, sorted before the columns; see `with_similarity`
+    similarity: Option<SimilaritySort>,
+    /// SQL ORDER BY clause, with the similarity query inlined as a literal
     pub sql: String,
 }
 
@@ -129,29 +192,146 @@ impl Sorting {
         columns.sort_by(|a, b| a.column.cmp(&b.column));
         columns.dedup_by(|a, b| a.column == b.column);
 
-        let mut sql = if !columns.is_empty() {
-            " ORDER BY ".to_string()
-        } else {
-            "".to_string()
+        let mut sorting = Sorting {
+            columns,
+            similarity: None,
+            sql: String::new(),
         };
-        let mut first = true;
-        for column in columns.iter() {
-            if first {
-                first = false;
-            } else {
-                sql.push_str(", ");
-            }
+        sorting.sql = sorting.order_by(&mut SqlValues::inline());
+        sorting
+    }
+
+    /// Sorts by similarity to a query before the columns, so the best matches come first
+    ///
+    /// The query is rendered as a quoted literal in `sql`, and bound by `sql_with_params` and
+    /// the query builders.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pg_filters::sorting::{SimilaritySort, SortedColumn, Sorting};
+    ///
+    /// let sorting = Sorting::new(vec![SortedColumn::new("name", "asc")])
+    ///     .with_similarity(SimilaritySort::new("name", "o'brien"));
+    ///
+    /// assert_eq!(
+    ///     sorting.sql,
+    ///     " ORDER BY similarity(name, 'o''brien') DESC, name ASC"
+    /// );
+    /// ```
+    pub fn with_similarity(mut self, similarity: SimilaritySort) -> Sorting {
+        self.similarity = Some(similarity);
+        self.sql = self.order_by(&mut SqlValues::inline());
+        self
+    }
+
+    /// The similarity sort, if any
+    pub fn similarity(&self) -> Option<&SimilaritySort> {
+        self.similarity.as_ref()
+    }
+
+    /// Like `sql`, but the similarity query is a numbered placeholder bound to `params`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pg_filters::filtering::QueryParams;
+    /// use pg_filters::sorting::{SimilaritySort, Sorting};
+    ///
+    /// let sorting = Sorting::new(vec![]).with_similarity(SimilaritySort::new("name", "jon"));
+    /// let mut params = QueryParams::new();
+    ///
+    /// assert_eq!(
+    ///     sorting.sql_with_params(&mut params),
+    ///     " ORDER BY similarity(name, $1) DESC"
+    /// );
+    /// assert_eq!(params.values.len(), 1);
+    /// ```
+    pub fn sql_with_params(&self, params: &mut QueryParams) -> String {
+        self.order_by(&mut SqlValues::bound(params))
+    }
+
+    /// The ORDER BY clause split at its bound similarity query
+    #[cfg(feature = "sqlx")]
+    pub(crate) fn sql_parts(&self) -> Result<Vec<SqlPart>> {
+        render_parts(&HashMap::new(), |values| Ok(self.order_by(values)))
+    }
+
+    fn order_by(&self, values: &mut SqlValues) -> String {
+        let mut terms = Vec::new();
+        if let Some(similarity) = &self.similarity {
+            let query = values.text(&similarity.query, None);
+            terms.push(format!("{} DESC", similarity.expression(&query)));
+        }
+        for column in self.columns.iter() {
             match column.order {
-                SortOrder::Asc => {
-                    sql.push_str(&column.column);
-                    sql.push_str(" ASC");
-                }
-                SortOrder::Desc => {
-                    sql.push_str(&column.column);
-                    sql.push_str(" DESC");
-                }
+                SortOrder::Asc => terms.push(format!("{} ASC", column.column)),
+                SortOrder::Desc => terms.push(format!("{} DESC", column.column)),
             }
         }
-        Sorting { columns, sql }
+
+        if terms.is_empty() {
+            return "".to_string();
+        }
+        format!(" ORDER BY {}", terms.join(", "))
     }
 }
+
+/// Parses a comma-separated sort parameter such as `-created_at,name`
+///
+/// A `-` prefix sorts the column in descending order. Columns are not checked against any
+/// column definitions.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_filters::sorting::{parse_sort, SortOrder};
+///
+/// let columns = parse_sort("-created_at, name");
+///
+/// assert_eq!(columns[0].column, "created_at");
+/// assert_eq!(columns[0].order, SortOrder::Desc);
+/// assert_eq!(columns[1].column, "name");
+/// assert_eq!(columns[1].order, SortOrder::Asc);
+/// ```
+pub fn parse_sort(sort: &str) -> Vec<SortedColumn> {
+    sort.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.strip_prefix('-') {
+            Some(column) => SortedColumn {
+                column: column.to_string(),
+                order: SortOrder::Desc,
+            },
+            None => SortedColumn {
+                column: entry.to_string(),
+                order: SortOrder::Asc,
+            },
+        })
+        .collect()
+}
+
+/// Formats sorted columns as a sort parameter, the reverse of `parse_sort`
+///
+/// # Example
+///
+/// ```rust
+/// use pg_filters::sorting::{format_sort, SortedColumn};
+///
+/// let sort = format_sort(&[
+///     SortedColumn::new("created_at", "desc"),
+///     SortedColumn::new("name", "asc"),
+/// ]);
+///
+/// assert_eq!(sort, "-created_at,name");
+/// ```
+pub fn format_sort(columns: &[SortedColumn]) -> String {
+    columns
+        .iter()
+        .map(|column| match column.order {
+            SortOrder::Asc => column.column.clone(),
+            SortOrder::Desc => format!("-{}", column.column),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
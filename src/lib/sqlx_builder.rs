@@ -0,0 +1,348 @@
+//! sqlx builder module
+//!
+//! This module pushes filters, sorting and pagination into an `sqlx::QueryBuilder<Postgres>`,
+//! binding every filter value with `push_bind` instead of rendering it inline.
+//!
+//! Values are bound with their Rust type: numbers and booleans as themselves, array values as
+//! `Vec<String>` and UUID values as `uuid::Uuid`. Nothing is checked against a database at
+//! compile time, so this works with sqlx's offline mode.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use pg_filters::sqlx_builder::push_filters;
+//! use pg_filters::{ColumnDef, FilteringOptions, PgFilters};
+//! use sqlx::{Postgres, QueryBuilder};
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let filters = PgFilters::new(
+//!     None,
+//!     vec![],
+//!     Some(FilteringOptions::new(
+//!         vec![FilterExpression::Condition(FilterCondition::integer(
+//!             "age",
+//!             FilterOperator::GreaterThan,
+//!             Some(18),
+//!         ))],
+//!         columns.clone(),
+//!     )),
+//!     columns,
+//! )
+//! .unwrap();
+//!
+//! let mut builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM person");
+//! push_filters(&mut builder, &filters).unwrap();
+//! assert_eq!(builder.sql(), "SELECT * FROM person WHERE age > $1");
+//! ```
+
+use crate::filtering::{FilterBuilder, SqlParam, SqlPart};
+use crate::pagination::{Page, Paginate};
+use crate::{ColumnDef, PgFilters};
+use eyre::Result;
+use sqlx::postgres::PgRow;
+use sqlx::{Connection, FromRow, PgConnection, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Pushes the WHERE clause for `filters` onto `builder`, binding every value
+///
+/// IN list values are cast to the type of their column in `column_defs`.
+pub fn push_filter_builder(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    filters: &FilterBuilder,
+    column_defs: &HashMap<&'static str, ColumnDef>,
+) -> Result<()> {
+    push_parts(builder, filters.build_parts(column_defs)?);
+    Ok(())
+}
+
+/// Pushes the WHERE, ORDER BY and LIMIT/OFFSET clauses for `filters` onto `builder`
+pub fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, filters: &PgFilters) -> Result<()> {
+    if let Some(filter_builder) = &filters.filters {
+        push_filter_builder(builder, filter_builder, &filters.column_defs)?;
+    }
+
+    if let Some(sorting) = &filters.sorting {
+        push_parts(builder, sorting.sql_parts()?);
+    }
+
+    if let Some(pagination) = &filters.pagination {
+        builder.push(" ");
+        builder.push(&pagination.sql);
+    }
+
+    Ok(())
+}
+
+/// Runs `base_query` with the filters, sorting and pagination from `filters` applied
+///
+/// `base_query` must not contain a WHERE, ORDER BY or LIMIT clause of its own, e.g.
+/// `SELECT * FROM person`. The count and the page query run in one read-only
+/// `REPEATABLE READ` transaction.
+///
+/// The `total_records` of the pagination options used to build `filters` is ignored and
+/// replaced with the filtered count, so the returned `Pagination` is fully populated and the
+/// current page is clamped to the last page. Pages have at most `per_page_limit` rows; without
+/// pagination in `filters`, the first `per_page_limit` rows are returned.
+pub async fn fetch_page<T>(
+    connection: &mut PgConnection,
+    base_query: &str,
+    filters: &PgFilters,
+    per_page_limit: i64,
+) -> Result<Page<T>>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let mut transaction = connection.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *transaction)
+        .await?;
+
+    let mut count: QueryBuilder<Postgres> = QueryBuilder::new("SELECT COUNT(*) FROM (");
+    count.push(base_query);
+    if let Some(filter_builder) = &filters.filters {
+        push_filter_builder(&mut count, filter_builder, &filters.column_defs)?;
+    }
+    count.push(") AS filtered");
+    let total_records: i64 = count
+        .build_query_scalar()
+        .fetch_one(&mut *transaction)
+        .await?;
+
+    let mut query: QueryBuilder<Postgres> = QueryBuilder::new(base_query);
+    if let Some(filter_builder) = &filters.filters {
+        push_filter_builder(&mut query, filter_builder, &filters.column_defs)?;
+    }
+    if let Some(sorting) = &filters.sorting {
+        push_parts(&mut query, sorting.sql_parts()?);
+    }
+
+    let paginate = paginate(filters, per_page_limit, total_records);
+    query.push(" ");
+    query.push(&paginate.sql);
+
+    let rows = query
+        .build_query_as::<T>()
+        .fetch_all(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(Page {
+        rows,
+        pagination: paginate.pagination,
+    })
+}
+
+/// The requested page, or the first page of `per_page_limit` rows when `filters` has no pagination
+fn paginate(filters: &PgFilters, per_page_limit: i64, total_records: i64) -> Paginate {
+    let (current_page, per_page) = match &filters.pagination {
+        Some(requested) => (
+            requested.pagination.current_page,
+            requested.pagination.per_page,
+        ),
+        None => (1, per_page_limit),
+    };
+    Paginate::new(current_page, per_page, per_page_limit, total_records)
+}
+
+/// Pushes each piece of SQL, and a `push_bind` for each value between them
+fn push_parts(builder: &mut QueryBuilder<'_, Postgres>, parts: Vec<SqlPart>) {
+    let mut parts = parts.into_iter().peekable();
+    while let Some(part) = parts.next() {
+        match part {
+            SqlPart::Sql(sql) => {
+                builder.push(sql);
+            }
+            SqlPart::Param(value) => {
+                let is_uuid =
+                    matches!(parts.peek(), Some(SqlPart::Sql(next)) if next.starts_with("::uuid"));
+                push_bind(builder, value, is_uuid);
+            }
+        }
+    }
+}
+
+fn push_bind(builder: &mut QueryBuilder<'_, Postgres>, value: SqlParam, is_uuid: bool) {
+    match value {
+        SqlParam::Text(value) if is_uuid => match Uuid::parse_str(&value) {
+            Ok(uuid) => builder.push_bind(uuid),
+            Err(_) => builder.push_bind(value),
+        },
+        SqlParam::Text(value) => builder.push_bind(value),
+        SqlParam::SmallInt(value) => builder.push_bind(value),
+        SqlParam::Integer(value) => builder.push_bind(value),
+        SqlParam::BigInt(value) => builder.push_bind(value),
+        SqlParam::Real(value) => builder.push_bind(value),
+        SqlParam::DoublePrecision(value) => builder.push_bind(value),
+        SqlParam::Boolean(value) => builder.push_bind(value),
+        SqlParam::TextArray(values) => builder.push_bind(values),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::{FilterCondition, FilterExpression, FilterOperator, LogicalOperator};
+    use crate::sorting::{SimilaritySort, SortedColumn};
+    use crate::{FilteringOptions, PaginationOptions};
+
+    fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+        let mut columns = HashMap::new();
+        columns.insert("id", ColumnDef::Uuid("id"));
+        columns.insert("name", ColumnDef::Text("name"));
+        columns.insert("age", ColumnDef::Integer("age"));
+        columns.insert("services", ColumnDef::TextArray("services"));
+        columns
+    }
+
+    #[test]
+    fn test_push_filters() -> Result<()> {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(
+            Some(PaginationOptions::new(2, 10, 10, 100)),
+            vec![SortedColumn::new("name", "asc")],
+            Some(FilteringOptions::new(
+                vec![
+                    FilterExpression::Condition(FilterCondition::in_values(
+                        "id",
+                        FilterOperator::In,
+                        vec![
+                            "123e4567-e89b-12d3-a456-426614174000",
+                            "123e4567-e89b-12d3-a456-426614174001",
+                        ],
+                    )),
+                    FilterExpression::Group {
+                        operator: LogicalOperator::Or,
+                        expressions: vec![
+                            FilterExpression::Condition(FilterCondition::text(
+                                "name",
+                                FilterOperator::Like,
+                                Some("%$1%"),
+                            )),
+                            FilterExpression::Condition(FilterCondition::ArrayOverlap {
+                                column: "services".to_string(),
+                                operator: FilterOperator::Overlaps,
+                                values: vec!["EPC".to_string(), "Search".to_string()],
+                            }),
+                        ],
+                    },
+                    FilterExpression::Condition(FilterCondition::integer(
+                        "age",
+                        FilterOperator::GreaterThan,
+                        Some(18),
+                    )),
+                ],
+                columns.clone(),
+            )),
+            columns,
+        )?;
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM person");
+        push_filters(&mut builder, &filters)?;
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM person WHERE (id IN ($1::uuid, $2::uuid) AND (LOWER(name) LIKE LOWER($3) OR services && $4::text[]) AND age > $5) ORDER BY name ASC LIMIT 10 OFFSET 10"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_filters_binds_similarity_sort() -> Result<()> {
+        let columns = setup_test_columns();
+        let mut filters = PgFilters::new(
+            None,
+            vec![SortedColumn::new("age", "desc")],
+            Some(FilteringOptions::new(
+                vec![FilterExpression::Condition(FilterCondition::integer(
+                    "age",
+                    FilterOperator::GreaterThan,
+                    Some(18),
+                ))],
+                columns.clone(),
+            )),
+            columns,
+        )?;
+        filters.sorting = filters
+            .sorting
+            .map(|sorting| sorting.with_similarity(SimilaritySort::new("name", "o'brien")));
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM person");
+        push_filters(&mut builder, &filters)?;
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM person WHERE age > $1 ORDER BY similarity(name, $2) DESC, age DESC"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_filter_builder_after_existing_binds() -> Result<()> {
+        let columns = setup_test_columns();
+        let filter_builder = FilterBuilder::new().add_condition(FilterCondition::uuid(
+            "id",
+            FilterOperator::Equal,
+            Some("123e4567-e89b-12d3-a456-426614174000"),
+        ));
+
+        let mut builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT * FROM (SELECT * FROM person WHERE active = ");
+        builder.push_bind(true);
+        builder.push(") AS p");
+        push_filter_builder(&mut builder, &filter_builder, &columns)?;
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM (SELECT * FROM person WHERE active = $1) AS p WHERE id = $2::uuid"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_filter_builder_with_dollar_in_column() -> Result<()> {
+        let filter_builder = FilterBuilder::new()
+            .add_condition(FilterCondition::text(
+                "\"price$1\"",
+                FilterOperator::Equal,
+                Some("$2"),
+            ))
+            .add_condition(FilterCondition::integer(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(18),
+            ));
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM person");
+        push_filter_builder(&mut builder, &filter_builder, &HashMap::new())?;
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM person WHERE (\"price$1\" = $1 AND age > $2)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_paginate_without_pagination() -> Result<()> {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(None, vec![], None, columns.clone())?;
+        let page = paginate(&filters, 3, 8);
+        assert_eq!(page.sql, "LIMIT 3 OFFSET 0");
+        assert_eq!(page.pagination.current_page, 1);
+        assert_eq!(page.pagination.per_page, 3);
+        assert_eq!(page.pagination.total_pages, 3);
+
+        // A requested page is still capped at `per_page_limit`
+        let filters = PgFilters::new(
+            Some(PaginationOptions::new(2, 50, 50, 0)),
+            vec![],
+            None,
+            columns,
+        )?;
+        let page = paginate(&filters, 3, 8);
+        assert_eq!(page.sql, "LIMIT 3 OFFSET 3");
+        assert_eq!(page.pagination.current_page, 2);
+        Ok(())
+    }
+}
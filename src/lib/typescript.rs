@@ -0,0 +1,391 @@
+//! TypeScript module
+//!
+//! This module generates TypeScript definitions of the filter request format for a column map,
+//! so frontends get compile-time checking of the `JsonFilter`s they build. Each filter type is a
+//! union with one member per column and value format: `n` is one of the `column_defs` keys, `f`
+//! one of the operators of `ColumnDef::json_operators` for that column, and `v` a value typed
+//! as narrowly as TypeScript allows, e.g. `` number | `${number}` `` for numeric columns or an
+//! array for `IN`. The date functions of date and timestamp columns are template literal types
+//! such as `` `EXTRACT(${"YEAR" | "MONTH"}) ${"=" | "!="}` ``. Filters on the `relations` have
+//! members for `EXISTS`, `NOT EXISTS` and `COUNT` on the relation name, and for each related
+//! column under names such as `items.sku`, with the operators optionally quantified by `ANY`,
+//! `ALL` or `NONE`.
+//!
+//! For a name of `People`, the definitions are:
+//!
+//! - `PeopleField`: the filterable and sortable columns
+//! - `PeopleConnector`: `"AND" | "OR"`
+//! - `PeopleFilter`: a `JsonFilter`
+//! - `PeopleSort`: a sort column, optionally prefixed with `-`, or a text column prefixed with
+//!   `~` to order by similarity to `q`
+//! - `PeopleFilterRequest`: a `FilterRequest` body
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_filters::typescript::TypeScriptTypes;
+//! use pg_filters::ColumnDef;
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::Text("name"));
+//! columns.insert("age", ColumnDef::Integer("age"));
+//!
+//! let definitions = TypeScriptTypes::new("People", columns).to_typescript();
+//! assert!(definitions.contains(r#"export type PeopleField = "age" | "name";"#));
+//! assert!(definitions.contains(
+//!     r#"  | { n: "age"; f: "=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM" | ">" | ">=" | "<" | "<="; v: number | `${number}`; c?: PeopleConnector }"#
+//! ));
+//! assert!(definitions.contains(
+//!     r#"export type PeopleSort = PeopleField | `-${PeopleField}` | `~${"name"}`;"#
+//! ));
+//! ```
+
+use crate::relation::Relation;
+use crate::{ColumnDef, ValueFormat, DATE_FUNCTION_OPERATOR_GROUPS};
+use eyre::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The column map TypeScript definitions are generated from
+#[derive(Debug, Clone)]
+pub struct TypeScriptTypes {
+    /// Prefix of the generated type names
+    pub name: String,
+    pub column_defs: HashMap<&'static str, ColumnDef>,
+    /// Whether `RELATIVE` is allowed for timestamp columns
+    pub allow_relative_dates: bool,
+    /// Related tables that filters may refer to, by name
+    pub relations: HashMap<&'static str, Relation>,
+}
+
+impl TypeScriptTypes {
+    pub fn new(name: impl Into<String>, column_defs: HashMap<&'static str, ColumnDef>) -> Self {
+        Self {
+            name: name.into(),
+            column_defs,
+            allow_relative_dates: false,
+            relations: HashMap::new(),
+        }
+    }
+
+    pub fn allow_relative_dates(mut self, value: bool) -> Self {
+        self.allow_relative_dates = value;
+        self
+    }
+
+    /// Describes filters on the rows of `relation` under `name`
+    pub fn relation(mut self, name: &'static str, relation: Relation) -> Self {
+        self.relations.insert(name, relation);
+        self
+    }
+
+    pub fn to_typescript(&self) -> String {
+        let name = &self.name;
+        let mut columns = self.column_defs.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(column, _)| **column);
+
+        let fields = columns
+            .iter()
+            .map(|(column, _)| format!("{:?}", column))
+            .collect::<Vec<_>>();
+
+        let mut filters = Vec::new();
+        let mut push = |n: &str, operators: String, format: ValueFormat, value_type: String| {
+            let value = match format {
+                ValueFormat::Ignored => "v?",
+                _ => "v",
+            };
+            filters.push(format!(
+                "  | {{ n: {:?}; f: {}; {}: {}; c?: {}Connector }}",
+                n, operators, value, value_type, name
+            ));
+        };
+        for (column, column_def) in &columns {
+            for (operators, format, value_type) in self.operator_groups(column_def) {
+                push(column, operators, format, value_type);
+            }
+        }
+
+        let mut relations = self.relations.iter().collect::<Vec<_>>();
+        relations.sort_by_key(|(relation, _)| **relation);
+        for (relation_name, relation) in relations {
+            push(
+                relation_name,
+                quoted(&["EXISTS", "NOT EXISTS"]),
+                ValueFormat::Ignored,
+                value_type(&ColumnDef::Text(""), ValueFormat::Ignored),
+            );
+            push(
+                relation_name,
+                format!(
+                    "`COUNT ${{{}}}`",
+                    quoted(&["=", "!=", ">", ">=", "<", "<="])
+                ),
+                ValueFormat::Single,
+                value_type(&ColumnDef::Integer(""), ValueFormat::Single),
+            );
+
+            let mut related_columns = relation.column_defs.iter().collect::<Vec<_>>();
+            related_columns.sort_by_key(|(column, _)| **column);
+            for (column, column_def) in related_columns {
+                for (operators, format, value_type) in self.operator_groups(column_def) {
+                    push(
+                        &format!("{}.{}", relation_name, column),
+                        format!(
+                            "{} | `${{\"ANY\" | \"ALL\" | \"NONE\"}} ${{{}}}`",
+                            operators, operators
+                        ),
+                        format,
+                        value_type,
+                    );
+                }
+            }
+        }
+
+        let mut output = String::from("// Generated by pg_filters. Do not edit.\n\n");
+        output.push_str(&format!(
+            "export type {}Field = {};\n\n",
+            name,
+            union(&fields)
+        ));
+        output.push_str(&format!(
+            "export type {}Connector = \"AND\" | \"OR\";\n\n",
+            name
+        ));
+        if filters.is_empty() {
+            output.push_str(&format!("export type {}Filter = never;\n\n", name));
+        } else {
+            output.push_str(&format!(
+                "export type {}Filter =\n{};\n\n",
+                name,
+                filters.join("\n")
+            ));
+        }
+        let text_fields = columns
+            .iter()
+            .filter(|(_, column_def)| {
+                matches!(
+                    column_def,
+                    ColumnDef::Text(_)
+                        | ColumnDef::Varchar(_)
+                        | ColumnDef::Char(_)
+                        | ColumnDef::CiText(_)
+                )
+            })
+            .map(|(column, _)| format!("{:?}", column))
+            .collect::<Vec<_>>();
+        let similarity = if text_fields.is_empty() {
+            String::new()
+        } else {
+            format!(" | `~${{{}}}`", union(&text_fields))
+        };
+        output.push_str(&format!(
+            "export type {name}Sort = {name}Field | `-${{{name}Field}}`{similarity};\n\n"
+        ));
+        output.push_str(&format!(
+            "export interface {name}FilterRequest {{\n  page?: number;\n  per_page?: number;\n  /** Comma-separated {name}Sort values, e.g. \"name,-age\"; a ~ entry must come first */\n  sort?: string;\n  filters?: {name}Filter[];\n  /** IANA time zone of date filters on timestamptz columns, e.g. \"Australia/Sydney\" */\n  time_zone?: string;\n  /** Free-text search across the searchable columns */\n  q?: string;\n}}\n"
+        ));
+        output
+    }
+
+    /// Writes the definitions to `path`, e.g. from a build script
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_typescript())
+            .map_err(|e| eyre::eyre!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// The operators of a column as the type of `f`, with the format and type of the value they
+    /// expect
+    fn operator_groups(&self, column_def: &ColumnDef) -> Vec<(String, ValueFormat, String)> {
+        let mut groups = column_def
+            .json_operator_groups(self.allow_relative_dates)
+            .into_iter()
+            .map(|(operators, format)| (quoted(&operators), format, value_type(column_def, format)))
+            .collect::<Vec<_>>();
+
+        for (function, arguments) in column_def.json_date_functions() {
+            // EXTRACT compares integers, DATE_TRUNC values of the column type
+            let value_def = match function {
+                "EXTRACT" => &ColumnDef::Integer(""),
+                _ => column_def,
+            };
+            for (operators, format) in DATE_FUNCTION_OPERATOR_GROUPS {
+                groups.push((
+                    format!(
+                        "`{}(${{{}}}) ${{{}}}`",
+                        function,
+                        quoted(&arguments),
+                        quoted(operators)
+                    ),
+                    format,
+                    value_type(value_def, format),
+                ));
+            }
+        }
+
+        groups
+    }
+}
+
+fn union(members: &[String]) -> String {
+    if members.is_empty() {
+        "never".to_string()
+    } else {
+        members.join(" | ")
+    }
+}
+
+/// A union of string literal types
+fn quoted<T: AsRef<str>>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| format!("{:?}", value.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The TypeScript type of a value in the given format
+fn value_type(column_def: &ColumnDef, format: ValueFormat) -> String {
+    const DATE: &str = "`${number}-${number}-${number}`";
+
+    let single = match column_def {
+        ColumnDef::SmallInt(_)
+        | ColumnDef::Integer(_)
+        | ColumnDef::BigInt(_)
+        | ColumnDef::Real(_)
+        | ColumnDef::DoublePrecision(_)
+        | ColumnDef::Numeric(_) => "number | `${number}`",
+        ColumnDef::Boolean(_) => "boolean | \"true\" | \"false\"",
+        ColumnDef::Date(_) => DATE,
+        _ => "string",
+    };
+
+    match format {
+        ValueFormat::Single => single.to_string(),
+        ValueFormat::List if single.contains(' ') => format!("({})[] | string", single),
+        ValueFormat::List => format!("{}[] | string", single),
+        ValueFormat::Range | ValueFormat::Pair => {
+            let text = match column_def {
+                ColumnDef::SmallInt(_)
+                | ColumnDef::Integer(_)
+                | ColumnDef::BigInt(_)
+                | ColumnDef::Real(_)
+                | ColumnDef::DoublePrecision(_)
+                | ColumnDef::Numeric(_) => "`${number},${number}`",
+                _ => "`${string},${string}`",
+            };
+            match format {
+                ValueFormat::Pair => format!("{text} | [{single}, {single}]"),
+                _ => format!(
+                    "{text} | [{single}, {single}] | {{ start: {single}; end: {single}; bounds?: \"[]\" | \"[)\" | \"(]\" | \"()\" }}"
+                ),
+            }
+        }
+        ValueFormat::Date => DATE.to_string(),
+        ValueFormat::DateRange => {
+            "`${string},${string}` | [string, string] | { start: string; end: string }".to_string()
+        }
+        ValueFormat::Ignored => "string | null".to_string(),
+        ValueFormat::RelativeDate => "string".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_typescript() {
+        let mut columns = HashMap::new();
+        columns.insert("active", ColumnDef::Boolean("active"));
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+        columns.insert("tags", ColumnDef::TextArray("tags"));
+
+        let expected = r#"// Generated by pg_filters. Do not edit.
+
+export type PeopleField = "active" | "created_at" | "tags";
+
+export type PeopleConnector = "AND" | "OR";
+
+export type PeopleFilter =
+  | { n: "active"; f: "=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM"; v: boolean | "true" | "false"; c?: PeopleConnector }
+  | { n: "active"; f: "IS NULL" | "IS NOT NULL"; v?: string | null; c?: PeopleConnector }
+  | { n: "created_at"; f: "=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM" | ">" | ">=" | "<" | "<="; v: string; c?: PeopleConnector }
+  | { n: "created_at"; f: "IN" | "NOT IN"; v: string[] | string; c?: PeopleConnector }
+  | { n: "created_at"; f: "BETWEEN" | "NOT BETWEEN" | "BETWEEN SYMMETRIC" | "NOT BETWEEN SYMMETRIC"; v: `${string},${string}` | [string, string] | { start: string; end: string; bounds?: "[]" | "[)" | "(]" | "()" }; c?: PeopleConnector }
+  | { n: "created_at"; f: "IS NULL" | "IS NOT NULL"; v?: string | null; c?: PeopleConnector }
+  | { n: "created_at"; f: "DATE_ONLY"; v: `${number}-${number}-${number}`; c?: PeopleConnector }
+  | { n: "created_at"; f: "DATE_RANGE"; v: `${string},${string}` | [string, string] | { start: string; end: string }; c?: PeopleConnector }
+  | { n: "created_at"; f: `EXTRACT(${"YEAR" | "QUARTER" | "MONTH" | "WEEK" | "DAY" | "DOW" | "ISODOW" | "DOY" | "HOUR" | "MINUTE" | "SECOND"}) ${"=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM" | ">" | ">=" | "<" | "<="}`; v: number | `${number}`; c?: PeopleConnector }
+  | { n: "created_at"; f: `EXTRACT(${"YEAR" | "QUARTER" | "MONTH" | "WEEK" | "DAY" | "DOW" | "ISODOW" | "DOY" | "HOUR" | "MINUTE" | "SECOND"}) ${"IN" | "NOT IN"}`; v: (number | `${number}`)[] | string; c?: PeopleConnector }
+  | { n: "created_at"; f: `EXTRACT(${"YEAR" | "QUARTER" | "MONTH" | "WEEK" | "DAY" | "DOW" | "ISODOW" | "DOY" | "HOUR" | "MINUTE" | "SECOND"}) ${"BETWEEN" | "NOT BETWEEN"}`; v: `${number},${number}` | [number | `${number}`, number | `${number}`]; c?: PeopleConnector }
+  | { n: "created_at"; f: `DATE_TRUNC(${"MINUTE" | "HOUR" | "DAY" | "WEEK" | "MONTH" | "QUARTER" | "YEAR"}) ${"=" | "!=" | "IS DISTINCT FROM" | "IS NOT DISTINCT FROM" | ">" | ">=" | "<" | "<="}`; v: string; c?: PeopleConnector }
+  | { n: "created_at"; f: `DATE_TRUNC(${"MINUTE" | "HOUR" | "DAY" | "WEEK" | "MONTH" | "QUARTER" | "YEAR"}) ${"IN" | "NOT IN"}`; v: string[] | string; c?: PeopleConnector }
+  | { n: "created_at"; f: `DATE_TRUNC(${"MINUTE" | "HOUR" | "DAY" | "WEEK" | "MONTH" | "QUARTER" | "YEAR"}) ${"BETWEEN" | "NOT BETWEEN"}`; v: `${string},${string}` | [string, string]; c?: PeopleConnector }
+  | { n: "tags"; f: "CONTAINS" | "OVERLAPS"; v: string[] | string; c?: PeopleConnector };
+
+export type PeopleSort = PeopleField | `-${PeopleField}`;
+
+export interface PeopleFilterRequest {
+  page?: number;
+  per_page?: number;
+  /** Comma-separated PeopleSort values, e.g. "name,-age"; a ~ entry must come first */
+  sort?: string;
+  filters?: PeopleFilter[];
+  /** IANA time zone of date filters on timestamptz columns, e.g. "Australia/Sydney" */
+  time_zone?: string;
+  /** Free-text search across the searchable columns */
+  q?: string;
+}
+"#;
+        assert_eq!(
+            TypeScriptTypes::new("People", columns).to_typescript(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        let mut columns = HashMap::new();
+        columns.insert("created_at", ColumnDef::Timestamp("created_at"));
+
+        let types = TypeScriptTypes::new("Events", columns);
+        assert!(!types.to_typescript().contains("RELATIVE"));
+        assert!(types
+            .allow_relative_dates(true)
+            .to_typescript()
+            .contains(r#"  | { n: "created_at"; f: "RELATIVE"; v: string; c?: EventsConnector }"#));
+    }
+
+    #[test]
+    fn test_relations() {
+        let mut item_columns = HashMap::new();
+        item_columns.insert("quantity", ColumnDef::Integer("quantity"));
+        let definitions = TypeScriptTypes::new("Orders", HashMap::new())
+            .relation(
+                "items",
+                Relation::new("order_items", item_columns).key("order_id", "orders.id"),
+            )
+            .to_typescript();
+
+        assert!(definitions.contains(
+            r#"  | { n: "items"; f: "EXISTS" | "NOT EXISTS"; v?: string | null; c?: OrdersConnector }"#
+        ));
+        assert!(definitions.contains(
+            r#"  | { n: "items"; f: `COUNT ${"=" | "!=" | ">" | ">=" | "<" | "<="}`; v: number | `${number}`; c?: OrdersConnector }"#
+        ));
+        assert!(definitions.contains(
+            r#"  | { n: "items.quantity"; f: "IN" | "NOT IN" | `${"ANY" | "ALL" | "NONE"} ${"IN" | "NOT IN"}`; v: (number | `${number}`)[] | string; c?: OrdersConnector }"#
+        ));
+        assert!(definitions.contains("export type OrdersField = never;"));
+    }
+
+    #[test]
+    fn test_empty_column_map() {
+        let definitions = TypeScriptTypes::new("Empty", HashMap::new()).to_typescript();
+        assert!(definitions.contains("export type EmptyField = never;"));
+        assert!(definitions.contains("export type EmptyFilter = never;"));
+    }
+}
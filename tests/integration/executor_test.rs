@@ -0,0 +1,146 @@
+use crate::integration::run_with_container;
+use pg_filters::{
+    executor::fetch_page,
+    filtering::{FilterCondition, FilterExpression, FilterOperator},
+    sorting::{SortOrder, SortedColumn},
+    ColumnDef, FilteringOptions, PaginationOptions, PgFilters,
+};
+use std::collections::HashMap;
+
+fn setup_test_columns() -> HashMap<&'static str, ColumnDef> {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::Text("name"));
+    columns.insert("age", ColumnDef::Integer("age"));
+    columns.insert("registration", ColumnDef::Timestamp("registration"));
+    columns.insert("uuid", ColumnDef::Uuid("uuid"));
+    columns
+}
+
+#[tokio::test]
+async fn test_fetch_page() {
+    run_with_container(|pool| async move {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(
+            Some(PaginationOptions::new(5, 3, 10, 0)),
+            vec![SortedColumn {
+                column: "age".to_string(),
+                order: SortOrder::Asc,
+            }],
+            Some(FilteringOptions::new(
+                vec![
+                    FilterExpression::Condition(FilterCondition::IntegerValue {
+                        column: "age".to_string(),
+                        operator: FilterOperator::GreaterThanOrEqual,
+                        value: Some(10),
+                    }),
+                    FilterExpression::Condition(FilterCondition::TimestampValue {
+                        column: "registration".to_string(),
+                        operator: FilterOperator::LessThan,
+                        value: Some("2023-10-19 00:00:00".to_string()),
+                    }),
+                    FilterExpression::Condition(FilterCondition::TextValue {
+                        column: "name".to_string(),
+                        operator: FilterOperator::Like,
+                        value: Some("NAME%".to_string()),
+                    }),
+                ],
+                columns.clone(),
+            )),
+            columns,
+        )
+        .unwrap();
+
+        let mut client = pool.get().await.unwrap();
+        let page = fetch_page(&mut client, "SELECT * FROM person", &[], &filters, 10)
+            .await
+            .unwrap();
+
+        let ages: Vec<i32> = page.rows.iter().map(|row| row.get("age")).collect();
+
+        // Ages 10 to 17 match, so page 5 of 3 per page is clamped to the last page
+        assert_eq!(ages, vec![16, 17]);
+        assert_eq!(page.pagination.total_records, 8);
+        assert_eq!(page.pagination.total_pages, 3);
+        assert_eq!(page.pagination.current_page, 3);
+        assert_eq!(page.pagination.previous_page, 2);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_fetch_page_with_base_query_params() {
+    run_with_container(|pool| async move {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(
+            None,
+            vec![],
+            Some(FilteringOptions::new(
+                vec![FilterExpression::Condition(FilterCondition::InValues {
+                    column: "uuid".to_string(),
+                    operator: FilterOperator::In,
+                    values: vec![
+                        "550e8400-e29b-41d4-a716-446655440001".to_string(),
+                        "550e8400-e29b-41d4-a716-446655440003".to_string(),
+                    ],
+                })],
+                columns.clone(),
+            )),
+            columns,
+        )
+        .unwrap();
+
+        let mut client = pool.get().await.unwrap();
+        let page = fetch_page(
+            &mut client,
+            "SELECT * FROM (SELECT * FROM person WHERE active = $1) AS p",
+            &[&false],
+            &filters,
+            10,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(page.rows.len(), 4);
+        assert_eq!(page.pagination.total_records, 4);
+        assert_eq!(page.pagination.total_pages, 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_fetch_page_without_pagination() {
+    run_with_container(|pool| async move {
+        let columns = setup_test_columns();
+        let filters = PgFilters::new(
+            None,
+            vec![SortedColumn {
+                column: "age".to_string(),
+                order: SortOrder::Asc,
+            }],
+            Some(FilteringOptions::new(
+                vec![FilterExpression::Condition(FilterCondition::IntegerValue {
+                    column: "age".to_string(),
+                    operator: FilterOperator::GreaterThanOrEqual,
+                    value: Some(10),
+                })],
+                columns.clone(),
+            )),
+            columns,
+        )
+        .unwrap();
+
+        let mut client = pool.get().await.unwrap();
+        let page = fetch_page(&mut client, "SELECT * FROM person", &[], &filters, 3)
+            .await
+            .unwrap();
+
+        // The first page of `per_page_limit` rows
+        let ages: Vec<i32> = page.rows.iter().map(|row| row.get("age")).collect();
+        assert_eq!(ages, vec![10, 11, 12]);
+        assert_eq!(page.pagination.current_page, 1);
+        assert_eq!(page.pagination.per_page, 3);
+        assert_eq!(page.pagination.total_records, 8);
+        assert_eq!(page.pagination.total_pages, 3);
+    })
+    .await;
+}
@@ -0,0 +1,59 @@
+use crate::integration::run_with_container;
+use pg_filters::introspection::{introspect_table, UnsupportedColumn};
+use pg_filters::ColumnDef;
+
+#[tokio::test]
+async fn test_introspect_table() {
+    run_with_container(|pool| async move {
+        let client = pool.get().await.unwrap();
+        client
+            .execute("ALTER TABLE person ADD COLUMN scores INTEGER[]", &[])
+            .await
+            .unwrap();
+
+        let table = introspect_table(&**client, "public", "person")
+            .await
+            .unwrap();
+
+        assert_eq!(table.column_defs.len(), 8);
+        assert!(matches!(
+            table.column_defs.get("id"),
+            Some(ColumnDef::Integer("id"))
+        ));
+        assert!(matches!(
+            table.column_defs.get("nickname"),
+            Some(ColumnDef::Varchar("nickname"))
+        ));
+        assert!(matches!(
+            table.column_defs.get("capacity"),
+            Some(ColumnDef::DoublePrecision("capacity"))
+        ));
+        assert!(matches!(
+            table.column_defs.get("registration"),
+            Some(ColumnDef::Timestamp("registration"))
+        ));
+        assert!(matches!(
+            table.column_defs.get("uuid"),
+            Some(ColumnDef::Uuid("uuid"))
+        ));
+        assert_eq!(
+            table.unsupported,
+            vec![UnsupportedColumn {
+                column: "scores".to_string(),
+                udt_name: "_int4".to_string(),
+            }]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_introspect_missing_table() {
+    run_with_container(|pool| async move {
+        let client = pool.get().await.unwrap();
+        let result = introspect_table(&**client, "public", "missing").await;
+
+        assert!(result.is_err());
+    })
+    .await;
+}
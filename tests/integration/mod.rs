@@ -9,7 +9,11 @@ use testcontainers_modules::testcontainers::runners::AsyncRunner;
 use tokio_postgres::{Config, NoTls};
 use uuid::Uuid;
 
+#[cfg(feature = "tokio-postgres")]
+pub mod executor_test;
 pub mod integration_test;
+#[cfg(feature = "introspection")]
+pub mod introspection_test;
 
 const DB_POOL_MAX_OPEN: u64 = 10;
 const DB_POOL_TIMEOUT_SECONDS: u64 = 10;
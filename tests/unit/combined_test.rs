@@ -1,6 +1,8 @@
 use eyre::Result;
 use pg_filters::{
-    filtering::{FilterCondition, FilterExpression, FilterOperator, LogicalOperator},
+    filtering::{
+        FilterCondition, FilterExpression, FilterOperator, LogicalOperator, QueryParams, SqlParam,
+    },
     sorting::{SortOrder, SortedColumn},
     ColumnDef, FilteringOptions, PaginationOptions, PgFilters,
 };
@@ -375,3 +377,48 @@ fn test_complex_filtering_with_and_or_conditions() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_filtering_with_params() -> Result<()> {
+    let columns = setup_test_columns();
+    let filters = PgFilters::new(
+        Some(PaginationOptions {
+            current_page: 1,
+            per_page: 10,
+            per_page_limit: 10,
+            total_records: 1000,
+        }),
+        vec![SortedColumn {
+            column: "name".to_string(),
+            order: SortOrder::Asc,
+        }],
+        Some(FilteringOptions::new(
+            vec![
+                FilterExpression::Condition(FilterCondition::TextValue {
+                    column: "name".to_string(),
+                    operator: FilterOperator::Equal,
+                    value: Some("O'Brien".to_string()),
+                }),
+                FilterExpression::Condition(FilterCondition::IntegerValue {
+                    column: "age".to_string(),
+                    operator: FilterOperator::GreaterThan,
+                    value: Some(18),
+                }),
+            ],
+            columns.clone(),
+        )),
+        columns,
+    )?;
+
+    let mut params = QueryParams::starting_after(1);
+    let sql = filters.sql_with_params(&mut params)?;
+    assert_eq!(
+        sql,
+        " WHERE (LOWER(name) = LOWER($2) AND age > $3) ORDER BY name ASC LIMIT 10 OFFSET 0"
+    );
+    assert_eq!(
+        params.values,
+        vec![SqlParam::Text("O'Brien".to_string()), SqlParam::Integer(18)]
+    );
+    Ok(())
+}
@@ -260,6 +260,20 @@ fn test_filtering_with_not_like_rule() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_filtering_with_ilike_rule() -> Result<()> {
+    let sql = FilterBuilder::new()
+        .add_condition(FilterCondition::TextValue {
+            column: "name".to_string(),
+            operator: FilterOperator::ILike,
+            value: Some("jo%".to_string()),
+        })
+        .build()?;
+
+    assert_eq!(sql, " WHERE name ILIKE 'jo%'");
+    Ok(())
+}
+
 #[test]
 fn test_filtering_with_is_null() -> Result<()> {
     let sql = FilterBuilder::new()